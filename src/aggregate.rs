@@ -0,0 +1,684 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::{InfluxLine, InfluxLineError, InfluxValue, KeyName, MeasurementName, Timestamp};
+
+/// Windows and statistics of an [`Aggregator`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use influx_line::*;
+///
+/// let config = AggregatorConfig::sliding(Duration::from_secs(60), Duration::from_secs(10))
+///     .with_aggregates([Aggregate::Mean, Aggregate::Max, Aggregate::Percentile(99.0)])
+///     .with_suffix(Aggregate::Mean, "_avg")
+///     .with_allowed_lateness(Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AggregatorConfig {
+    size: Duration,
+    step: Duration,
+    allowed_lateness: Duration,
+    aggregates: Vec<(Aggregate, String)>,
+}
+
+/// A statistic computed over the values of a numeric field within a window.
+///
+/// Every statistic is a float, except for [`Self::Count`], which is an integer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Mean,
+    Min,
+    Max,
+    Sum,
+    Count,
+    /// Value with the earliest timestamp.
+    First,
+    /// Value with the latest timestamp.
+    Last,
+    /// Sample standard deviation, which needs at least two values.
+    Stddev,
+    /// Percentile between 0 and 100, interpolated linearly between the closest values.
+    Percentile(f64),
+}
+
+/// Groups Lines by series and time windows,
+/// and turns every window into a Line of statistics over its numeric fields.
+///
+/// Windows are aligned to the Unix epoch. Tumbling windows follow each other,
+/// while sliding windows overlap, so a Line falls into `size / step` of them.
+///
+/// Windows are closed by a watermark, which trails the latest timestamp seen
+/// by [`AggregatorConfig::with_allowed_lateness`].
+/// A closed window is emitted right away, and Lines arriving for it later are dropped.
+/// An aggregated Line keeps the measurement and tags of its series,
+/// has a field per statistic of every numeric field, e.g. `usage_mean`, ordered by field key,
+/// and is timestamped with the exclusive end of its window,
+/// as Flux `aggregateWindow` does by default.
+///
+/// Values of a field are kept in full only if percentiles are asked for.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::str::FromStr;
+/// use std::time::Duration;
+/// use influx_line::*;
+///
+/// let config = AggregatorConfig::tumbling(Duration::from_secs(10))
+///     .with_aggregates([Aggregate::Mean, Aggregate::Count]);
+/// let mut aggregator = Aggregator::new(config).unwrap();
+///
+/// let mut emitted = Vec::new();
+/// for line in ["cpu,host=a usage=1 1000000000", "cpu,host=a usage=3 2000000000"] {
+///     emitted.extend(aggregator.add(&InfluxLine::from_str(line).unwrap()).unwrap());
+/// }
+/// // Closes the first window.
+/// emitted.extend(aggregator.add(&InfluxLine::from_str("cpu,host=a usage=5 10000000000").unwrap()).unwrap());
+///
+/// assert_eq!(1, emitted.len());
+/// assert_eq!("cpu,host=a usage_mean=2.0,usage_count=2i 10000000000", emitted[0].to_string());
+///
+/// let rest = aggregator.flush();
+/// assert_eq!("cpu,host=a usage_mean=5.0,usage_count=1i 20000000000", rest[0].to_string());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Aggregator {
+    config: AggregatorConfig,
+    size: i64,
+    step: i64,
+    lateness: i64,
+    /// Open windows by their start.
+    windows: BTreeMap<i64, BTreeMap<SeriesKey, BTreeMap<KeyName, FieldStats>>>,
+    watermark: Option<i64>,
+    late: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SeriesKey {
+    measurement: MeasurementName,
+    tags: Vec<(KeyName, KeyName)>,
+}
+
+#[derive(Debug, Clone)]
+struct FieldStats {
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+    /// Running mean and sum of squared differences from it, as in Welford's algorithm.
+    mean: f64,
+    squares: f64,
+    first: (i64, f64),
+    last: (i64, f64),
+    values: Option<Vec<f64>>,
+}
+
+impl Aggregate {
+    /// Returns the suffix appended to field keys unless overridden,
+    /// e.g. `_mean`, or `_p99_9` for the 99.9th percentile.
+    pub fn default_suffix(&self) -> String {
+        match self {
+            Aggregate::Mean => "_mean".to_owned(),
+            Aggregate::Min => "_min".to_owned(),
+            Aggregate::Max => "_max".to_owned(),
+            Aggregate::Sum => "_sum".to_owned(),
+            Aggregate::Count => "_count".to_owned(),
+            Aggregate::First => "_first".to_owned(),
+            Aggregate::Last => "_last".to_owned(),
+            Aggregate::Stddev => "_stddev".to_owned(),
+            Aggregate::Percentile(percentile) => {
+                format!("_p{}", percentile.to_string().replace('.', "_"))
+            }
+        }
+    }
+}
+
+impl AggregatorConfig {
+    /// Statistics computed unless set otherwise.
+    pub const DEFAULT_AGGREGATES: [Aggregate; 4] = [
+        Aggregate::Mean,
+        Aggregate::Min,
+        Aggregate::Max,
+        Aggregate::Count,
+    ];
+
+    /// Creates a config of consecutive windows of the given size,
+    /// with [`Self::DEFAULT_AGGREGATES`] and no allowed lateness.
+    pub fn tumbling(size: Duration) -> Self {
+        Self::sliding(size, size)
+    }
+
+    /// Creates a config of overlapping windows of the given size that start every step,
+    /// with [`Self::DEFAULT_AGGREGATES`] and no allowed lateness.
+    pub fn sliding(size: Duration, step: Duration) -> Self {
+        Self {
+            size,
+            step,
+            allowed_lateness: Duration::ZERO,
+            aggregates: Vec::new(),
+        }
+        .with_aggregates(Self::DEFAULT_AGGREGATES)
+    }
+
+    /// Sets how far behind the latest timestamp Lines may still arrive.
+    pub fn with_allowed_lateness(mut self, allowed_lateness: Duration) -> Self {
+        self.allowed_lateness = allowed_lateness;
+        self
+    }
+
+    /// Sets the statistics to compute, with their default suffixes.
+    pub fn with_aggregates(mut self, aggregates: impl IntoIterator<Item = Aggregate>) -> Self {
+        self.aggregates = aggregates
+            .into_iter()
+            .map(|aggregate| (aggregate, aggregate.default_suffix()))
+            .collect();
+        self
+    }
+
+    /// Overrides the suffix of a statistic that is computed.
+    pub fn with_suffix(mut self, aggregate: Aggregate, suffix: &str) -> Self {
+        for (existing, existing_suffix) in self.aggregates.iter_mut() {
+            if *existing == aggregate {
+                *existing_suffix = suffix.to_owned();
+            }
+        }
+        self
+    }
+}
+
+impl Aggregator {
+    /// The most windows a single Line may fall into, i.e. the largest `size / step`.
+    pub const MAX_OVERLAP: i64 = 1024;
+
+    /// Creates an aggregator without any open windows.
+    ///
+    /// Fails with [`InfluxLineError::BadWindow`] if the size or the step is zero,
+    /// if the step exceeds the size, if the size is more than [`Self::MAX_OVERLAP`] steps,
+    /// or if either does not fit into nanoseconds of `i64`.
+    /// Fails with [`InfluxLineError::BadSuffix`] if two statistics share a suffix,
+    /// or if a suffix does not make a valid field key.
+    pub fn new(config: AggregatorConfig) -> Result<Self, InfluxLineError> {
+        let nanoseconds = |duration: Duration| {
+            i64::try_from(duration.as_nanos()).map_err(|_| InfluxLineError::BadWindow)
+        };
+        let size = nanoseconds(config.size)?;
+        let step = nanoseconds(config.step)?;
+        let lateness = nanoseconds(config.allowed_lateness)?;
+        if size <= 0 || step <= 0 || step > size || (size - 1) / step >= Self::MAX_OVERLAP {
+            return Err(InfluxLineError::BadWindow);
+        }
+
+        for (index, (_, suffix)) in config.aggregates.iter().enumerate() {
+            let repeated = config.aggregates[..index]
+                .iter()
+                .any(|(_, previous)| previous == suffix);
+            if repeated || KeyName::new(format!("field{}", suffix)).is_err() {
+                return Err(InfluxLineError::BadSuffix);
+            }
+        }
+
+        Ok(Self {
+            config,
+            size,
+            step,
+            lateness,
+            windows: BTreeMap::new(),
+            watermark: None,
+            late: 0,
+        })
+    }
+
+    /// Adds the Line to every open window it falls into,
+    /// and returns the Lines of windows closed by the watermark it advances.
+    ///
+    /// Fails with [`InfluxLineError::NoTimestamp`] if the Line has no timestamp.
+    /// Lines behind the watermark for all their windows are dropped and counted as late.
+    /// Windows starting or ending beyond the range of [`Timestamp`] are skipped,
+    /// so Lines at its very ends may fall into none.
+    pub fn add(&mut self, line: &InfluxLine) -> Result<Vec<InfluxLine>, InfluxLineError> {
+        let timestamp = i64::from(line.timestamp().ok_or(InfluxLineError::NoTimestamp)?);
+
+        let mut key = SeriesKey {
+            measurement: line.measurement().clone(),
+            tags: line
+                .tags()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        };
+        key.tags.sort();
+
+        let keep_values = self.keeps_values();
+        let mut windows = 0;
+        let mut added = false;
+        for start in self.window_starts(timestamp) {
+            windows += 1;
+            if self.is_closed(start) {
+                continue;
+            }
+            added = true;
+
+            let fields = self
+                .windows
+                .entry(start)
+                .or_default()
+                .entry(key.clone())
+                .or_default();
+            for (field, value) in line.fields() {
+                let value = match value {
+                    InfluxValue::Float(float) => *float,
+                    InfluxValue::Integer(integer) => i64::from(*integer) as f64,
+                    InfluxValue::UInteger(uinteger) => u64::from(*uinteger) as f64,
+                    InfluxValue::Boolean(_) | InfluxValue::String(_) => continue,
+                };
+                fields
+                    .entry(field.clone())
+                    .or_insert_with(|| FieldStats::new(keep_values))
+                    .add(timestamp, value);
+            }
+        }
+        if windows > 0 && !added {
+            self.late += 1;
+        }
+
+        let watermark = timestamp.saturating_sub(self.lateness);
+        Ok(self.close(watermark))
+    }
+
+    /// Moves the watermark forward, e.g. by a wall clock while the stream is idle,
+    /// and returns the Lines of windows it closes.
+    ///
+    /// A watermark behind the current one is ignored.
+    pub fn advance_watermark(&mut self, watermark: Timestamp) -> Vec<InfluxLine> {
+        self.close(i64::from(watermark))
+    }
+
+    /// Closes every open window regardless of the watermark, e.g. at the end of a stream.
+    pub fn flush(&mut self) -> Vec<InfluxLine> {
+        let windows = std::mem::take(&mut self.windows);
+        windows
+            .into_iter()
+            .flat_map(|(start, series)| self.emit(start, series))
+            .collect()
+    }
+
+    /// Returns the current watermark, unless no Lines have been seen yet.
+    pub fn watermark(&self) -> Option<Timestamp> {
+        self.watermark.map(Timestamp::from)
+    }
+
+    /// Returns the number of Lines dropped for arriving after their windows closed.
+    pub fn late(&self) -> u64 {
+        self.late
+    }
+
+    /// Returns the starts of windows containing the timestamp, in ascending order.
+    ///
+    /// Windows whose start or end does not fit into `i64` are left out.
+    fn window_starts(&self, timestamp: i64) -> impl Iterator<Item = i64> + use<> {
+        let last = timestamp.checked_sub(timestamp.rem_euclid(self.step));
+        let (size, step) = (self.size, self.step);
+        // The overlap is limited, so `index * step` never exceeds the size.
+        let count = (size - 1) / step + 1;
+        (0..count)
+            .rev()
+            .filter_map(move |index| last?.checked_sub(index * step))
+            .filter(move |start| start.checked_add(size).is_some_and(|end| timestamp < end))
+    }
+
+    fn is_closed(&self, start: i64) -> bool {
+        self.watermark
+            .is_some_and(|watermark| start.saturating_add(self.size) <= watermark)
+    }
+
+    fn keeps_values(&self) -> bool {
+        self.config
+            .aggregates
+            .iter()
+            .any(|(aggregate, _)| matches!(aggregate, Aggregate::Percentile(_)))
+    }
+
+    fn close(&mut self, watermark: i64) -> Vec<InfluxLine> {
+        if self.watermark.is_some_and(|current| watermark <= current) {
+            return Vec::new();
+        }
+        self.watermark = Some(watermark);
+
+        let mut emitted = Vec::new();
+        while let Some(entry) = self.windows.first_entry() {
+            if entry.key().saturating_add(self.size) > watermark {
+                break;
+            }
+            let (start, series) = entry.remove_entry();
+            emitted.extend(self.emit(start, series));
+        }
+        emitted
+    }
+
+    fn emit(
+        &self,
+        start: i64,
+        series: BTreeMap<SeriesKey, BTreeMap<KeyName, FieldStats>>,
+    ) -> Vec<InfluxLine> {
+        let end = start.saturating_add(self.size);
+        series
+            .into_iter()
+            .filter_map(|(key, fields)| {
+                let fields = fields.into_iter().flat_map(|(field, mut stats)| {
+                    let aggregates: Vec<_> = self
+                        .config
+                        .aggregates
+                        .iter()
+                        .filter_map(|(aggregate, suffix)| {
+                            let value = stats.compute(*aggregate)?;
+                            let key = KeyName::new(format!("{}{}", field.as_ref(), suffix))
+                                .expect("Suffixes are checked by Aggregator::new");
+                            Some((key, value))
+                        })
+                        .collect();
+                    aggregates
+                });
+                // Series with only non-numeric fields produce nothing.
+                InfluxLine::full(key.measurement, key.tags, fields, Some(end)).ok()
+            })
+            .collect()
+    }
+}
+
+impl FieldStats {
+    fn new(keep_values: bool) -> Self {
+        Self {
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            squares: 0.0,
+            first: (i64::MAX, 0.0),
+            last: (i64::MIN, 0.0),
+            values: keep_values.then(Vec::new),
+        }
+    }
+
+    fn add(&mut self, timestamp: i64, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.squares += delta * (value - self.mean);
+
+        // Ties keep the earliest added value as first, and the latest added as last.
+        if timestamp < self.first.0 {
+            self.first = (timestamp, value);
+        }
+        if timestamp >= self.last.0 {
+            self.last = (timestamp, value);
+        }
+        if let Some(values) = self.values.as_mut() {
+            values.push(value);
+        }
+    }
+
+    fn compute(&mut self, aggregate: Aggregate) -> Option<InfluxValue> {
+        let value = match aggregate {
+            Aggregate::Mean => self.mean,
+            Aggregate::Min => self.min,
+            Aggregate::Max => self.max,
+            Aggregate::Sum => self.sum,
+            Aggregate::Count => {
+                return Some(InfluxValue::from(self.count.min(i64::MAX as u64) as i64));
+            }
+            Aggregate::First => self.first.1,
+            Aggregate::Last => self.last.1,
+            Aggregate::Stddev if self.count < 2 => return None,
+            Aggregate::Stddev => (self.squares / (self.count - 1) as f64).sqrt(),
+            Aggregate::Percentile(percentile) => {
+                let values = self.values.as_mut()?;
+                values.sort_by(f64::total_cmp);
+                let rank = percentile.clamp(0.0, 100.0) / 100.0 * (values.len() - 1) as f64;
+                let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+                values[lower] + (values[upper] - values[lower]) * (rank - lower as f64)
+            }
+        };
+        Some(InfluxValue::from(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use crate::{Aggregate, Aggregator, AggregatorConfig, InfluxLine, InfluxLineError, Timestamp};
+
+    const SECOND: i64 = 1_000_000_000;
+
+    fn line(host: &str, usage: f64, second: i64) -> InfluxLine {
+        InfluxLine::try_new("cpu", "usage", usage)
+            .and_then(|line| line.try_with_tag("host", host))
+            .unwrap()
+            .with_timestamp(second * SECOND)
+    }
+
+    fn feed(aggregator: &mut Aggregator, lines: &[InfluxLine]) -> Vec<String> {
+        let mut emitted = Vec::new();
+        for line in lines {
+            emitted.extend(aggregator.add(line).unwrap());
+        }
+        emitted.extend(aggregator.flush());
+        emitted.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[rstest::rstest]
+    #[case::mean(Aggregate::Mean, "usage_mean=2.5")]
+    #[case::min(Aggregate::Min, "usage_min=1.0")]
+    #[case::max(Aggregate::Max, "usage_max=4.0")]
+    #[case::sum(Aggregate::Sum, "usage_sum=10.0")]
+    #[case::count(Aggregate::Count, "usage_count=4i")]
+    #[case::first(Aggregate::First, "usage_first=3.0")]
+    #[case::last(Aggregate::Last, "usage_last=2.0")]
+    #[case::stddev(Aggregate::Stddev, "usage_stddev=1.2909944487358056")]
+    #[case::median(Aggregate::Percentile(50.0), "usage_p50=2.5")]
+    #[case::p90(Aggregate::Percentile(90.0), "usage_p90=3.7")]
+    #[case::p12_5(Aggregate::Percentile(12.5), "usage_p12_5=1.375")]
+    fn aggregates(#[case] aggregate: Aggregate, #[case] expected: &str) {
+        let config =
+            AggregatorConfig::tumbling(Duration::from_secs(10)).with_aggregates([aggregate]);
+        let mut aggregator = Aggregator::new(config).unwrap();
+
+        // Out of order within the window, so first and last follow timestamps.
+        let emitted = feed(
+            &mut aggregator,
+            &[
+                line("a", 1.0, 2),
+                line("a", 3.0, 0),
+                line("a", 4.0, 5),
+                line("a", 2.0, 9),
+            ],
+        );
+
+        assert_eq!(
+            vec![format!("cpu,host=a {} {}", expected, 10 * SECOND)],
+            emitted
+        );
+    }
+
+    #[test]
+    fn groups_by_series_and_window() {
+        let config = AggregatorConfig::tumbling(Duration::from_secs(10))
+            .with_aggregates([Aggregate::Count])
+            .with_suffix(Aggregate::Count, "_n");
+        let mut aggregator = Aggregator::new(config).unwrap();
+        let reordered = InfluxLine::from_str("cpu,zone=x,host=a usage=1 3000000000").unwrap();
+        let sorted = InfluxLine::from_str("cpu,host=a,zone=x usage=1 4000000000").unwrap();
+
+        let emitted = feed(
+            &mut aggregator,
+            &[
+                line("a", 1.0, 1),
+                line("b", 1.0, 2),
+                reordered,
+                sorted,
+                line("a", 1.0, 12),
+            ],
+        );
+
+        assert_eq!(
+            vec![
+                "cpu,host=a usage_n=1i 10000000000",
+                "cpu,host=a,zone=x usage_n=2i 10000000000",
+                "cpu,host=b usage_n=1i 10000000000",
+                "cpu,host=a usage_n=1i 20000000000",
+            ],
+            emitted
+        );
+    }
+
+    #[test]
+    fn sliding_windows_overlap() {
+        let config = AggregatorConfig::sliding(Duration::from_secs(10), Duration::from_secs(5))
+            .with_aggregates([Aggregate::Sum]);
+        let mut aggregator = Aggregator::new(config).unwrap();
+
+        let emitted = feed(&mut aggregator, &[line("a", 1.0, 3), line("a", 2.0, 7)]);
+
+        assert_eq!(
+            vec![
+                "cpu,host=a usage_sum=1.0 5000000000",
+                "cpu,host=a usage_sum=3.0 10000000000",
+                "cpu,host=a usage_sum=2.0 15000000000",
+            ],
+            emitted
+        );
+    }
+
+    #[test]
+    fn lateness_keeps_windows_open() {
+        let config = AggregatorConfig::tumbling(Duration::from_secs(10))
+            .with_aggregates([Aggregate::Count])
+            .with_allowed_lateness(Duration::from_secs(5));
+        let mut aggregator = Aggregator::new(config).unwrap();
+
+        let open = aggregator.add(&line("a", 1.0, 1)).unwrap();
+        let still_open = aggregator.add(&line("a", 1.0, 12)).unwrap();
+        let in_time = aggregator.add(&line("a", 1.0, 8)).unwrap();
+        let closing = aggregator.add(&line("a", 1.0, 16)).unwrap();
+        let late = aggregator.add(&line("a", 1.0, 9)).unwrap();
+
+        assert!(open.is_empty() && still_open.is_empty() && in_time.is_empty() && late.is_empty());
+        assert_eq!(
+            "cpu,host=a usage_count=2i 10000000000",
+            closing[0].to_string()
+        );
+        assert_eq!(1, aggregator.late());
+        assert_eq!(Some(Timestamp::from(11 * SECOND)), aggregator.watermark());
+    }
+
+    #[test]
+    fn external_watermark_closes_idle_windows() {
+        let mut aggregator =
+            Aggregator::new(AggregatorConfig::tumbling(Duration::from_secs(10))).unwrap();
+        aggregator.add(&line("a", 1.0, 1)).unwrap();
+
+        let early = aggregator.advance_watermark(Timestamp::from(9 * SECOND));
+        let closed = aggregator.advance_watermark(Timestamp::from(10 * SECOND));
+        let behind = aggregator.advance_watermark(Timestamp::from(0));
+
+        assert!(early.is_empty());
+        assert_eq!(
+            "cpu,host=a usage_mean=1.0,usage_min=1.0,usage_max=1.0,usage_count=1i 10000000000",
+            closed[0].to_string()
+        );
+        assert!(behind.is_empty());
+        assert_eq!(Some(Timestamp::from(10 * SECOND)), aggregator.watermark());
+    }
+
+    #[test]
+    fn skips_non_numeric_fields() {
+        let mut aggregator = Aggregator::new(
+            AggregatorConfig::tumbling(Duration::from_secs(10)).with_aggregates([Aggregate::Max]),
+        )
+        .unwrap();
+        let mixed = InfluxLine::from_str(r#"cpu v=2i,u=3u,ok=true,s="x" 1"#).unwrap();
+        let text = InfluxLine::from_str(r#"log message="x" 1"#).unwrap();
+
+        let emitted = feed(&mut aggregator, &[mixed, text]);
+
+        assert_eq!(vec!["cpu u_max=3.0,v_max=2.0 10000000000"], emitted);
+    }
+
+    #[rstest::rstest]
+    #[case::zero_size(Duration::ZERO, Duration::ZERO)]
+    #[case::zero_step(Duration::from_secs(1), Duration::ZERO)]
+    #[case::gaps(Duration::from_secs(1), Duration::from_secs(2))]
+    fn bad_windows(#[case] size: Duration, #[case] step: Duration) {
+        assert!(matches!(
+            Aggregator::new(AggregatorConfig::sliding(size, step)),
+            Err(InfluxLineError::BadWindow)
+        ));
+    }
+
+    #[rstest::rstest]
+    #[case::too_many_windows(Duration::from_secs(3600), Duration::from_nanos(1))]
+    #[case::just_too_many(Duration::from_secs(1025), Duration::from_secs(1))]
+    fn too_much_overlap(#[case] size: Duration, #[case] step: Duration) {
+        assert!(matches!(
+            Aggregator::new(AggregatorConfig::sliding(size, step)),
+            Err(InfluxLineError::BadWindow)
+        ));
+        let most = AggregatorConfig::sliding(Duration::from_secs(1024), Duration::from_secs(1));
+        assert!(Aggregator::new(most).is_ok());
+    }
+
+    #[rstest::rstest]
+    #[case::repeated(
+        AggregatorConfig::tumbling(Duration::from_secs(1))
+            .with_aggregates([Aggregate::Mean, Aggregate::Max])
+            .with_suffix(Aggregate::Max, "_mean")
+    )]
+    #[case::empty_twice(
+        AggregatorConfig::tumbling(Duration::from_secs(1))
+            .with_aggregates([Aggregate::Min, Aggregate::Max])
+            .with_suffix(Aggregate::Min, "")
+            .with_suffix(Aggregate::Max, "")
+    )]
+    fn bad_suffixes(#[case] config: AggregatorConfig) {
+        assert!(matches!(
+            Aggregator::new(config),
+            Err(InfluxLineError::BadSuffix)
+        ));
+    }
+
+    #[rstest::rstest]
+    #[case::max("cpu v=1 9223372036854775806")]
+    #[case::min("cpu v=1 -9223372036854775807")]
+    #[case::min_exactly("cpu v=1 -9223372036854775808")]
+    fn skips_windows_beyond_timestamps(#[case] input: &str) {
+        let config = AggregatorConfig::sliding(Duration::from_secs(10), Duration::from_secs(5));
+        let mut aggregator = Aggregator::new(config).unwrap();
+
+        let emitted = aggregator
+            .add(&InfluxLine::from_str(input).unwrap())
+            .unwrap();
+
+        assert!(emitted.is_empty());
+        assert!(aggregator.flush().is_empty());
+        assert_eq!(0, aggregator.late());
+    }
+
+    #[test]
+    fn requires_timestamps() {
+        let mut aggregator =
+            Aggregator::new(AggregatorConfig::tumbling(Duration::from_secs(1))).unwrap();
+
+        assert!(matches!(
+            aggregator.add(&InfluxLine::try_new("cpu", "v", 1).unwrap()),
+            Err(InfluxLineError::NoTimestamp)
+        ));
+    }
+}
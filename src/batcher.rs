@@ -0,0 +1,488 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::mem;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::{InfluxLine, InfluxLineError, StampMode, Stamper, Timestamp};
+
+/// Limits of a single payload produced by [`LineBatcher`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use influx_line::*;
+///
+/// let config = BatchConfig::new()
+///     .with_max_lines(1_000)
+///     .with_max_bytes(512 * 1024)
+///     .with_interval(Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    max_lines: usize,
+    max_bytes: usize,
+    interval: Duration,
+    max_pending: usize,
+    stamper: Option<Stamper>,
+}
+
+/// Collects Lines from many threads into newline-terminated payloads
+/// and hands them to a flush function on a background thread.
+///
+/// A payload is sealed as soon as the next Line would exceed
+/// [`BatchConfig::with_max_lines`] or [`BatchConfig::with_max_bytes`],
+/// or once [`BatchConfig::with_interval`] has passed since its first Line.
+/// Payloads are flushed one at a time in the order they were sealed.
+///
+/// If the flush function falls behind by [`BatchConfig::with_max_pending`] payloads,
+/// [`Self::add`] blocks until it catches up, so memory usage stays bounded.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::{Arc, Mutex};
+/// use influx_line::*;
+///
+/// let payloads = Arc::new(Mutex::new(Vec::new()));
+/// let batcher = {
+///     let payloads = payloads.clone();
+///     LineBatcher::new(BatchConfig::new().with_max_lines(2), move |payload| {
+///         payloads.lock().unwrap().push(payload);
+///     })
+///     .unwrap()
+/// };
+///
+/// for value in 0..3 {
+///     batcher.add(&InfluxLine::try_new("cpu", "usage", value).unwrap()).unwrap();
+/// }
+/// batcher.close();
+///
+/// assert_eq!(
+///     vec!["cpu usage=0i\ncpu usage=1i\n", "cpu usage=2i\n"],
+///     *payloads.lock().unwrap()
+/// );
+/// ```
+#[derive(Debug)]
+pub struct LineBatcher {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: BatchConfig,
+    state: Mutex<State>,
+    /// Signals the worker about sealed payloads and shutdown.
+    sealed: Condvar,
+    /// Signals blocked producers about flushed payloads.
+    flushed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    current: String,
+    lines: usize,
+    started: Option<Instant>,
+    ready: VecDeque<String>,
+    closed: bool,
+    /// Timestamp of the current payload with [`StampMode::EachBatch`].
+    instant: Option<Timestamp>,
+}
+
+impl BatchConfig {
+    const DEFAULT_MAX_LINES: usize = 5_000;
+    const DEFAULT_MAX_BYTES: usize = 1024 * 1024;
+    const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+    const DEFAULT_MAX_PENDING: usize = 16;
+
+    pub fn new() -> Self {
+        Self {
+            max_lines: Self::DEFAULT_MAX_LINES,
+            max_bytes: Self::DEFAULT_MAX_BYTES,
+            interval: Self::DEFAULT_INTERVAL,
+            max_pending: Self::DEFAULT_MAX_PENDING,
+            stamper: None,
+        }
+    }
+
+    /// Limits the number of Lines in a payload, 5000 by default.
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines.max(1);
+        self
+    }
+
+    /// Limits the size of a payload in bytes, 1 MiB by default.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes.max(1);
+        self
+    }
+
+    /// Limits how long the first Line of a payload waits for the flush, 1 second by default.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Limits the number of sealed payloads waiting for the flush function, 16 by default.
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending.max(1);
+        self
+    }
+
+    /// Stamps Lines without a timestamp as they are encoded,
+    /// with [`StampMode::EachBatch`] once per payload.
+    pub fn with_stamper(mut self, stamper: Stamper) -> Self {
+        self.stamper = Some(stamper);
+        self
+    }
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineBatcher {
+    /// Starts the background thread calling `flush` with every payload.
+    pub fn new<F>(config: BatchConfig, flush: F) -> Result<Self, InfluxLineError>
+    where
+        F: FnMut(String) + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            config,
+            state: Mutex::new(State::default()),
+            sealed: Condvar::new(),
+            flushed: Condvar::new(),
+        });
+
+        let worker = {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name("influx-line-batcher".to_owned())
+                .spawn(move || run(&shared, flush))?
+        };
+
+        Ok(Self {
+            shared,
+            worker: Some(worker),
+        })
+    }
+
+    /// Appends a Line to the current payload, sealing it first if the Line does not fit.
+    ///
+    /// A Line that exceeds the byte limit on its own
+    /// is rejected with [`InfluxLineError::LineTooLarge`].
+    pub fn add(&self, line: &InfluxLine) -> Result<(), InfluxLineError> {
+        let mut body = String::new();
+        // Writing to a String never fails.
+        let _ = write!(body, "{}", line);
+        if body.ends_with('\n') {
+            body.pop();
+        }
+
+        let config = &self.shared.config;
+        let stamper = config
+            .stamper
+            .as_ref()
+            .filter(|_| line.timestamp().is_none());
+
+        let mut state = self.shared.lock();
+        let mut encoded = state.terminate(&body, stamper);
+        if encoded.len() > config.max_bytes {
+            return Err(InfluxLineError::LineTooLarge {
+                size: encoded.len(),
+                limit: config.max_bytes,
+            });
+        }
+
+        if state.current.len() + encoded.len() > config.max_bytes {
+            state = self.shared.seal(state);
+            if stamper.is_some() {
+                // The next payload may have another instant.
+                encoded = state.terminate(&body, stamper);
+            }
+        }
+
+        state.current.push_str(&encoded);
+        state.lines += 1;
+        if state.started.is_none() {
+            state.started = Some(Instant::now());
+            // Lets the worker pick up the new deadline.
+            self.shared.sealed.notify_one();
+        }
+        if state.lines >= config.max_lines {
+            drop(self.shared.seal(state));
+        }
+        Ok(())
+    }
+
+    /// Seals the current payload without waiting for the limits or the interval.
+    pub fn flush(&self) {
+        let state = self.shared.lock();
+        drop(self.shared.seal(state));
+    }
+
+    /// Flushes everything added so far and stops the background thread.
+    ///
+    /// Dropping the batcher closes it as well.
+    pub fn close(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        {
+            let state = self.shared.lock();
+            let mut state = self.shared.seal(state);
+            state.closed = true;
+        }
+        self.shared.sealed.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for LineBatcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Moves the current payload to the ready ones,
+    /// waiting for room if the flush function falls behind.
+    fn seal<'a>(&'a self, state: MutexGuard<'a, State>) -> MutexGuard<'a, State> {
+        if state.current.is_empty() {
+            return state;
+        }
+
+        let mut state = self
+            .flushed
+            .wait_while(state, |state| {
+                state.ready.len() >= self.config.max_pending && !state.closed
+            })
+            .unwrap_or_else(PoisonError::into_inner);
+        let payload = mem::take(&mut state.current);
+        state.ready.push_back(payload);
+        state.lines = 0;
+        state.started = None;
+        state.instant = None;
+        self.sealed.notify_one();
+        state
+    }
+}
+
+impl State {
+    /// Appends the timestamp, if any, and the newline to an encoded Line.
+    fn terminate(&mut self, body: &str, stamper: Option<&Stamper>) -> String {
+        let mut encoded = body.to_owned();
+        if let Some(stamper) = stamper {
+            let timestamp = match stamper.mode() {
+                StampMode::EachLine => stamper.now(),
+                StampMode::EachBatch => *self.instant.get_or_insert_with(|| stamper.now()),
+            };
+            // Writing to a String never fails.
+            let _ = write!(encoded, " {}", timestamp);
+        }
+        encoded.push('\n');
+        encoded
+    }
+}
+
+fn run<F>(shared: &Shared, mut flush: F)
+where
+    F: FnMut(String),
+{
+    loop {
+        let payload = {
+            let mut state = shared.lock();
+            loop {
+                if let Some(payload) = state.ready.pop_front() {
+                    shared.flushed.notify_all();
+                    break payload;
+                }
+                if state.closed {
+                    return;
+                }
+
+                match state.started {
+                    Some(started) => {
+                        let elapsed = started.elapsed();
+                        if elapsed >= shared.config.interval {
+                            state = shared.seal(state);
+                            continue;
+                        }
+                        state = shared
+                            .sealed
+                            .wait_timeout(state, shared.config.interval - elapsed)
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .0;
+                    }
+                    None => {
+                        state = shared
+                            .sealed
+                            .wait(state)
+                            .unwrap_or_else(PoisonError::into_inner);
+                    }
+                }
+            }
+        };
+
+        flush(payload);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::{
+        BatchConfig, InfluxLine, InfluxLineError, LineBatcher, ManualClock, StampMode, Stamper,
+        Timestamp,
+    };
+
+    fn line(index: u64) -> InfluxLine {
+        InfluxLine::try_new("cpu", "index", index).unwrap()
+    }
+
+    fn batcher(config: BatchConfig) -> (LineBatcher, Arc<Mutex<Vec<String>>>) {
+        let payloads = Arc::new(Mutex::new(Vec::new()));
+        let batcher = {
+            let payloads = payloads.clone();
+            LineBatcher::new(config, move |payload| {
+                payloads.lock().unwrap().push(payload)
+            })
+            .unwrap()
+        };
+        (batcher, payloads)
+    }
+
+    #[rstest::rstest]
+    #[case::by_count(BatchConfig::new().with_max_lines(2), vec![2, 2, 1])]
+    // Every Line takes 13 bytes with the newline.
+    #[case::by_bytes(BatchConfig::new().with_max_bytes(39), vec![3, 2])]
+    #[case::by_both(BatchConfig::new().with_max_lines(2).with_max_bytes(26), vec![2, 2, 1])]
+    fn limits(#[case] config: BatchConfig, #[case] expected_sizes: Vec<usize>) {
+        let (batcher, payloads) = batcher(config.with_interval(Duration::from_secs(60)));
+
+        for index in 0..5 {
+            batcher.add(&line(index)).unwrap();
+        }
+        batcher.close();
+
+        let payloads = payloads.lock().unwrap();
+        let sizes: Vec<_> = payloads
+            .iter()
+            .map(|payload| payload.lines().count())
+            .collect();
+        let expected: String = (0..5).map(|index| format!("{}\n", line(index))).collect();
+        assert_eq!(expected_sizes, sizes);
+        assert_eq!(expected, payloads.concat());
+    }
+
+    #[test]
+    fn rejects_oversized_lines() {
+        let (batcher, payloads) = batcher(BatchConfig::new().with_max_bytes(13));
+
+        batcher.add(&line(1)).unwrap();
+        let error = batcher.add(&line(1000)).unwrap_err();
+        batcher.close();
+
+        assert!(matches!(
+            error,
+            InfluxLineError::LineTooLarge {
+                size: 16,
+                limit: 13
+            }
+        ));
+        assert_eq!(vec!["cpu index=1u\n"], *payloads.lock().unwrap());
+    }
+
+    #[rstest::rstest]
+    #[case::each_line(
+        StampMode::EachLine,
+        vec!["cpu index=0u 1\ncpu index=1u 2\n", "cpu index=2u 3\ncpu index=3u 7\n"]
+    )]
+    #[case::each_batch(
+        StampMode::EachBatch,
+        vec!["cpu index=0u 1\ncpu index=1u 1\n", "cpu index=2u 3\ncpu index=3u 7\n"]
+    )]
+    fn stamps_lines(#[case] mode: StampMode, #[case] expected: Vec<&str>) {
+        let clock = ManualClock::new(Timestamp::from(1));
+        let stamper = Stamper::new().with_clock(clock.clone()).with_mode(mode);
+        let (batcher, payloads) = batcher(
+            BatchConfig::new()
+                .with_max_lines(2)
+                .with_interval(Duration::from_secs(60))
+                .with_stamper(stamper),
+        );
+
+        for index in 0..3 {
+            batcher.add(&line(index)).unwrap();
+            clock.advance(Duration::from_nanos(1));
+        }
+        batcher.add(&line(3).with_timestamp(7)).unwrap();
+        batcher.close();
+
+        assert_eq!(expected, *payloads.lock().unwrap());
+    }
+
+    #[test]
+    fn flushes_on_interval() {
+        let (batcher, payloads) =
+            batcher(BatchConfig::new().with_interval(Duration::from_millis(20)));
+
+        batcher.add(&line(1)).unwrap();
+        for _ in 0..100 {
+            if !payloads.lock().unwrap().is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(vec!["cpu index=1u\n"], *payloads.lock().unwrap());
+        batcher.close();
+    }
+
+    #[test]
+    fn drains_many_producers() {
+        let (batcher, payloads) = batcher(
+            BatchConfig::new()
+                .with_max_lines(7)
+                .with_max_bytes(64)
+                .with_max_pending(1),
+        );
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for index in 0..250 {
+                        batcher.add(&line(index)).unwrap();
+                    }
+                });
+            }
+        });
+        batcher.close();
+
+        let payloads = payloads.lock().unwrap();
+        assert!(payloads.iter().all(|payload| payload.len() <= 64));
+        assert!(payloads.iter().all(|payload| payload.lines().count() <= 7));
+        assert_eq!(
+            1000,
+            payloads
+                .iter()
+                .map(|payload| payload.lines().count())
+                .sum::<usize>()
+        );
+    }
+}
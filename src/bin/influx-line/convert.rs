@@ -0,0 +1,396 @@
+use std::collections::BTreeSet;
+use std::io::{BufRead, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use influx_line::{
+    Compression, InfluxLine, InfluxLineError, InfluxValue, KeyName, LineReader, MeasurementName,
+    Precision, Timestamp,
+};
+use serde_json::{Map, Number, Value, json};
+
+use crate::inputs;
+
+/// Formats `convert` translates between.
+///
+/// - JSON Lines hold an object per Line:
+///   `{"measurement":"cpu","tags":{"host":"a"},"fields":{"usage":0.5},"time":1}`.
+///   Integers and unsigned integers both become JSON integers,
+///   and are read back as integers unless they only fit into an unsigned one.
+/// - CSV has a `measurement` column, a `time` column with nanoseconds,
+///   and a `tag:<key>` or `field:<key>` column per key seen in the input.
+///   Field cells hold Line Protocol values, e.g. `1i` or `"text"`,
+///   so the conversion is lossless. Empty cells mean missing keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Lp,
+    Jsonl,
+    Csv,
+}
+
+const TAG_PREFIX: &str = "tag:";
+const FIELD_PREFIX: &str = "field:";
+
+/// Converts every input, stopping at the first invalid Line.
+///
+/// Line Protocol and JSON Lines are written as they are read,
+/// while CSV needs every key up front, so its Lines are collected first.
+pub fn run(
+    files: &[PathBuf],
+    from: Format,
+    to: Format,
+    precision: Precision,
+    output: &mut impl Write,
+) -> Result<ExitCode, InfluxLineError> {
+    let mut lines = Vec::new();
+    let mut emit = |line: InfluxLine| -> Result<(), InfluxLineError> {
+        match to {
+            Format::Lp => writeln!(output, "{}", line)?,
+            Format::Jsonl => writeln!(output, "{}", to_json(&line))?,
+            Format::Csv => lines.push(line),
+        }
+        Ok(())
+    };
+
+    for input in inputs(files)? {
+        let read = match from {
+            Format::Lp => read_lp(input.reader, &input.name, precision, &mut emit),
+            Format::Jsonl => read_jsonl(input.reader, &input.name, &mut emit),
+            Format::Csv => read_csv(input.reader, &input.name, &mut emit),
+        };
+        match read {
+            Ok(()) => (),
+            Err(Stop::Invalid(message)) => {
+                eprintln!("{}", message);
+                return Ok(ExitCode::FAILURE);
+            }
+            Err(Stop::Output(error)) => return Err(error),
+        }
+    }
+
+    if to == Format::Csv {
+        write_csv(&lines, output)?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Why reading an input stopped early.
+enum Stop {
+    /// The input is invalid, as described by a message with its position.
+    Invalid(String),
+    /// Writing a converted Line failed.
+    Output(InfluxLineError),
+}
+
+impl From<String> for Stop {
+    fn from(message: String) -> Self {
+        Self::Invalid(message)
+    }
+}
+
+type ReadResult = Result<(), Stop>;
+
+/// Takes every Line read, e.g. to write it out.
+type Emit<'a> = dyn FnMut(InfluxLine) -> Result<(), InfluxLineError> + 'a;
+
+fn read_lp(
+    reader: Box<dyn Read>,
+    name: &str,
+    precision: Precision,
+    emit: &mut Emit<'_>,
+) -> ReadResult {
+    let mut reader = LineReader::new(reader)
+        .map_err(|error| format!("{}: {}", name, error))?
+        .with_precision(precision);
+    while let Some(result) = reader.next() {
+        let line =
+            result.map_err(|error| format!("{}:{}: {}", name, reader.line_number(), error))?;
+        emit(line).map_err(Stop::Output)?;
+    }
+    Ok(())
+}
+
+fn read_jsonl(reader: Box<dyn Read>, name: &str, emit: &mut Emit<'_>) -> ReadResult {
+    let reader = Compression::decompress(reader).map_err(|error| format!("{}: {}", name, error))?;
+    for (index, row) in reader.lines().enumerate() {
+        let position = || format!("{}:{}", name, index + 1);
+        let row = row.map_err(|error| format!("{}: {}", position(), error))?;
+        if row.trim().is_empty() {
+            continue;
+        }
+
+        let value: Value =
+            serde_json::from_str(&row).map_err(|error| format!("{}: {}", position(), error))?;
+        let line = from_json(&value).map_err(|error| format!("{}: {}", position(), error))?;
+        emit(line).map_err(Stop::Output)?;
+    }
+    Ok(())
+}
+
+fn read_csv(reader: Box<dyn Read>, name: &str, emit: &mut Emit<'_>) -> ReadResult {
+    let reader = Compression::decompress(reader).map_err(|error| format!("{}: {}", name, error))?;
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader
+        .headers()
+        .map_err(|error| format!("{}: {}", name, error))?
+        .clone();
+
+    for (index, record) in reader.records().enumerate() {
+        // The header takes the first row.
+        let position = || format!("{}:{}", name, index + 2);
+        let record = record.map_err(|error| format!("{}: {}", position(), error))?;
+        let line =
+            from_csv(&headers, &record).map_err(|error| format!("{}: {}", position(), error))?;
+        emit(line).map_err(Stop::Output)?;
+    }
+    Ok(())
+}
+
+fn to_json(line: &InfluxLine) -> Value {
+    let tags: Map<String, Value> = line
+        .tags()
+        .map(|(key, value)| (key.as_ref().to_owned(), Value::from(value.as_ref())))
+        .collect();
+    let fields: Map<String, Value> = line
+        .fields()
+        .map(|(key, value)| {
+            let value = match value {
+                InfluxValue::Float(float) => {
+                    Number::from_f64(*float).map_or(Value::Null, Value::Number)
+                }
+                InfluxValue::Integer(integer) => Value::from(i64::from(*integer)),
+                InfluxValue::UInteger(uinteger) => Value::from(u64::from(*uinteger)),
+                InfluxValue::Boolean(boolean) => Value::from(bool::from(*boolean)),
+                InfluxValue::String(string) => Value::from(string.as_ref()),
+            };
+            (key.as_ref().to_owned(), value)
+        })
+        .collect();
+
+    let mut object = json!({
+        "measurement": line.measurement().as_ref(),
+        "tags": tags,
+        "fields": fields,
+    });
+    if let Some(timestamp) = line.timestamp() {
+        object["time"] = Value::from(i64::from(timestamp));
+    }
+    object
+}
+
+fn from_json(value: &Value) -> Result<InfluxLine, InfluxLineError> {
+    let measurement = value
+        .get("measurement")
+        .and_then(Value::as_str)
+        .ok_or(InfluxLineError::NoMeasurement)?;
+
+    let mut tags = Vec::new();
+    if let Some(object) = value.get("tags").and_then(Value::as_object) {
+        for (key, value) in object {
+            let value = value.as_str().ok_or(InfluxLineError::BadValue)?;
+            tags.push((KeyName::new(key)?, KeyName::new(value)?));
+        }
+    }
+
+    let mut fields = Vec::new();
+    if let Some(object) = value.get("fields").and_then(Value::as_object) {
+        for (key, value) in object {
+            let value = match value {
+                Value::Bool(boolean) => InfluxValue::from(*boolean),
+                Value::String(string) => InfluxValue::from(string.as_str()),
+                Value::Number(number) => {
+                    if let Some(integer) = number.as_i64() {
+                        InfluxValue::from(integer)
+                    } else if let Some(uinteger) = number.as_u64() {
+                        InfluxValue::from(uinteger)
+                    } else {
+                        InfluxValue::from(number.as_f64().ok_or(InfluxLineError::BadValue)?)
+                    }
+                }
+                _ => return Err(InfluxLineError::BadValue),
+            };
+            fields.push((KeyName::new(key)?, value));
+        }
+    }
+
+    let timestamp = match value.get("time") {
+        Some(time) => Some(Timestamp::from(
+            time.as_i64().ok_or(InfluxLineError::TimestampNotParsed)?,
+        )),
+        None => None,
+    };
+
+    InfluxLine::full(MeasurementName::new(measurement)?, tags, fields, timestamp)
+}
+
+fn write_csv(lines: &[InfluxLine], output: &mut impl Write) -> Result<(), InfluxLineError> {
+    let tag_keys: BTreeSet<String> = lines
+        .iter()
+        .flat_map(|line| line.tags().map(|(key, _)| key.as_ref().to_owned()))
+        .collect();
+    let field_keys: BTreeSet<String> = lines
+        .iter()
+        .flat_map(|line| line.fields().map(|(key, _)| key.as_ref().to_owned()))
+        .collect();
+
+    let mut writer = csv::Writer::from_writer(output);
+    let mut header = vec!["measurement".to_owned()];
+    header.extend(tag_keys.iter().map(|key| format!("{}{}", TAG_PREFIX, key)));
+    header.extend(
+        field_keys
+            .iter()
+            .map(|key| format!("{}{}", FIELD_PREFIX, key)),
+    );
+    header.push("time".to_owned());
+    writer.write_record(&header).map_err(csv_error)?;
+
+    for line in lines {
+        let mut record = vec![line.measurement().as_ref().to_owned()];
+        record.extend(tag_keys.iter().map(|key| {
+            line.tag(key.as_str())
+                .map(|value| value.as_ref().to_owned())
+                .unwrap_or_default()
+        }));
+        record.extend(field_keys.iter().map(|key| {
+            line.field(key.as_str())
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+        }));
+        record.push(
+            line.timestamp()
+                .map(|timestamp| timestamp.to_string())
+                .unwrap_or_default(),
+        );
+        writer.write_record(&record).map_err(csv_error)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn from_csv(
+    headers: &csv::StringRecord,
+    record: &csv::StringRecord,
+) -> Result<InfluxLine, InfluxLineError> {
+    let mut measurement = None;
+    let mut tags = Vec::new();
+    let mut fields = Vec::new();
+    let mut timestamp = None;
+
+    for (header, cell) in headers.iter().zip(record.iter()) {
+        if cell.is_empty() {
+            continue;
+        }
+
+        if header == "measurement" {
+            measurement = Some(MeasurementName::new(cell)?);
+        } else if header == "time" {
+            timestamp = Some(Timestamp::from_str(cell)?);
+        } else if let Some(key) = header.strip_prefix(TAG_PREFIX) {
+            tags.push((KeyName::new(key)?, KeyName::new(cell)?));
+        } else if let Some(key) = header.strip_prefix(FIELD_PREFIX) {
+            fields.push((KeyName::new(key)?, InfluxValue::from_str(cell)?));
+        }
+    }
+
+    InfluxLine::full(
+        measurement.ok_or(InfluxLineError::NoMeasurement)?,
+        tags,
+        fields,
+        timestamp,
+    )
+}
+
+fn csv_error(error: csv::Error) -> InfluxLineError {
+    InfluxLineError::Io(error.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::ExitCode;
+    use std::str::FromStr;
+
+    use influx_line::{InfluxLine, Precision};
+
+    use super::{Format, from_csv, from_json, run, to_json, write_csv};
+
+    fn lines() -> Vec<InfluxLine> {
+        [
+            r#"cpu,host=web\ 1,zone=a cores=8i,note="a \"b\", c",ok=true,usage=0.5 1704067200000000000"#,
+            "mem free=1024u",
+        ]
+        .iter()
+        .map(|line| InfluxLine::from_str(line).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let converted: Vec<_> = lines()
+            .iter()
+            .map(|line| from_json(&to_json(line)).unwrap())
+            .collect();
+
+        // Both formats order keys by name, so the input is already sorted.
+        // Unsigned integers that fit into signed ones become integers.
+        assert_eq!(lines()[0], converted[0]);
+        assert_eq!("mem free=1024i", converted[1].to_string());
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let mut output = Vec::new();
+        write_csv(&lines(), &mut output).unwrap();
+
+        let mut reader = csv::Reader::from_reader(output.as_slice());
+        let headers = reader.headers().unwrap().clone();
+        let converted: Vec<_> = reader
+            .records()
+            .map(|record| from_csv(&headers, &record.unwrap()).unwrap())
+            .collect();
+
+        let header = String::from_utf8(output).unwrap();
+        assert_eq!(
+            Some(
+                "measurement,tag:host,tag:zone,field:cores,field:free,field:note,field:ok,field:usage,time"
+            ),
+            header.lines().next()
+        );
+        assert_eq!(lines(), converted);
+    }
+
+    #[rstest::rstest]
+    #[case::lp_in_precision(
+        Format::Lp,
+        Precision::Seconds,
+        "cpu v=1i 1704067200\n",
+        ExitCode::SUCCESS,
+        "cpu v=1i 1704067200000000000\n"
+    )]
+    #[case::streams_until_invalid_line(
+        Format::Jsonl,
+        Precision::Nanoseconds,
+        "cpu v=1i 1\ncpu v=\n",
+        ExitCode::FAILURE,
+        "{\"fields\":{\"v\":1},\"measurement\":\"cpu\",\"tags\":{},\"time\":1}\n"
+    )]
+    fn converts_files(
+        #[case] to: Format,
+        #[case] precision: Precision,
+        #[case] input: &str,
+        #[case] expected_code: ExitCode,
+        #[case] expected: &str,
+    ) {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("data.lp");
+        std::fs::write(&path, input).unwrap();
+        let mut output = Vec::new();
+
+        let code = run(&[path], Format::Lp, to, precision, &mut output).unwrap();
+
+        assert_eq!(expected_code, code);
+        assert_eq!(expected, String::from_utf8(output).unwrap());
+    }
+}
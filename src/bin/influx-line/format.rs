@@ -0,0 +1,219 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use influx_line::{
+    Compression, InfluxLine, InfluxLineError, LineEncoder, LineReader, Precision, Timestamp,
+};
+
+use crate::inputs;
+
+/// Prints canonical Lines to the output, stopping at the first invalid one.
+///
+/// Timestamps are written in the input precision unless another one is given.
+pub fn print(
+    files: &[PathBuf],
+    precision: Precision,
+    output_precision: Option<Precision>,
+    output: &mut impl Write,
+) -> Result<ExitCode, InfluxLineError> {
+    let output_precision = output_precision.unwrap_or(precision);
+    for input in inputs(files)? {
+        let mut reader = LineReader::new(input.reader)?.with_precision(precision);
+        while let Some(result) = reader.next() {
+            match result {
+                Ok(line) => writeln!(output, "{}", canonical(&line, output_precision)?)?,
+                Err(error) => {
+                    eprintln!("{}:{}: {}", input.name, reader.line_number(), error);
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Rewrites every file in place, keeping its compression and timestamp precision
+/// unless another one is given.
+///
+/// A file with an invalid Line is left untouched.
+/// Stdin cannot be rewritten, so files are required.
+pub fn rewrite(
+    files: &[PathBuf],
+    precision: Precision,
+    output_precision: Option<Precision>,
+) -> Result<ExitCode, InfluxLineError> {
+    if files.is_empty() || files.iter().any(|path| path.as_os_str() == "-") {
+        eprintln!("error: --write needs files to rewrite, not stdin");
+        return Ok(ExitCode::from(2));
+    }
+
+    let output_precision = output_precision.unwrap_or(precision);
+    let mut code = ExitCode::SUCCESS;
+    for path in files {
+        if !rewrite_file(path, precision, output_precision)? {
+            code = ExitCode::FAILURE;
+        }
+    }
+    Ok(code)
+}
+
+/// Writes the canonical Lines next to the file and replaces it,
+/// removing the temporary file unless the replacement succeeds.
+fn rewrite_file(
+    path: &Path,
+    precision: Precision,
+    output_precision: Precision,
+) -> Result<bool, InfluxLineError> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+
+    let result = replace_file(path, &temporary, precision, output_precision);
+    if !matches!(result, Ok(true)) {
+        // The file is not there if creating it failed.
+        let _ = fs::remove_file(&temporary);
+    }
+    result
+}
+
+fn replace_file(
+    path: &Path,
+    temporary: &Path,
+    precision: Precision,
+    output_precision: Precision,
+) -> Result<bool, InfluxLineError> {
+    let mut reader = LineReader::open(path)?.with_precision(precision);
+    let mut encoder = LineEncoder::new(
+        BufWriter::new(File::create(temporary)?),
+        Compression::from_path(path),
+    )?;
+    while let Some(result) = reader.next() {
+        match result.and_then(|line| canonical(&line, output_precision)) {
+            Ok(line) => encoder.write_line(&line)?,
+            Err(error) => {
+                eprintln!("{}:{}: {}", path.display(), reader.line_number(), error);
+                return Ok(false);
+            }
+        }
+    }
+
+    encoder.finish()?;
+    fs::rename(temporary, path)?;
+    Ok(true)
+}
+
+/// Sorts tags by key, as InfluxDB does for series keys,
+/// and writes the timestamp in the output precision.
+///
+/// Values are normalized by formatting itself, e.g. `T` turns into `true`.
+pub fn canonical(
+    line: &InfluxLine,
+    output_precision: Precision,
+) -> Result<InfluxLine, InfluxLineError> {
+    let mut tags: Vec<_> = line
+        .tags()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    tags.sort_by(|(left, _), (right, _)| left.as_ref().cmp(right.as_ref()));
+
+    let timestamp = line
+        .timestamp()
+        .map(|timestamp| Timestamp::from(timestamp.to_precision(output_precision)));
+
+    InfluxLine::full(
+        line.measurement().clone(),
+        tags,
+        line.fields()
+            .map(|(key, value)| (key.clone(), value.clone())),
+        timestamp,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::process::ExitCode;
+    use std::str::FromStr;
+
+    use influx_line::{InfluxLine, Precision};
+
+    use super::{canonical, rewrite};
+
+    #[rstest::rstest]
+    #[case::sorts_tags(
+        "cpu,zone=b,host=a,dc=c usage=1 1",
+        Precision::Nanoseconds,
+        "cpu,dc=c,host=a,zone=b usage=1.0 1"
+    )]
+    #[case::normalizes_booleans(
+        "cpu up=T,down=FALSE",
+        Precision::Nanoseconds,
+        "cpu up=true,down=false"
+    )]
+    #[case::converts_precision(
+        "cpu usage=1i 1704067200123456789",
+        Precision::Milliseconds,
+        "cpu usage=1i 1704067200123"
+    )]
+    fn canonical_form(
+        #[case] input: &str,
+        #[case] output_precision: Precision,
+        #[case] expected: &str,
+    ) {
+        let line = InfluxLine::from_str(input).expect("Must parse here");
+
+        let actual = canonical(&line, output_precision).unwrap();
+
+        assert_eq!(expected, actual.to_string());
+    }
+
+    #[rstest::rstest]
+    #[case::input_precision(None, "cpu,a=1,b=2 usage=1.0 1704067200\n")]
+    #[case::output_precision(
+        Some(Precision::Milliseconds),
+        "cpu,a=1,b=2 usage=1.0 1704067200000\n"
+    )]
+    fn rewrites_files(#[case] output_precision: Option<Precision>, #[case] expected: &str) {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("data.lp");
+        std::fs::write(&path, "cpu,b=2,a=1 usage=1 1704067200\n").unwrap();
+
+        let code = rewrite(
+            std::slice::from_ref(&path),
+            Precision::Seconds,
+            output_precision,
+        )
+        .unwrap();
+
+        assert_eq!(ExitCode::SUCCESS, code);
+        assert_eq!(expected, std::fs::read_to_string(&path).unwrap());
+        assert_eq!(1, std::fs::read_dir(directory.path()).unwrap().count());
+    }
+
+    #[test]
+    fn keeps_invalid_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("data.lp");
+        std::fs::write(&path, "cpu usage=1\ncpu usage=\n").unwrap();
+
+        let code = rewrite(std::slice::from_ref(&path), Precision::Nanoseconds, None).unwrap();
+
+        assert_eq!(ExitCode::FAILURE, code);
+        assert_eq!(
+            "cpu usage=1\ncpu usage=\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+        assert_eq!(1, std::fs::read_dir(directory.path()).unwrap().count());
+    }
+
+    #[rstest::rstest]
+    #[case::no_files(vec![])]
+    #[case::stdin(vec![PathBuf::from("-")])]
+    fn rewrite_needs_files(#[case] files: Vec<PathBuf>) {
+        let code = rewrite(&files, Precision::Nanoseconds, None).unwrap();
+
+        assert_eq!(ExitCode::from(2), code);
+    }
+}
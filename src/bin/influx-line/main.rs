@@ -0,0 +1,246 @@
+//! Command-line tool to validate, format, convert and summarize Line Protocol.
+//!
+//! Every subcommand reads the given files, or stdin if there are none or `-` is given.
+//! Compressed input, e.g. `.lp.gz` or `.lp.zst`, is detected automatically.
+
+mod convert;
+mod format;
+mod stats;
+
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use influx_line::{InfluxLineError, LineReader, Linter, Precision, Severity};
+
+use crate::convert::Format;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Reports every invalid line with its position.
+    Validate {
+        /// Input files, stdin by default.
+        files: Vec<PathBuf>,
+        /// Precision of raw timestamps: ns, us, ms or s.
+        #[arg(long, default_value = "ns")]
+        precision: Precision,
+    },
+    /// Rewrites lines in canonical form with sorted tags and normalized values.
+    Fmt {
+        /// Input files, stdin by default.
+        files: Vec<PathBuf>,
+        /// Rewrites the files in place instead of printing to stdout.
+        #[arg(short, long)]
+        write: bool,
+        /// Precision of raw timestamps in the input: ns, us, ms or s.
+        #[arg(long, default_value = "ns")]
+        precision: Precision,
+        /// Converts timestamps to another precision, the input precision by default.
+        #[arg(long)]
+        output_precision: Option<Precision>,
+    },
+    /// Translates between Line Protocol, JSON Lines and CSV.
+    Convert {
+        /// Input files, stdin by default.
+        files: Vec<PathBuf>,
+        #[arg(long, value_enum, default_value = "lp")]
+        from: Format,
+        #[arg(long, value_enum)]
+        to: Format,
+        /// Precision of raw timestamps in Line Protocol input: ns, us, ms or s.
+        ///
+        /// Timestamps are always written in nanoseconds.
+        #[arg(long, default_value = "ns")]
+        precision: Precision,
+    },
+    /// Reports likely mistakes, such as unique IDs in tags or wrong timestamp precision.
+    Lint {
+        /// Input files, stdin by default.
+        files: Vec<PathBuf>,
+        /// Precision of raw timestamps: ns, us, ms or s.
+        #[arg(long, default_value = "ns")]
+        precision: Precision,
+        /// Fails on warnings too, not only on errors.
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Summarizes measurements, series, field types and the time range.
+    Stats {
+        /// Input files, stdin by default.
+        files: Vec<PathBuf>,
+        /// Precision of raw timestamps: ns, us, ms or s.
+        #[arg(long, default_value = "ns")]
+        precision: Precision,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let stdout = io::stdout().lock();
+    let mut output = BufWriter::new(stdout);
+
+    let result = match cli.command {
+        Command::Validate { files, precision } => validate(&files, precision, &mut output),
+        Command::Fmt {
+            files,
+            write: true,
+            precision,
+            output_precision,
+        } => format::rewrite(&files, precision, output_precision),
+        Command::Fmt {
+            files,
+            write: false,
+            precision,
+            output_precision,
+        } => format::print(&files, precision, output_precision, &mut output),
+        Command::Convert {
+            files,
+            from,
+            to,
+            precision,
+        } => convert::run(&files, from, to, precision, &mut output),
+        Command::Lint {
+            files,
+            precision,
+            strict,
+        } => lint(&files, precision, strict, &mut output),
+        Command::Stats { files, precision } => stats::run(&files, precision, &mut output),
+    };
+    let result = result.and_then(|code| {
+        output.flush()?;
+        Ok(code)
+    });
+
+    match result {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// An input file or stdin along with its name for messages.
+///
+/// The reader is not decompressed yet, as [`LineReader`] does it on its own.
+struct Input {
+    name: String,
+    reader: Box<dyn io::Read>,
+}
+
+fn inputs(files: &[PathBuf]) -> Result<Vec<Input>, InfluxLineError> {
+    if files.is_empty() {
+        return Ok(vec![stdin()?]);
+    }
+
+    files
+        .iter()
+        .map(|path| {
+            if path.as_os_str() == "-" {
+                return stdin();
+            }
+            Ok(Input {
+                name: path.display().to_string(),
+                reader: Box::new(std::fs::File::open(path)?),
+            })
+        })
+        .collect()
+}
+
+/// Shares stdin between inputs rather than locking it,
+/// as holding its lock for one `-` would block reading another one forever.
+/// Stdin is read up to its end by the first of them anyway.
+fn stdin() -> Result<Input, InfluxLineError> {
+    Ok(Input {
+        name: "<stdin>".to_owned(),
+        reader: Box::new(io::stdin()),
+    })
+}
+
+fn validate(
+    files: &[PathBuf],
+    precision: Precision,
+    output: &mut impl Write,
+) -> Result<ExitCode, InfluxLineError> {
+    let mut checked = 0;
+    let mut invalid = 0;
+    for input in inputs(files)? {
+        let mut reader = LineReader::new(input.reader)?.with_precision(precision);
+        while let Some(result) = reader.next() {
+            checked += 1;
+            match result {
+                Ok(_) => {}
+                Err(InfluxLineError::Io(error)) => return Err(error.into()),
+                Err(error) => {
+                    invalid += 1;
+                    writeln!(output, "{}:{}: {}", input.name, reader.line_number(), error)?;
+                }
+            }
+        }
+    }
+
+    eprintln!("{} lines checked, {} invalid", checked, invalid);
+    Ok(if invalid == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Lints every input with the recommended rules,
+/// expecting raw timestamps in the given precision.
+fn lint(
+    files: &[PathBuf],
+    precision: Precision,
+    strict: bool,
+    output: &mut impl Write,
+) -> Result<ExitCode, InfluxLineError> {
+    let failing = if strict {
+        Severity::Warning
+    } else {
+        Severity::Error
+    };
+
+    let mut failed = false;
+    for input in inputs(files)? {
+        // Rules remember keys per input, so every file is linted on its own.
+        let mut linter = Linter::recommended_for(precision);
+        let mut reader = LineReader::new(input.reader)?.with_precision(precision);
+        while let Some(result) = reader.next() {
+            let findings = match result {
+                Ok(line) => linter.check(reader.line_number(), &line),
+                Err(InfluxLineError::Io(error)) => return Err(error.into()),
+                Err(error) => {
+                    failed = true;
+                    writeln!(
+                        output,
+                        "{}:{}: error: {} [{}]",
+                        input.name,
+                        reader.line_number(),
+                        error,
+                        Linter::PARSE_RULE
+                    )?;
+                    continue;
+                }
+            };
+            for finding in findings {
+                failed |= finding.severity() >= failing;
+                writeln!(output, "{}:{}", input.name, finding)?;
+            }
+        }
+    }
+
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
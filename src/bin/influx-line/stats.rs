@@ -0,0 +1,169 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use influx_line::{InfluxLine, InfluxLineError, InfluxValueType, LineReader, Precision, Timestamp};
+
+use crate::inputs;
+
+pub fn run(
+    files: &[PathBuf],
+    precision: Precision,
+    output: &mut impl Write,
+) -> Result<ExitCode, InfluxLineError> {
+    let mut stats = Stats::default();
+    for input in inputs(files)? {
+        let mut reader = LineReader::new(input.reader)?.with_precision(precision);
+        while let Some(result) = reader.next() {
+            match result {
+                Ok(line) => stats.add(&line),
+                Err(InfluxLineError::Io(error)) => return Err(error.into()),
+                Err(error) => {
+                    stats.invalid += 1;
+                    eprintln!("{}:{}: {}", input.name, reader.line_number(), error);
+                }
+            }
+        }
+    }
+
+    stats.report(output)?;
+    Ok(if stats.invalid == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Counters gathered over all valid Lines.
+#[derive(Debug, Default)]
+struct Stats {
+    lines: usize,
+    invalid: usize,
+    measurements: BTreeMap<String, MeasurementStats>,
+    earliest: Option<Timestamp>,
+    latest: Option<Timestamp>,
+}
+
+#[derive(Debug, Default)]
+struct MeasurementStats {
+    lines: usize,
+    /// Series are told apart by their sorted tag sets.
+    series: BTreeSet<Vec<(String, String)>>,
+    fields: BTreeMap<String, BTreeSet<InfluxValueType>>,
+}
+
+impl Stats {
+    fn add(&mut self, line: &InfluxLine) {
+        self.lines += 1;
+
+        let measurement = self
+            .measurements
+            .entry(line.measurement().as_ref().to_owned())
+            .or_default();
+        measurement.lines += 1;
+
+        let mut tags: Vec<_> = line
+            .tags()
+            .map(|(key, value)| (key.as_ref().to_owned(), value.as_ref().to_owned()))
+            .collect();
+        tags.sort();
+        measurement.series.insert(tags);
+
+        for (key, value) in line.fields() {
+            measurement
+                .fields
+                .entry(key.as_ref().to_owned())
+                .or_default()
+                .insert(value.value_type());
+        }
+
+        if let Some(timestamp) = line.timestamp() {
+            self.earliest = Some(self.earliest.map_or(timestamp, |t| t.min(timestamp)));
+            self.latest = Some(self.latest.map_or(timestamp, |t| t.max(timestamp)));
+        }
+    }
+
+    fn series(&self) -> usize {
+        self.measurements
+            .values()
+            .map(|measurement| measurement.series.len())
+            .sum()
+    }
+
+    fn report(&self, output: &mut impl Write) -> Result<(), InfluxLineError> {
+        writeln!(output, "lines: {}", self.lines)?;
+        writeln!(output, "invalid: {}", self.invalid)?;
+        writeln!(output, "measurements: {}", self.measurements.len())?;
+        writeln!(output, "series: {}", self.series())?;
+        if let (Some(earliest), Some(latest)) = (self.earliest, self.latest) {
+            writeln!(output, "time: {} .. {}", rfc3339(earliest), rfc3339(latest))?;
+        }
+
+        for (name, measurement) in self.measurements.iter() {
+            writeln!(output)?;
+            writeln!(
+                output,
+                "{}: {} lines, {} series",
+                name,
+                measurement.lines,
+                measurement.series.len()
+            )?;
+            for (field, types) in measurement.fields.iter() {
+                let types: Vec<_> = types
+                    .iter()
+                    .map(|value_type| value_type.to_string())
+                    .collect();
+                let conflict = if types.len() > 1 { " (conflict)" } else { "" };
+                writeln!(output, "  {}: {}{}", field, types.join(", "), conflict)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn rfc3339(timestamp: Timestamp) -> String {
+    DateTime::<Utc>::from(timestamp).to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use influx_line::InfluxLine;
+
+    use super::Stats;
+
+    #[test]
+    fn report() {
+        let mut stats = Stats::default();
+        for line in [
+            "cpu,host=a,zone=1 usage=0.5 1704067200000000000",
+            "cpu,zone=1,host=a usage=1i 1704067260000000000",
+            "cpu,host=b usage=0.7,cores=4i 1704067230000000000",
+            "mem free=1024u",
+        ] {
+            stats.add(&InfluxLine::from_str(line).expect("Must parse here"));
+        }
+
+        let mut output = Vec::new();
+        stats.report(&mut output).unwrap();
+
+        let expected = "\
+lines: 4
+invalid: 0
+measurements: 2
+series: 3
+time: 2024-01-01T00:00:00Z .. 2024-01-01T00:01:00Z
+
+cpu: 3 lines, 2 series
+  cores: integer
+  usage: float, integer (conflict)
+
+mem: 1 lines, 1 series
+  free: unsigned
+";
+        assert_eq!(expected, String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::{InfluxLine, InfluxLineError, KeyName, MeasurementName};
+
+/// Estimates the number of distinct series in a stream of Lines
+/// with [HyperLogLog](https://en.wikipedia.org/wiki/HyperLogLog) sketches,
+/// without keeping the series keys themselves.
+///
+/// A series is a measurement along with its whole tag set, regardless of the tag order.
+/// Besides the whole stream, series are counted for each measurement,
+/// and distinct values are counted for each tag key across all measurements,
+/// which points at the tags that drive cardinality up.
+///
+/// Every sketch takes `2^precision` bytes, and has a standard error of
+/// `1.04 / sqrt(2^precision)`, i.e. 1.6% with [`Self::DEFAULT_PRECISION`].
+/// Only the first [`Self::with_max_keys`] measurements and tag keys get sketches of their own,
+/// and the rest share one sketch each, see [`Self::untracked_series`],
+/// so that unique measurement names or tag keys cannot blow up the estimator itself.
+/// Estimators fed on different threads can be combined with [`Self::merge`].
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let mut estimator = CardinalityEstimator::new();
+/// for host in 0..1000 {
+///     for _ in 0..3 {
+///         let line = InfluxLine::try_new("cpu", "usage", 0.5)
+///             .and_then(|line| line.try_with_tag("host", format!("web-{}", host)))
+///             .unwrap();
+///         estimator.observe(&line);
+///     }
+/// }
+///
+/// let series = estimator.series() as f64;
+/// assert!((series - 1000.0).abs() < 1000.0 * 3.0 * estimator.standard_error());
+/// assert_eq!(estimator.series(), estimator.measurement_series("cpu").unwrap());
+///
+/// let hosts = estimator.tag_values("host").unwrap() as f64;
+/// assert!((hosts - 1000.0).abs() < 1000.0 * 3.0 * estimator.standard_error());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardinalityEstimator {
+    precision: u8,
+    max_keys: usize,
+    total: HyperLogLog,
+    measurements: Sketches<MeasurementName>,
+    tag_keys: Sketches<KeyName>,
+}
+
+/// Sketches by key, up to a limit, and a shared one for the keys beyond it.
+#[derive(Debug, Clone, PartialEq)]
+struct Sketches<K> {
+    tracked: BTreeMap<K, HyperLogLog>,
+    untracked: Option<HyperLogLog>,
+}
+
+/// A single sketch of `2^precision` registers,
+/// each holding the longest run of leading zeros seen in its share of hashes.
+#[derive(Debug, Clone, PartialEq)]
+struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl CardinalityEstimator {
+    /// Number of hash bits that select a register unless set otherwise.
+    pub const DEFAULT_PRECISION: u8 = 12;
+    /// Number of measurements, and separately of tag keys, tracked unless set otherwise.
+    pub const DEFAULT_MAX_KEYS: usize = 1_000;
+
+    const PRECISIONS: std::ops::RangeInclusive<u8> = 4..=16;
+
+    /// Creates an empty estimator with [`Self::DEFAULT_PRECISION`]
+    /// and [`Self::DEFAULT_MAX_KEYS`].
+    pub fn new() -> Self {
+        Self::empty(Self::DEFAULT_PRECISION, Self::DEFAULT_MAX_KEYS)
+    }
+
+    /// Sets the precision, trading memory for accuracy, and forgets everything observed.
+    ///
+    /// Fails with [`InfluxLineError::BadEstimatorPrecision`] unless it is between 4 and 16.
+    pub fn try_with_precision(self, precision: u8) -> Result<Self, InfluxLineError> {
+        if !Self::PRECISIONS.contains(&precision) {
+            return Err(InfluxLineError::BadEstimatorPrecision);
+        }
+        Ok(Self::empty(precision, self.max_keys))
+    }
+
+    /// Limits the number of measurements, and separately of tag keys,
+    /// that get sketches of their own, and forgets everything observed.
+    pub fn with_max_keys(self, max_keys: usize) -> Self {
+        Self::empty(self.precision, max_keys)
+    }
+
+    fn empty(precision: u8, max_keys: usize) -> Self {
+        Self {
+            precision,
+            max_keys,
+            total: HyperLogLog::new(precision),
+            measurements: Sketches::new(),
+            tag_keys: Sketches::new(),
+        }
+    }
+
+    /// Counts the series of the Line, and the values of its tags.
+    pub fn observe(&mut self, line: &InfluxLine) {
+        let series = series_hash(line);
+        self.total.insert(series);
+        self.measurements
+            .sketch(line.measurement(), self.max_keys, self.precision)
+            .insert(series);
+
+        for (key, value) in line.tags() {
+            self.tag_keys
+                .sketch(key, self.max_keys, self.precision)
+                .insert(hash(value.as_ref()));
+        }
+    }
+
+    /// Returns the estimated number of distinct series in the whole stream.
+    pub fn series(&self) -> u64 {
+        self.total.estimate()
+    }
+
+    /// Returns the estimated number of distinct series of a measurement,
+    /// if any of its Lines were observed.
+    pub fn measurement_series<S>(&self, measurement: S) -> Option<u64>
+    where
+        S: AsRef<str>,
+    {
+        let measurement = MeasurementName::new(measurement.as_ref()).ok()?;
+        self.measurements
+            .tracked
+            .get(&measurement)
+            .map(HyperLogLog::estimate)
+    }
+
+    /// Returns the estimated number of distinct values of a tag key,
+    /// if any Lines with the tag were observed.
+    pub fn tag_values<S>(&self, key: S) -> Option<u64>
+    where
+        S: AsRef<str>,
+    {
+        let key = KeyName::new(key.as_ref()).ok()?;
+        self.tag_keys.tracked.get(&key).map(HyperLogLog::estimate)
+    }
+
+    /// Returns the estimated number of distinct series of measurements
+    /// beyond [`Self::with_max_keys`], if any of their Lines were observed.
+    pub fn untracked_series(&self) -> Option<u64> {
+        self.measurements
+            .untracked
+            .as_ref()
+            .map(HyperLogLog::estimate)
+    }
+
+    /// Returns the estimated number of distinct values of tag keys
+    /// beyond [`Self::with_max_keys`] taken together, if any Lines with them were observed.
+    pub fn untracked_tag_values(&self) -> Option<u64> {
+        self.tag_keys.untracked.as_ref().map(HyperLogLog::estimate)
+    }
+
+    /// Returns an iterator over measurements and their estimated series,
+    /// ordered by measurement name.
+    pub fn measurements(&self) -> impl Iterator<Item = (&MeasurementName, u64)> {
+        self.measurements
+            .tracked
+            .iter()
+            .map(|(measurement, sketch)| (measurement, sketch.estimate()))
+    }
+
+    /// Returns an iterator over tag keys and their estimated distinct values,
+    /// ordered by key.
+    pub fn tag_keys(&self) -> impl Iterator<Item = (&KeyName, u64)> {
+        self.tag_keys
+            .tracked
+            .iter()
+            .map(|(key, sketch)| (key, sketch.estimate()))
+    }
+
+    /// Returns the standard error relative to the true count.
+    pub fn standard_error(&self) -> f64 {
+        1.04 / ((1_usize << self.precision) as f64).sqrt()
+    }
+
+    /// Adds everything observed by another estimator,
+    /// as if its Lines had been observed by this one.
+    ///
+    /// Keys of the other estimator beyond the limit of this one are merged as untracked.
+    ///
+    /// Fails with [`InfluxLineError::EstimatorMismatch`] if precisions differ.
+    pub fn merge(&mut self, other: &Self) -> Result<(), InfluxLineError> {
+        if self.precision != other.precision {
+            return Err(InfluxLineError::EstimatorMismatch);
+        }
+
+        self.total.merge(&other.total);
+        self.measurements
+            .merge(&other.measurements, self.max_keys, self.precision);
+        self.tag_keys
+            .merge(&other.tag_keys, self.max_keys, self.precision);
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone> Sketches<K> {
+    fn new() -> Self {
+        Self {
+            tracked: BTreeMap::new(),
+            untracked: None,
+        }
+    }
+
+    /// Returns the sketch of a key, or the shared one once the limit of keys is reached.
+    fn sketch(&mut self, key: &K, max_keys: usize, precision: u8) -> &mut HyperLogLog {
+        if self.tracked.contains_key(key) || self.tracked.len() < max_keys {
+            self.tracked
+                .entry(key.clone())
+                .or_insert_with(|| HyperLogLog::new(precision))
+        } else {
+            self.untracked
+                .get_or_insert_with(|| HyperLogLog::new(precision))
+        }
+    }
+
+    fn merge(&mut self, other: &Self, max_keys: usize, precision: u8) {
+        for (key, sketch) in other.tracked.iter() {
+            self.sketch(key, max_keys, precision).merge(sketch);
+        }
+        if let Some(sketch) = &other.untracked {
+            self.untracked
+                .get_or_insert_with(|| HyperLogLog::new(precision))
+                .merge(sketch);
+        }
+    }
+}
+
+impl Default for CardinalityEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HyperLogLog {
+    fn new(precision: u8) -> Self {
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    fn insert(&mut self, hash: u64) {
+        let index = (hash >> (64 - self.precision)) as usize;
+        // A sentinel bit keeps the rank within the bits left after the index.
+        let rest = (hash << self.precision) | (1 << (self.precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        for (register, other) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    fn estimate(&self) -> u64 {
+        let count = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / count),
+        };
+
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|register| 2_f64.powi(-i32::from(*register)))
+            .sum();
+        let raw = alpha * count * count / sum;
+
+        let zeros = self
+            .registers
+            .iter()
+            .filter(|register| **register == 0)
+            .count();
+        // Linear counting is more accurate while many registers are still empty.
+        let estimate = if raw <= 2.5 * count && zeros > 0 {
+            count * (count / zeros as f64).ln()
+        } else {
+            raw
+        };
+        estimate.round() as u64
+    }
+}
+
+/// Hashes the series key of a Line, i.e. its measurement and sorted tags.
+pub(crate) fn series_hash(line: &InfluxLine) -> u64 {
+    let mut tags: Vec<(&str, &str)> = line
+        .tags()
+        .map(|(key, value)| (key.as_ref(), value.as_ref()))
+        .collect();
+    tags.sort_unstable();
+
+    hash(&(line.measurement().as_ref(), &tags))
+}
+
+/// Hashes with fixed keys, so that sketches built separately agree on every value.
+pub(crate) fn hash<T>(value: &T) -> u64
+where
+    T: Hash + ?Sized,
+{
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CardinalityEstimator, InfluxLine, InfluxLineError};
+
+    fn line(measurement: &str, host: usize, region: usize) -> InfluxLine {
+        InfluxLine::try_new(measurement, "usage", 0.5)
+            .and_then(|line| line.try_with_tag("host", format!("web-{}", host)))
+            .and_then(|line| line.try_with_tag("region", format!("r-{}", region)))
+            .unwrap()
+    }
+
+    fn assert_close(expected: u64, actual: u64, estimator: &CardinalityEstimator) {
+        let tolerance = expected as f64 * 3.0 * estimator.standard_error();
+        assert!(
+            (expected as f64 - actual as f64).abs() <= tolerance.max(1.0),
+            "expected about {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::tiny(6, 50)]
+    #[case::default(12, 10)]
+    #[case::default_large(12, 100_000)]
+    #[case::fine(16, 100_000)]
+    fn estimates_within_error(#[case] precision: u8, #[case] hosts: usize) {
+        let mut estimator = CardinalityEstimator::new()
+            .try_with_precision(precision)
+            .unwrap();
+
+        for host in 0..hosts {
+            estimator.observe(&line("cpu", host, host % 4));
+            estimator.observe(&line("cpu", host, host % 4));
+        }
+
+        assert_close(hosts as u64, estimator.series(), &estimator);
+        assert_close(
+            hosts as u64,
+            estimator.tag_values("host").unwrap(),
+            &estimator,
+        );
+        assert_close(
+            4.min(hosts) as u64,
+            estimator.tag_values("region").unwrap(),
+            &estimator,
+        );
+    }
+
+    #[test]
+    fn tag_order_does_not_make_a_new_series() {
+        let mut estimator = CardinalityEstimator::new();
+        let reordered = InfluxLine::try_new("cpu", "usage", 0.5)
+            .and_then(|line| line.try_with_tag("region", "r-0"))
+            .and_then(|line| line.try_with_tag("host", "web-0"))
+            .unwrap();
+
+        estimator.observe(&line("cpu", 0, 0));
+        estimator.observe(&reordered);
+
+        assert_eq!(1, estimator.series());
+    }
+
+    #[test]
+    fn counts_per_measurement() {
+        let mut estimator = CardinalityEstimator::new();
+        for host in 0..100 {
+            estimator.observe(&line("cpu", host, 0));
+        }
+        for host in 0..10 {
+            estimator.observe(&line("mem", host, 0));
+        }
+
+        let measurements: Vec<_> = estimator
+            .measurements()
+            .map(|(measurement, series)| (measurement.as_ref().to_owned(), series))
+            .collect();
+
+        assert_eq!(
+            vec!["cpu", "mem"],
+            measurements.iter().map(|(m, _)| m).collect::<Vec<_>>()
+        );
+        assert_close(100, measurements[0].1, &estimator);
+        assert_close(10, measurements[1].1, &estimator);
+        assert_close(110, estimator.series(), &estimator);
+        assert_close(100, estimator.tag_values("host").unwrap(), &estimator);
+        assert_eq!(None, estimator.measurement_series("disk"));
+        assert_eq!(None, estimator.tag_values("zone"));
+    }
+
+    #[test]
+    fn merge_equals_observing_everything() {
+        let mut whole = CardinalityEstimator::new();
+        let mut left = CardinalityEstimator::new();
+        let mut right = CardinalityEstimator::new();
+        for host in 0..5000 {
+            let line = line(if host % 2 == 0 { "cpu" } else { "mem" }, host, host % 7);
+            whole.observe(&line);
+            if host % 3 == 0 {
+                left.observe(&line);
+            } else {
+                right.observe(&line);
+            }
+        }
+
+        left.merge(&right).unwrap();
+
+        assert_eq!(whole, left);
+    }
+
+    #[test]
+    fn limits_tracked_keys() {
+        let mut estimator = CardinalityEstimator::new().with_max_keys(2);
+        for measurement in 0..100 {
+            let line = InfluxLine::try_new(format!("m{}", measurement), "usage", 0.5)
+                .and_then(|line| line.try_with_tag(format!("k{}", measurement), "v"))
+                .unwrap();
+            estimator.observe(&line);
+        }
+
+        assert_eq!(2, estimator.measurements().count());
+        assert_eq!(2, estimator.tag_keys().count());
+        assert_eq!(None, estimator.measurement_series("m2"));
+        assert_close(98, estimator.untracked_series().unwrap(), &estimator);
+        assert_eq!(Some(1), estimator.untracked_tag_values());
+        assert_close(100, estimator.series(), &estimator);
+    }
+
+    #[test]
+    fn merges_keys_beyond_limit_as_untracked() {
+        let mut left = CardinalityEstimator::new().with_max_keys(1);
+        let mut right = CardinalityEstimator::new();
+        left.observe(&line("cpu", 0, 0));
+        right.observe(&line("mem", 0, 0));
+
+        left.merge(&right).unwrap();
+
+        assert_eq!(Some(1), left.measurement_series("cpu"));
+        assert_eq!(None, left.measurement_series("mem"));
+        assert_eq!(Some(1), left.untracked_series());
+        assert_eq!(2, left.series());
+    }
+
+    #[test]
+    fn merge_requires_same_precision() {
+        let mut left = CardinalityEstimator::new();
+        let right = CardinalityEstimator::new().try_with_precision(10).unwrap();
+
+        assert!(matches!(
+            left.merge(&right),
+            Err(InfluxLineError::EstimatorMismatch)
+        ));
+    }
+
+    #[rstest::rstest]
+    #[case::too_small(3)]
+    #[case::too_large(17)]
+    fn bad_precisions(#[case] precision: u8) {
+        assert!(matches!(
+            CardinalityEstimator::new().try_with_precision(precision),
+            Err(InfluxLineError::BadEstimatorPrecision)
+        ));
+    }
+}
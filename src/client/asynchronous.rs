@@ -0,0 +1,160 @@
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, Url};
+
+use crate::{InfluxLine, InfluxLineError};
+
+use super::{Outcome, WriteConfig};
+
+/// Writes batches of Lines to InfluxDB over HTTP asynchronously.
+///
+/// Behaves exactly like its blocking counterpart [`super::WriteClient`],
+/// but waits between retries with [`tokio::time::sleep`],
+/// so it must be used within a Tokio runtime with the timer enabled.
+#[derive(Debug, Clone)]
+pub struct AsyncWriteClient {
+    config: WriteConfig,
+    url: Url,
+    http: Client,
+}
+
+impl AsyncWriteClient {
+    /// Creates a client, validating the URL and the token.
+    pub fn new(config: WriteConfig) -> Result<Self, InfluxLineError> {
+        let http = Client::builder()
+            .default_headers(config.headers()?)
+            .build()?;
+        Ok(Self {
+            url: config.url()?,
+            config,
+            http,
+        })
+    }
+
+    /// Writes the whole batch, splitting it on `413 Payload Too Large`.
+    ///
+    /// See [`super::WriteClient::write`].
+    pub async fn write(&self, lines: &[InfluxLine]) -> Result<(), InfluxLineError> {
+        let lines = self.config.stamp(lines);
+        let mut pending = vec![(0, &lines[..])];
+
+        while let Some((offset, chunk)) = pending.pop() {
+            if chunk.is_empty() {
+                continue;
+            }
+
+            let body = self.config.encode(chunk)?;
+            let mut attempt = 0;
+            loop {
+                let response = self
+                    .http
+                    .post(self.url.clone())
+                    .body(body.clone())
+                    .send()
+                    .await?;
+                let status = response.status().as_u16();
+                let retry_after = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok());
+
+                match self.config.outcome(status, retry_after, attempt) {
+                    Outcome::Done => break,
+                    Outcome::Retry(delay) => {
+                        tokio::time::sleep(delay).await;
+                        attempt += 1;
+                    }
+                    Outcome::Split if chunk.len() > 1 => {
+                        let (left, right) = chunk.split_at(chunk.len() / 2);
+                        pending.push((offset + left.len(), right));
+                        pending.push((offset, left));
+                        break;
+                    }
+                    Outcome::Split | Outcome::Fail => {
+                        return Err(InfluxLineError::WriteRejected {
+                            status,
+                            body: response.text().await.unwrap_or_default(),
+                            lines: offset..offset + chunk.len(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::client::mock::{MockResponse, MockServer};
+    use crate::{AsyncWriteClient, InfluxLine, InfluxLineError, WriteConfig, WriteEndpoint};
+
+    fn lines(count: usize) -> Vec<InfluxLine> {
+        (0..count)
+            .map(|index| InfluxLine::try_new("cpu", "index", index as u64).unwrap())
+            .collect()
+    }
+
+    fn client(server: &MockServer) -> AsyncWriteClient {
+        let config = WriteConfig::new(
+            server.url(),
+            WriteEndpoint::V2 {
+                org: "org".into(),
+                bucket: "bucket".into(),
+            },
+        )
+        .with_gzip(true)
+        .with_backoff(Duration::from_millis(1), Duration::from_millis(1));
+        AsyncWriteClient::new(config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn writes_batches() {
+        let server = MockServer::start(|_| MockResponse::new(204));
+
+        client(&server).write(&lines(3)).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(1, requests.len());
+        assert_eq!(
+            "/api/v2/write?org=org&bucket=bucket&precision=ns",
+            requests[0].target
+        );
+        assert_eq!(
+            "cpu index=0u\ncpu index=1u\ncpu index=2u\n",
+            requests[0].text()
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_and_splits() {
+        let server = MockServer::start(|request| match request.sequence {
+            0 => MockResponse::new(503).with_header("Retry-After", "0"),
+            _ if request.text().lines().count() > 1 => MockResponse::new(413),
+            _ => MockResponse::new(204),
+        });
+
+        client(&server).write(&lines(2)).await.unwrap();
+
+        let sizes: Vec<_> = server
+            .requests()
+            .iter()
+            .map(|request| request.text().lines().count())
+            .collect();
+        assert_eq!(vec![2, 2, 1, 1], sizes);
+    }
+
+    #[tokio::test]
+    async fn single_line_too_large() {
+        let server = MockServer::start(|_| MockResponse::new(413));
+
+        let error = client(&server).write(&lines(1)).await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            InfluxLineError::WriteRejected { status: 413, .. }
+        ));
+    }
+}
@@ -0,0 +1,309 @@
+use std::thread;
+
+use reqwest::Url;
+use reqwest::blocking::Client;
+use reqwest::header::RETRY_AFTER;
+
+use crate::{InfluxLine, InfluxLineError};
+
+use super::{Outcome, WriteConfig};
+
+/// Writes batches of Lines to InfluxDB over HTTP, blocking the current thread.
+///
+/// See [`WriteConfig`] for the retry and batch splitting behavior,
+/// and [`super::AsyncWriteClient`] for the async counterpart.
+///
+/// Only plain HTTP is enabled by default.
+/// Enable one of the TLS features of `reqwest` in your own manifest to use HTTPS.
+#[derive(Debug, Clone)]
+pub struct WriteClient {
+    config: WriteConfig,
+    url: Url,
+    http: Client,
+}
+
+impl WriteClient {
+    /// Creates a client, validating the URL and the token.
+    pub fn new(config: WriteConfig) -> Result<Self, InfluxLineError> {
+        let http = Client::builder()
+            .default_headers(config.headers()?)
+            .build()?;
+        Ok(Self {
+            url: config.url()?,
+            config,
+            http,
+        })
+    }
+
+    /// Writes the whole batch, splitting it on `413 Payload Too Large`.
+    ///
+    /// Parts of a split batch are written in order.
+    /// If one of them fails, the following ones are not attempted,
+    /// while the preceding ones are already stored by the server.
+    ///
+    /// [`InfluxLineError::WriteRejected`] names the failed part of the batch,
+    /// so its body can be mapped onto the Lines with [`super::WriteRejection::parse_for_batch`].
+    pub fn write(&self, lines: &[InfluxLine]) -> Result<(), InfluxLineError> {
+        let lines = self.config.stamp(lines);
+        let mut pending = vec![(0, &lines[..])];
+
+        while let Some((offset, chunk)) = pending.pop() {
+            if chunk.is_empty() {
+                continue;
+            }
+
+            let body = self.config.encode(chunk)?;
+            let mut attempt = 0;
+            loop {
+                let response = self.http.post(self.url.clone()).body(body.clone()).send()?;
+                let status = response.status().as_u16();
+                let retry_after = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok());
+
+                match self.config.outcome(status, retry_after, attempt) {
+                    Outcome::Done => break,
+                    Outcome::Retry(delay) => {
+                        thread::sleep(delay);
+                        attempt += 1;
+                    }
+                    Outcome::Split if chunk.len() > 1 => {
+                        let (left, right) = chunk.split_at(chunk.len() / 2);
+                        pending.push((offset + left.len(), right));
+                        pending.push((offset, left));
+                        break;
+                    }
+                    Outcome::Split | Outcome::Fail => {
+                        return Err(InfluxLineError::WriteRejected {
+                            status,
+                            body: response.text().unwrap_or_default(),
+                            lines: offset..offset + chunk.len(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::client::mock::{MockResponse, MockServer};
+    use crate::{
+        InfluxLine, InfluxLineError, ManualClock, StampMode, Stamper, Timestamp, WriteClient,
+        WriteConfig, WriteEndpoint, WriteRejection,
+    };
+
+    fn lines(count: usize) -> Vec<InfluxLine> {
+        (0..count)
+            .map(|index| InfluxLine::try_new("cpu", "index", index as u64).unwrap())
+            .collect()
+    }
+
+    fn client(server: &MockServer, endpoint: WriteEndpoint) -> WriteClient {
+        let config = WriteConfig::new(server.url(), endpoint)
+            .with_token("secret")
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(1));
+        WriteClient::new(config).unwrap()
+    }
+
+    #[rstest::rstest]
+    #[case::v1(
+        WriteEndpoint::V1 { db: "db".into(), rp: Some("rp".into()) },
+        "/write?db=db&precision=ns&rp=rp",
+        "Token secret"
+    )]
+    #[case::v2(
+        WriteEndpoint::V2 { org: "org".into(), bucket: "bucket".into() },
+        "/api/v2/write?org=org&bucket=bucket&precision=ns",
+        "Token secret"
+    )]
+    #[case::v3(
+        WriteEndpoint::V3 { db: "db".into() },
+        "/api/v3/write_lp?db=db&precision=nanosecond",
+        "Bearer secret"
+    )]
+    fn writes_to_endpoints(
+        #[case] endpoint: WriteEndpoint,
+        #[case] expected_target: &str,
+        #[case] expected_authorization: &str,
+    ) {
+        let server = MockServer::start(|_| MockResponse::new(204));
+
+        client(&server, endpoint).write(&lines(2)).unwrap();
+
+        let requests = server.requests();
+        assert_eq!(1, requests.len());
+        assert_eq!("POST", requests[0].method);
+        assert_eq!(expected_target, requests[0].target);
+        assert_eq!(
+            Some(expected_authorization),
+            requests[0].header("authorization")
+        );
+        assert_eq!("cpu index=0u\ncpu index=1u\n", requests[0].text());
+    }
+
+    #[test]
+    fn compresses_with_gzip() {
+        let server = MockServer::start(|_| MockResponse::new(204));
+        let config =
+            WriteConfig::new(server.url(), WriteEndpoint::V3 { db: "db".into() }).with_gzip(true);
+
+        WriteClient::new(config).unwrap().write(&lines(1)).unwrap();
+
+        let requests = server.requests();
+        assert_eq!(Some("gzip"), requests[0].header("content-encoding"));
+        assert_eq!("cpu index=0u\n", requests[0].text());
+    }
+
+    #[test]
+    fn retries_throttled_requests() {
+        let server = MockServer::start(|request| {
+            if request.sequence < 2 {
+                MockResponse::new(429).with_header("Retry-After", "0")
+            } else {
+                MockResponse::new(204)
+            }
+        });
+
+        client(&server, WriteEndpoint::V3 { db: "db".into() })
+            .write(&lines(1))
+            .unwrap();
+
+        assert_eq!(3, server.requests().len());
+    }
+
+    #[test]
+    fn stamps_once_per_write() {
+        let clock = ManualClock::new(Timestamp::from(1));
+        let server = {
+            let clock = clock.clone();
+            MockServer::start(move |request| {
+                clock.advance(Duration::from_nanos(1));
+                match request.sequence {
+                    0 => MockResponse::new(503).with_header("Retry-After", "0"),
+                    1 => MockResponse::new(413),
+                    _ => MockResponse::new(204),
+                }
+            })
+        };
+        let config = WriteConfig::new(server.url(), WriteEndpoint::V3 { db: "db".into() })
+            .with_stamper(
+                Stamper::new()
+                    .with_clock(clock)
+                    .with_mode(StampMode::EachBatch),
+            );
+
+        WriteClient::new(config).unwrap().write(&lines(2)).unwrap();
+
+        let bodies: Vec<_> = server
+            .requests()
+            .iter()
+            .map(|request| request.text())
+            .collect();
+        assert_eq!(
+            vec![
+                "cpu index=0u 1\ncpu index=1u 1\n",
+                "cpu index=0u 1\ncpu index=1u 1\n",
+                "cpu index=0u 1\n",
+                "cpu index=1u 1\n",
+            ],
+            bodies
+        );
+    }
+
+    #[test]
+    fn gives_up_after_max_retries() {
+        let server = MockServer::start(|_| MockResponse::new(503));
+
+        let error = client(&server, WriteEndpoint::V3 { db: "db".into() })
+            .write(&lines(1))
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            InfluxLineError::WriteRejected { status: 503, .. }
+        ));
+        assert_eq!(4, server.requests().len());
+    }
+
+    #[test]
+    fn splits_too_large_batches() {
+        let server = MockServer::start(|request| {
+            if request.text().lines().count() > 1 {
+                MockResponse::new(413)
+            } else {
+                MockResponse::new(204)
+            }
+        });
+
+        client(&server, WriteEndpoint::V3 { db: "db".into() })
+            .write(&lines(4))
+            .unwrap();
+
+        let written: Vec<_> = server
+            .requests()
+            .iter()
+            .filter(|request| request.text().lines().count() == 1)
+            .map(|request| request.text())
+            .collect();
+        let expected: Vec<_> = lines(4).iter().map(|line| format!("{}\n", line)).collect();
+        assert_eq!(expected, written);
+        assert_eq!(7, server.requests().len());
+    }
+
+    #[test]
+    fn reports_rejections() {
+        let server = MockServer::start(|_| {
+            MockResponse::new(400).with_body(r#"{"code":"invalid","message":"bad line"}"#)
+        });
+
+        let error = client(&server, WriteEndpoint::V3 { db: "db".into() })
+            .write(&lines(1))
+            .unwrap_err();
+
+        match error {
+            InfluxLineError::WriteRejected {
+                status,
+                body,
+                lines,
+            } => {
+                assert_eq!(400, status);
+                assert!(body.contains("bad line"));
+                assert_eq!(0..1, lines);
+            }
+            other => panic!("Unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn maps_rejections_of_split_batches() {
+        let server = MockServer::start(|request| {
+            match request.text().lines().count() {
+            1 if request.text().starts_with("cpu index=2u") => MockResponse::new(400).with_body(
+                r#"{"error":"failed","data":[{"original_line":"cpu index=2u","line_number":1,"error_message":"bad"}]}"#,
+            ),
+            1 => MockResponse::new(204),
+            _ => MockResponse::new(413),
+        }
+        });
+        let batch = lines(4);
+
+        let error = client(&server, WriteEndpoint::V3 { db: "db".into() })
+            .write(&batch)
+            .unwrap_err();
+
+        let InfluxLineError::WriteRejected { body, lines, .. } = error else {
+            panic!("Unexpected error: {:?}", error);
+        };
+        assert_eq!(2..3, lines);
+        let rejections = WriteRejection::parse_for_batch(&body, &batch, lines);
+        assert_eq!(1, rejections.len());
+        assert_eq!(Some(2), rejections[0].line());
+    }
+}
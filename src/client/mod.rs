@@ -0,0 +1,325 @@
+mod asynchronous;
+mod blocking;
+#[cfg(test)]
+mod mock;
+mod rejection;
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use reqwest::Url;
+use reqwest::header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE, HeaderMap, HeaderValue};
+
+use crate::{InfluxLine, InfluxLineError, Stamper, WriteEndpoint};
+
+pub use self::asynchronous::AsyncWriteClient;
+pub use self::blocking::WriteClient;
+pub use self::rejection::{RejectionReason, WriteRejection};
+
+/// Settings shared by [`WriteClient`] and [`AsyncWriteClient`].
+///
+/// # Retries
+///
+/// Responses with `429 Too Many Requests` and `503 Service Unavailable` are retried
+/// up to [`Self::with_max_retries`] times.
+/// The delay is taken from the `Retry-After` header if there is one,
+/// and grows exponentially from [`Self::with_backoff`] otherwise.
+/// Either way it never exceeds the maximum backoff.
+///
+/// A `413 Payload Too Large` response makes the client split the batch in halves
+/// and write them one after another, until single Lines are left.
+///
+/// Timestamps are always sent with nanosecond precision,
+/// which is the precision of [`crate::Timestamp`].
+/// Lines without one are timestamped by the server on receipt,
+/// unless [`Self::with_stamper`] stamps them once per write,
+/// so that retries and split batches keep the same timestamps.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use influx_line::*;
+///
+/// let config = WriteConfig::new(
+///     "http://localhost:8086",
+///     WriteEndpoint::V2 { org: "acme".into(), bucket: "telemetry".into() },
+/// )
+/// .with_token("secret")
+/// .with_gzip(true)
+/// .with_max_retries(5)
+/// .with_backoff(Duration::from_millis(100), Duration::from_secs(10));
+///
+/// let client = WriteClient::new(config).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct WriteConfig {
+    base_url: String,
+    endpoint: WriteEndpoint,
+    token: Option<String>,
+    gzip: bool,
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    stamper: Option<Stamper>,
+}
+
+/// What to do after receiving a response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Outcome {
+    Done,
+    Retry(Duration),
+    Split,
+    Fail,
+}
+
+impl WriteConfig {
+    const DEFAULT_MAX_RETRIES: u32 = 3;
+    const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+    const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+    /// Creates a configuration for a server, e.g. `http://localhost:8086`.
+    pub fn new<S>(base_url: S, endpoint: WriteEndpoint) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            base_url: base_url.into(),
+            endpoint,
+            token: None,
+            gzip: false,
+            max_retries: Self::DEFAULT_MAX_RETRIES,
+            initial_backoff: Self::DEFAULT_INITIAL_BACKOFF,
+            max_backoff: Self::DEFAULT_MAX_BACKOFF,
+            stamper: None,
+        }
+    }
+
+    /// Authenticates requests with an API token.
+    pub fn with_token<S>(mut self, token: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.token.replace(token.into());
+        self
+    }
+
+    /// Compresses request bodies with gzip.
+    pub fn with_gzip(mut self, gzip: bool) -> Self {
+        self.gzip = gzip;
+        self
+    }
+
+    /// Limits the number of retries of a single request.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry and the upper bound for the following ones,
+    /// which also caps delays asked for by `Retry-After`.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Stamps Lines without a timestamp before a batch is first sent.
+    pub fn with_stamper(mut self, stamper: Stamper) -> Self {
+        self.stamper = Some(stamper);
+        self
+    }
+
+    fn url(&self) -> Result<Url, InfluxLineError> {
+        let base = self.base_url.trim_end_matches('/');
+        let (path, params): (&str, Vec<(&str, &str)>) = match &self.endpoint {
+            WriteEndpoint::V1 { db, rp } => {
+                let mut params = vec![("db", db.as_str()), ("precision", "ns")];
+                if let Some(rp) = rp {
+                    params.push(("rp", rp.as_str()));
+                }
+                ("/write", params)
+            }
+            WriteEndpoint::V2 { org, bucket } => (
+                "/api/v2/write",
+                vec![
+                    ("org", org.as_str()),
+                    ("bucket", bucket.as_str()),
+                    ("precision", "ns"),
+                ],
+            ),
+            WriteEndpoint::V3 { db } => (
+                "/api/v3/write_lp",
+                vec![("db", db.as_str()), ("precision", "nanosecond")],
+            ),
+        };
+
+        Url::parse_with_params(&format!("{}{}", base, path), params)
+            .map_err(|_| InfluxLineError::BadUrl)
+    }
+
+    fn headers(&self) -> Result<HeaderMap, InfluxLineError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; charset=utf-8"),
+        );
+        if self.gzip {
+            headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        }
+        if let Some(token) = &self.token {
+            let scheme = match self.endpoint {
+                WriteEndpoint::V3 { .. } => "Bearer",
+                _ => "Token",
+            };
+            let mut value = HeaderValue::from_str(&format!("{} {}", scheme, token))
+                .map_err(|_| InfluxLineError::BadHeader)?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+        Ok(headers)
+    }
+
+    fn stamp<'a>(&self, lines: &'a [InfluxLine]) -> Cow<'a, [InfluxLine]> {
+        match &self.stamper {
+            Some(stamper) if lines.iter().any(|line| line.timestamp().is_none()) => {
+                Cow::Owned(stamper.stamp_batch(lines.iter().cloned()))
+            }
+            _ => Cow::Borrowed(lines),
+        }
+    }
+
+    fn encode(&self, lines: &[InfluxLine]) -> Result<Vec<u8>, InfluxLineError> {
+        let mut payload = String::new();
+        for line in lines {
+            // Writing to a String never fails.
+            let _ = write!(payload, "{}", line);
+            if !payload.ends_with('\n') {
+                payload.push('\n');
+            }
+        }
+
+        if !self.gzip {
+            return Ok(payload.into_bytes());
+        }
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(payload.as_bytes())?;
+        Ok(encoder.finish()?)
+    }
+
+    fn outcome(&self, status: u16, retry_after: Option<&str>, attempt: u32) -> Outcome {
+        match status {
+            200..=299 => Outcome::Done,
+            413 => Outcome::Split,
+            429 | 503 if attempt < self.max_retries => Outcome::Retry(
+                retry_after
+                    .and_then(parse_retry_after)
+                    .map(|delay| delay.min(self.max_backoff))
+                    .unwrap_or_else(|| self.backoff(attempt)),
+            ),
+            _ => Outcome::Fail,
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+/// `Retry-After` is either a number of seconds or an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Outcome, WriteConfig, parse_retry_after};
+    use crate::WriteEndpoint;
+
+    fn config(endpoint: WriteEndpoint) -> WriteConfig {
+        WriteConfig::new("http://localhost:8086/", endpoint)
+    }
+
+    #[rstest::rstest]
+    #[case::v1(
+        WriteEndpoint::V1 { db: "telemetry".into(), rp: None },
+        "http://localhost:8086/write?db=telemetry&precision=ns"
+    )]
+    #[case::v1_with_rp(
+        WriteEndpoint::V1 { db: "telemetry".into(), rp: Some("one week".into()) },
+        "http://localhost:8086/write?db=telemetry&precision=ns&rp=one+week"
+    )]
+    #[case::v2(
+        WriteEndpoint::V2 { org: "acme & co".into(), bucket: "b".into() },
+        "http://localhost:8086/api/v2/write?org=acme+%26+co&bucket=b&precision=ns"
+    )]
+    #[case::v3(
+        WriteEndpoint::V3 { db: "telemetry".into() },
+        "http://localhost:8086/api/v3/write_lp?db=telemetry&precision=nanosecond"
+    )]
+    fn urls(#[case] endpoint: WriteEndpoint, #[case] expected_url: &str) {
+        assert_eq!(expected_url, config(endpoint).url().unwrap().as_str());
+    }
+
+    #[rstest::rstest]
+    #[case::success(204, None, 0, Outcome::Done)]
+    #[case::too_large(413, None, 0, Outcome::Split)]
+    #[case::bad_request(400, None, 0, Outcome::Fail)]
+    #[case::throttled(429, Some("0"), 0, Outcome::Retry(Duration::ZERO))]
+    #[case::retry_after_is_capped(
+        429,
+        Some("86400"),
+        0,
+        Outcome::Retry(Duration::from_millis(500))
+    )]
+    #[case::far_retry_date_is_capped(
+        503,
+        Some("Fri, 31 Dec 9999 23:59:59 GMT"),
+        0,
+        Outcome::Retry(Duration::from_millis(500))
+    )]
+    #[case::unavailable(503, None, 0, Outcome::Retry(Duration::from_millis(100)))]
+    #[case::backoff_grows(503, None, 2, Outcome::Retry(Duration::from_millis(400)))]
+    #[case::backoff_is_capped(503, None, 3, Outcome::Retry(Duration::from_millis(500)))]
+    #[case::retries_exhausted(503, None, 4, Outcome::Fail)]
+    fn outcomes(
+        #[case] status: u16,
+        #[case] retry_after: Option<&str>,
+        #[case] attempt: u32,
+        #[case] expected: Outcome,
+    ) {
+        let config = config(WriteEndpoint::V3 { db: "db".into() })
+            .with_max_retries(4)
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(500));
+
+        assert_eq!(expected, config.outcome(status, retry_after, attempt));
+    }
+
+    #[rstest::rstest]
+    #[case::seconds("120", Some(Duration::from_secs(120)))]
+    #[case::past_date("Wed, 21 Oct 2015 07:28:00 GMT", Some(Duration::ZERO))]
+    #[case::gibberish("soon", None)]
+    fn retry_after(#[case] value: &str, #[case] expected: Option<Duration>) {
+        assert_eq!(expected, parse_retry_after(value));
+    }
+}
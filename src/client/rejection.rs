@@ -0,0 +1,496 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::{InfluxLine, InfluxValueType};
+
+/// Why InfluxDB refused to store a point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectionReason {
+    /// A field already exists with another type.
+    TypeConflict,
+    /// The Line is malformed.
+    ParseError,
+    /// The timestamp is outside of the retention period.
+    OutsideRetention,
+    /// The Line or the whole request exceeds a size limit.
+    TooLarge,
+    Other,
+}
+
+/// A single rejection reported in an InfluxDB write error response.
+///
+/// InfluxDB v1 and v2 describe rejections in free text, e.g.
+/// `partial write: field type conflict: input field "usage" on measurement "cpu" is type integer, already exists as type float dropped=1`
+/// or `unable to parse 'cpu usage=': missing field value`,
+/// while InfluxDB v3 lists them in a structured `data` array.
+/// Both kinds are recognized by [`Self::parse`].
+///
+/// [`Self::parse_for_batch`] additionally maps the rejections onto the submitted batch,
+/// so that only the bad Lines are quarantined and the rest is retried.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let batch = vec![
+///     InfluxLine::try_new("cpu", "usage", 0.5).unwrap(),
+///     InfluxLine::try_new("cpu", "usage", 1).unwrap(),
+/// ];
+/// let body = r#"{"code":"invalid","message":"partial write: field type conflict: input field \"usage\" on measurement \"cpu\" is type integer, already exists as type float dropped=1"}"#;
+///
+/// let rejections = WriteRejection::parse_for_batch(body, &batch, 0..batch.len());
+///
+/// assert_eq!(1, rejections.len());
+/// assert_eq!(RejectionReason::TypeConflict, rejections[0].reason());
+/// assert_eq!(Some(1), rejections[0].line());
+/// assert_eq!(Some("usage"), rejections[0].field());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteRejection {
+    reason: RejectionReason,
+    line: Option<usize>,
+    text: Option<String>,
+    measurement: Option<String>,
+    field: Option<String>,
+    value_type: Option<InfluxValueType>,
+    message: String,
+}
+
+impl WriteRejection {
+    /// Extracts rejections from a response body.
+    ///
+    /// Line indices are only known if the server reports line numbers.
+    pub fn parse(body: &str) -> Vec<Self> {
+        let Ok(json) = serde_json::from_str::<Value>(body) else {
+            return parse_message(body);
+        };
+
+        let mut rejections = Vec::new();
+        if let Some(data) = json.get("data").and_then(Value::as_array) {
+            for entry in data {
+                let message = entry
+                    .get("error_message")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                let mut rejection = classify(message);
+                if rejection.reason == RejectionReason::Other {
+                    rejection.reason = RejectionReason::ParseError;
+                }
+                rejection.line = entry
+                    .get("line_number")
+                    .and_then(Value::as_u64)
+                    .and_then(|number| usize::try_from(number).ok()?.checked_sub(1));
+                rejection.text = entry
+                    .get("original_line")
+                    .and_then(Value::as_str)
+                    .map(str::to_owned);
+                rejections.push(rejection);
+            }
+        }
+        if !rejections.is_empty() {
+            return rejections;
+        }
+
+        let message = ["message", "error"]
+            .iter()
+            .find_map(|key| json.get(key).and_then(Value::as_str))
+            .unwrap_or_default();
+        let mut rejections = parse_message(message);
+        if json.get("code").and_then(Value::as_str) == Some("request too large") {
+            for rejection in rejections.iter_mut() {
+                rejection.reason = RejectionReason::TooLarge;
+            }
+        }
+        rejections
+    }
+
+    /// Extracts rejections from a response body and points them at Lines of the batch.
+    ///
+    /// `lines` is the part of the batch the rejected request carried,
+    /// as reported by [`crate::InfluxLineError::WriteRejected`],
+    /// and returned line indices are relative to the whole batch.
+    ///
+    /// - Reported line numbers are counted from the start of that part,
+    ///   since it was sent one Line per row.
+    ///   If the server also quotes the Line, the number is only trusted if the text matches.
+    /// - Unparsable Lines quoted by the server are looked up by their text within the part,
+    ///   each rejection taking the next Line with that text not claimed yet.
+    ///   Lines without a timestamp also match the text with one appended,
+    ///   as the client may stamp them before sending.
+    /// - A type conflict is reported once per point,
+    ///   so it is expanded to every Line of the part writing the field of the measurement
+    ///   with the conflicting type.
+    ///
+    /// Rejections that cannot be attributed to specific Lines,
+    /// e.g. points outside of retention, are kept without a line index.
+    pub fn parse_for_batch(body: &str, batch: &[InfluxLine], lines: Range<usize>) -> Vec<Self> {
+        let lines = lines.start.min(batch.len())..lines.end.min(batch.len());
+        let texts: Vec<String> = batch[lines.clone()]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let matches = |index: usize, text: &str| {
+            let line = &texts[index - lines.start];
+            line == text
+                || batch[index].timestamp().is_none()
+                    && text.rsplit_once(' ').is_some_and(|(head, timestamp)| {
+                        head == line && i64::from_str(timestamp).is_ok()
+                    })
+        };
+
+        let mut claimed = vec![false; batch.len()];
+        let mut located = Vec::new();
+        for mut rejection in Self::parse(body) {
+            rejection.line = rejection
+                .line
+                .and_then(|line| line.checked_add(lines.start))
+                .filter(|line| lines.contains(line))
+                .filter(|&line| {
+                    rejection
+                        .text
+                        .as_deref()
+                        .is_none_or(|text| matches(line, text))
+                });
+            if let Some(line) = rejection.line {
+                claimed[line] = true;
+                located.push(rejection);
+                continue;
+            }
+
+            let indices: Vec<usize> =
+                match (&rejection.text, &rejection.measurement, &rejection.field) {
+                    (Some(text), _, _) => {
+                        let mut candidates = lines.clone().filter(|&index| matches(index, text));
+                        let first = candidates.clone().next();
+                        candidates
+                            .find(|&index| !claimed[index])
+                            .or(first)
+                            .into_iter()
+                            .collect()
+                    }
+                    (None, Some(measurement), Some(field))
+                        if rejection.reason == RejectionReason::TypeConflict =>
+                    {
+                        lines
+                            .clone()
+                            .filter(|&index| {
+                                batch[index].measurement().as_str() == measurement.as_str()
+                            })
+                            .filter(|&index| {
+                                batch[index].field(field.as_str()).is_some_and(|value| {
+                                    rejection
+                                        .value_type
+                                        .is_none_or(|value_type| value.value_type() == value_type)
+                                })
+                            })
+                            .collect()
+                    }
+                    _ => Vec::new(),
+                };
+
+            if indices.is_empty() {
+                located.push(rejection);
+                continue;
+            }
+            for index in indices {
+                claimed[index] = true;
+                let mut copy = rejection.clone();
+                copy.line = Some(index);
+                located.push(copy);
+            }
+        }
+        located
+    }
+
+    pub fn reason(&self) -> RejectionReason {
+        self.reason
+    }
+
+    /// The index of the rejected Line within the batch, starting from 0.
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// The measurement of the rejected point, if the server mentions it.
+    pub fn measurement(&self) -> Option<&str> {
+        self.measurement.as_deref()
+    }
+
+    /// The affected field key, if the server mentions it.
+    pub fn field(&self) -> Option<&str> {
+        self.field.as_deref()
+    }
+
+    /// The server's own description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Splits a free-text message into rejections, one per line of text.
+fn parse_message(message: &str) -> Vec<WriteRejection> {
+    let mut rejections = Vec::new();
+    for row in message.lines() {
+        let mut row = row.trim();
+        for prefix in ["partial write:", "failed to parse line protocol:"] {
+            row = row.strip_prefix(prefix).unwrap_or(row).trim_start();
+        }
+        if row.is_empty() || row.starts_with("errors encountered on line") {
+            continue;
+        }
+
+        let mut line = None;
+        if let Some((number, rest)) = row
+            .strip_prefix("line ")
+            .and_then(|rest| rest.split_once(':'))
+            && let Ok(number) = usize::from_str(number.trim())
+        {
+            line = number.checked_sub(1);
+            row = rest.trim_start();
+        }
+
+        let mut rejection = classify(row);
+        rejection.line = line;
+        rejections.push(rejection);
+    }
+    rejections
+}
+
+/// Recognizes a single rejection message.
+fn classify(message: &str) -> WriteRejection {
+    let message = match message.rfind(" dropped=") {
+        Some(position) => &message[..position],
+        None => message,
+    }
+    .trim();
+    let contains = |needle: &str| find_ignoring_case(message, needle).is_some();
+
+    let mut rejection = WriteRejection {
+        reason: RejectionReason::Other,
+        line: None,
+        text: None,
+        measurement: None,
+        field: None,
+        value_type: None,
+        message: message.to_owned(),
+    };
+
+    if contains("field type conflict") {
+        rejection.reason = RejectionReason::TypeConflict;
+        rejection.field = quoted_after(message, "input field \"");
+        rejection.measurement = quoted_after(message, "on measurement \"");
+        rejection.value_type = message
+            .split_once("is type ")
+            .and_then(|(_, rest)| rest.split([',', ' ']).next())
+            .and_then(|name| InfluxValueType::from_str(name).ok());
+    } else if contains("invalid column type for column '") {
+        rejection.reason = RejectionReason::TypeConflict;
+        rejection.field = message
+            .split_once("column '")
+            .and_then(|(_, rest)| rest.split_once('\''))
+            .map(|(field, _)| field.to_owned());
+        rejection.value_type = message
+            .rsplit_once("got ")
+            .and_then(|(_, rest)| rest.rsplit("::").next())
+            .and_then(|name| InfluxValueType::from_str(name.trim()).ok());
+    } else if let Some(start) = find_ignoring_case(message, "unable to parse '") {
+        rejection.reason = RejectionReason::ParseError;
+        let quoted = &message[start + "unable to parse '".len()..];
+        rejection.text = quoted
+            .rfind("': ")
+            .or_else(|| quoted.strip_suffix('\'').map(str::len))
+            .map(|end| quoted[..end].to_owned());
+    } else if contains("retention") {
+        rejection.reason = RejectionReason::OutsideRetention;
+    } else if contains("too large") || contains("exceeds") {
+        rejection.reason = RejectionReason::TooLarge;
+    }
+    rejection
+}
+
+/// Finds an ASCII needle regardless of its case,
+/// returning a position within the haystack itself,
+/// which lowercasing would shift for some non-ASCII characters.
+fn find_ignoring_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .char_indices()
+        .map(|(start, _)| start)
+        .find(|&start| {
+            haystack.as_bytes()[start..]
+                .get(..needle.len())
+                .is_some_and(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+        })
+}
+
+/// Takes the text between a prefix ending with a quote and the closing quote.
+fn quoted_after(message: &str, prefix: &str) -> Option<String> {
+    let (_, rest) = message.split_once(prefix)?;
+    let (quoted, _) = rest.split_once('"')?;
+    Some(quoted.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InfluxLine, RejectionReason, WriteRejection};
+
+    fn batch() -> Vec<InfluxLine> {
+        vec![
+            InfluxLine::try_new("cpu", "usage", 0.5).unwrap(),
+            InfluxLine::try_new("cpu", "usage", 1).unwrap(),
+            InfluxLine::try_new("mem", "usage", 1).unwrap(),
+            InfluxLine::try_new("cpu", "usage", 2).unwrap(),
+        ]
+    }
+
+    fn summary(
+        rejections: &[WriteRejection],
+    ) -> Vec<(RejectionReason, Option<usize>, Option<&str>)> {
+        rejections
+            .iter()
+            .map(|rejection| (rejection.reason(), rejection.line(), rejection.field()))
+            .collect()
+    }
+
+    #[test]
+    fn type_conflicts() {
+        let body = r#"{"code":"invalid","message":"partial write: field type conflict: input field \"usage\" on measurement \"cpu\" is type integer, already exists as type float dropped=2"}"#;
+
+        let rejections = WriteRejection::parse_for_batch(body, &batch(), 0..4);
+
+        assert_eq!(
+            vec![
+                (RejectionReason::TypeConflict, Some(1), Some("usage")),
+                (RejectionReason::TypeConflict, Some(3), Some("usage")),
+            ],
+            summary(&rejections)
+        );
+        assert_eq!(Some("cpu"), rejections[0].measurement());
+        assert!(!rejections[0].message().contains("dropped"));
+    }
+
+    #[test]
+    fn numbered_parse_errors() {
+        let body = r#"{"code":"invalid","message":"failed to parse line protocol:\nerrors encountered on line(s):\nline 2: Unable to parse 'cpu usage=1i': bad\nline 4: Unable to parse 'cpu usage=2i': bad"}"#;
+
+        let rejections = WriteRejection::parse_for_batch(body, &batch(), 0..4);
+
+        assert_eq!(
+            vec![
+                (RejectionReason::ParseError, Some(1), None),
+                (RejectionReason::ParseError, Some(3), None),
+            ],
+            summary(&rejections)
+        );
+    }
+
+    #[test]
+    fn quoted_parse_errors() {
+        let body = r#"{"error":"unable to parse 'mem usage=1i': invalid field format\nunable to parse 'disk free=': missing field value"}"#;
+
+        let rejections = WriteRejection::parse_for_batch(body, &batch(), 0..4);
+
+        assert_eq!(
+            vec![
+                (RejectionReason::ParseError, Some(2), None),
+                (RejectionReason::ParseError, None, None),
+            ],
+            summary(&rejections)
+        );
+    }
+
+    #[test]
+    fn duplicate_and_stamped_texts() {
+        let batch = vec![
+            InfluxLine::try_new("cpu", "usage", 1).unwrap(),
+            InfluxLine::try_new("cpu", "usage", 1).unwrap(),
+            InfluxLine::try_new("cpu", "usage", 2).unwrap(),
+        ];
+        let body = r#"{"error":"unable to parse 'cpu usage=1i 5': bad\nunable to parse 'cpu usage=1i 5': bad\nunable to parse 'cpu usage=2i': bad"}"#;
+
+        let rejections = WriteRejection::parse_for_batch(body, &batch, 0..3);
+
+        assert_eq!(
+            vec![
+                (RejectionReason::ParseError, Some(0), None),
+                (RejectionReason::ParseError, Some(1), None),
+                (RejectionReason::ParseError, Some(2), None),
+            ],
+            summary(&rejections)
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::numbered(
+        r#"{"code":"invalid","message":"failed to parse line protocol:\nerrors encountered on line(s):\nline 1: Unable to parse 'mem usage=1i': bad\nline 2: Unable to parse 'cpu usage=2i': bad"}"#,
+        vec![
+            (RejectionReason::ParseError, Some(2), None),
+            (RejectionReason::ParseError, Some(3), None),
+        ]
+    )]
+    #[case::number_of_another_line(
+        r#"{"error":"failed","data":[{"original_line":"cpu usage=2i","line_number":1,"error_message":"bad"}]}"#,
+        vec![(RejectionReason::ParseError, Some(3), None)]
+    )]
+    #[case::type_conflict(
+        r#"{"code":"invalid","message":"partial write: field type conflict: input field \"usage\" on measurement \"cpu\" is type integer, already exists as type float dropped=1"}"#,
+        vec![(RejectionReason::TypeConflict, Some(3), Some("usage"))]
+    )]
+    fn parts_of_split_batches(
+        #[case] body: &str,
+        #[case] expected: Vec<(RejectionReason, Option<usize>, Option<&str>)>,
+    ) {
+        let rejections = WriteRejection::parse_for_batch(body, &batch(), 2..4);
+
+        assert_eq!(expected, summary(&rejections));
+    }
+
+    #[test]
+    fn v3_data() {
+        let body = r#"{"error":"parsing failed for write_lp endpoint","data":[{"original_line":"cpu usage=1i","line_number":2,"error_message":"invalid column type for column 'usage', expected iox::column_type::field::float, got iox::column_type::field::integer"}]}"#;
+
+        let rejections = WriteRejection::parse(body);
+
+        assert_eq!(
+            vec![(RejectionReason::TypeConflict, Some(1), Some("usage"))],
+            summary(&rejections)
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::retention(
+        r#"{"error":"partial write: points beyond retention policy dropped=3"}"#,
+        RejectionReason::OutsideRetention
+    )]
+    #[case::v2_retention(
+        r#"{"code":"unprocessable entity","message":"failure writing points to database: partial write: points beyond retention policy dropped=1"}"#,
+        RejectionReason::OutsideRetention
+    )]
+    #[case::too_large(
+        r#"{"code":"request too large","message":"unable to read data: points batch is too large"}"#,
+        RejectionReason::TooLarge
+    )]
+    #[case::plain_text("Request Entity Too Large", RejectionReason::TooLarge)]
+    #[case::unknown(
+        r#"{"code":"unauthorized","message":"unauthorized access"}"#,
+        RejectionReason::Other
+    )]
+    fn reasons(#[case] body: &str, #[case] expected: RejectionReason) {
+        let rejections = WriteRejection::parse_for_batch(body, &batch(), 0..4);
+
+        assert_eq!(vec![(expected, None, None)], summary(&rejections));
+    }
+
+    #[rstest::rstest]
+    #[case::growing_when_lowercased("İ unable to parse 'éx=1': bad", "éx=1")]
+    #[case::before_the_text("İİ Unable to parse 'cpu é x=1': bad", "cpu é x=1")]
+    fn non_ascii_messages(#[case] body: &str, #[case] expected: &str) {
+        let rejections = WriteRejection::parse(body);
+
+        assert_eq!(1, rejections.len());
+        assert_eq!(RejectionReason::ParseError, rejections[0].reason());
+        assert_eq!(Some(expected), rejections[0].text.as_deref());
+    }
+}
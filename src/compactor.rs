@@ -0,0 +1,227 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::cardinality::series_hash;
+use crate::{InfluxLine, Timestamp};
+
+/// Merges duplicate points, i.e. Lines of the same series and timestamp,
+/// as InfluxDB would on write, see [`InfluxLine::merge`].
+///
+/// Only the last [`Self::with_window`] distinct points are held,
+/// so duplicates further apart than that are left to the database.
+/// A merged point keeps the position of its first occurrence.
+///
+/// Lines without a timestamp are only merged within a single [`Self::compact`] call,
+/// which is expected to make up one write request,
+/// since the server stamps separate requests with different times.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let lines: Vec<InfluxLine> = [
+///     "cpu,host=a usage=0.5 10",
+///     "cpu,host=b usage=0.1 10",
+///     "cpu,host=a usage=0.7,cores=4i 10",
+///     "cpu,host=a usage=0.9 20",
+/// ]
+/// .into_iter()
+/// .map(|line| line.parse().unwrap())
+/// .collect();
+///
+/// let mut compactor = Compactor::new();
+/// let compacted: Vec<String> = compactor.compact(lines).iter().map(ToString::to_string).collect();
+///
+/// assert_eq!(
+///     vec![
+///         "cpu,host=a usage=0.7,cores=4i 10",
+///         "cpu,host=b usage=0.1 10",
+///         "cpu,host=a usage=0.9 20",
+///     ],
+///     compacted
+/// );
+/// assert_eq!(1, compactor.merged());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Compactor {
+    window: usize,
+    pending: VecDeque<InfluxLine>,
+    /// Sequence numbers of pending points by their series and timestamp.
+    index: HashMap<(u64, Option<Timestamp>), u64>,
+    /// Sequence number of the oldest pending point.
+    first: u64,
+    merged: usize,
+}
+
+impl Compactor {
+    pub const DEFAULT_WINDOW: usize = 10_000;
+
+    /// Creates a compactor holding up to [`Self::DEFAULT_WINDOW`] points.
+    pub fn new() -> Self {
+        Self {
+            window: Self::DEFAULT_WINDOW,
+            pending: VecDeque::new(),
+            index: HashMap::new(),
+            first: 0,
+            merged: 0,
+        }
+    }
+
+    /// Limits the number of distinct points held, at least one.
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Merges a Line into a pending point of the same series and timestamp,
+    /// or adds it as a new one.
+    /// Returns the oldest point once more than the window is pending.
+    ///
+    /// Lines without a timestamp are never merged here,
+    /// as they may end up in different write requests.
+    pub fn add(&mut self, line: InfluxLine) -> Option<InfluxLine> {
+        self.add_point(line, false)
+    }
+
+    /// Adds Lines and returns every point in the order of first occurrence,
+    /// leaving the compactor empty.
+    ///
+    /// Lines without a timestamp are merged with each other as well,
+    /// as long as they are of the same series.
+    pub fn compact<I>(&mut self, lines: I) -> Vec<InfluxLine>
+    where
+        I: IntoIterator<Item = InfluxLine>,
+    {
+        let mut compacted: Vec<InfluxLine> = lines
+            .into_iter()
+            .filter_map(|line| self.add_point(line, true))
+            .collect();
+        compacted.extend(self.flush());
+        compacted
+    }
+
+    /// Returns every pending point in the order of first occurrence.
+    pub fn flush(&mut self) -> Vec<InfluxLine> {
+        self.first += self.pending.len() as u64;
+        self.index.clear();
+        self.pending.drain(..).collect()
+    }
+
+    /// Returns the number of Lines merged into earlier ones so far.
+    pub fn merged(&self) -> usize {
+        self.merged
+    }
+
+    /// Lines without a timestamp are not indexed unless `untimestamped` is set,
+    /// so nothing is merged into them.
+    fn add_point(&mut self, line: InfluxLine, untimestamped: bool) -> Option<InfluxLine> {
+        if untimestamped || line.timestamp().is_some() {
+            let key = (series_hash(&line), line.timestamp());
+            if let Some(sequence) = self.index.get(&key) {
+                let existing = &mut self.pending[(sequence - self.first) as usize];
+                // Otherwise, it is a hash collision, and the Line takes over the index entry.
+                if existing.is_same_point(&line) {
+                    existing.merge(line).expect("Lines are of the same point");
+                    self.merged += 1;
+                    return None;
+                }
+            }
+
+            self.index
+                .insert(key, self.first + self.pending.len() as u64);
+        }
+
+        self.pending.push_back(line);
+        if self.pending.len() > self.window {
+            return self.evict();
+        }
+        None
+    }
+
+    fn evict(&mut self) -> Option<InfluxLine> {
+        let line = self.pending.pop_front()?;
+        let key = (series_hash(&line), line.timestamp());
+        if self.index.get(&key) == Some(&self.first) {
+            self.index.remove(&key);
+        }
+        self.first += 1;
+        Some(line)
+    }
+}
+
+impl Default for Compactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Compactor, InfluxLine};
+
+    fn lines(source: &str) -> Vec<InfluxLine> {
+        source
+            .lines()
+            .map(|line| line.trim().parse().expect("Must parse here"))
+            .collect()
+    }
+
+    #[rstest::rstest]
+    #[case::no_duplicates(
+        10,
+        "m,a=1 x=1i 1\n m,a=2 x=1i 1\n m,a=1 x=1i 2",
+        "m,a=1 x=1i 1\n m,a=2 x=1i 1\n m,a=1 x=1i 2"
+    )]
+    #[case::last_write_wins(10, "m x=1i,y=1i 1\n m x=2i 1\n m x=3i 1", "m x=3i,y=1i 1")]
+    #[case::without_timestamps(10, "m x=1i\n m y=2i", "m x=1i,y=2i")]
+    #[case::reordered_tags(10, "m,a=1,b=2 x=1i 1\n m,b=2,a=1 y=2i 1", "m,a=1,b=2 x=1i,y=2i 1")]
+    #[case::within_window(
+        2,
+        "m,a=1 x=1i 1\n m,a=2 x=1i 1\n m,a=1 x=2i 1",
+        "m,a=1 x=2i 1\n m,a=2 x=1i 1"
+    )]
+    #[case::beyond_window(
+        1,
+        "m,a=1 x=1i 1\n m,a=2 x=1i 1\n m,a=1 x=2i 1",
+        "m,a=1 x=1i 1\n m,a=2 x=1i 1\n m,a=1 x=2i 1"
+    )]
+    fn compacts(#[case] window: usize, #[case] input: &str, #[case] expected: &str) {
+        let mut compactor = Compactor::new().with_window(window);
+
+        assert_eq!(lines(expected), compactor.compact(lines(input)));
+    }
+
+    #[test]
+    fn keeps_indexing_after_eviction() {
+        let mut compactor = Compactor::new().with_window(2);
+
+        let evicted: Vec<_> = lines("m,a=1 x=1i 1\n m,a=2 x=1i 1\n m,a=3 x=1i 1\n m,a=3 x=2i 1")
+            .into_iter()
+            .filter_map(|line| compactor.add(line))
+            .collect();
+
+        assert_eq!(lines("m,a=1 x=1i 1"), evicted);
+        assert_eq!(lines("m,a=2 x=1i 1\n m,a=3 x=2i 1"), compactor.flush());
+        assert_eq!(1, compactor.merged());
+
+        assert_eq!(None, compactor.add(lines("m,a=3 x=3i 1").remove(0)));
+        assert_eq!(lines("m,a=3 x=3i 1"), compactor.flush());
+    }
+
+    #[test]
+    fn merges_untimestamped_lines_within_compact() {
+        let mut compactor = Compactor::new();
+
+        assert_eq!(None, compactor.add(lines("m x=1i").remove(0)));
+        assert_eq!(None, compactor.add(lines("m y=2i").remove(0)));
+        assert_eq!(lines("m x=1i\n m y=2i"), compactor.flush());
+        assert_eq!(0, compactor.merged());
+
+        compactor.add(lines("m x=1i").remove(0));
+        assert_eq!(
+            lines("m x=1i\n m y=2i,z=3i"),
+            compactor.compact(lines("m y=2i\n m z=3i"))
+        );
+        assert_eq!(1, compactor.merged());
+    }
+}
@@ -0,0 +1,420 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+use crate::{InfluxLine, InfluxLineError, Precision};
+
+/// Compression of a Line Protocol stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// Writes newline-terminated Lines straight into a possibly compressed stream.
+///
+/// Nothing but the compressor's own window is buffered,
+/// so batches of any size can be encoded to a file or a request body.
+/// Call [`Self::finish`] to write the compression trailer,
+/// otherwise the output is truncated.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let mut encoder = LineEncoder::new(Vec::new(), Compression::Zstd).unwrap();
+/// encoder.write_line(&InfluxLine::try_new("cpu", "usage", 0.5).unwrap()).unwrap();
+/// let compressed = encoder.finish().unwrap();
+///
+/// let lines: Vec<_> = LineReader::new(compressed.as_slice())
+///     .unwrap()
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(vec![InfluxLine::try_new("cpu", "usage", 0.5).unwrap()], lines);
+/// ```
+pub struct LineEncoder<W: Write> {
+    stream: EncoderStream<W>,
+}
+
+enum EncoderStream<W: Write> {
+    None(W),
+    Gzip(GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+/// Reads Lines one by one from a possibly compressed stream.
+///
+/// Compression is detected by the magic bytes at the start of the stream,
+/// so `.lp`, `.lp.gz` and `.lp.zst` files are all read the same way,
+/// even if they are misnamed.
+/// Blank lines and `#` comments are skipped.
+///
+/// Yields an error for every malformed Line and keeps going,
+/// but stops after the first I/O or decompression error.
+pub struct LineReader<'a> {
+    input: Box<dyn BufRead + 'a>,
+    precision: Precision,
+    buffer: String,
+    line_number: usize,
+    failed: bool,
+}
+
+impl Compression {
+    const GZIP_MAGIC: &'static [u8] = &[0x1f, 0x8b];
+    const ZSTD_MAGIC: &'static [u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+    /// Picks the compression by a file extension: `.gz` or `.zst`.
+    pub fn from_path<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        match path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("gz") => Self::Gzip,
+            Some("zst") => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+
+    /// Wraps a reader with a decompressor matching its first bytes.
+    ///
+    /// Reads until the longest magic number is available or the stream ends,
+    /// since pipes and sockets may return fewer bytes at once.
+    /// Useful to read other formats, e.g. JSON Lines, from compressed files.
+    pub fn decompress<'a, R>(mut reader: R) -> Result<Box<dyn BufRead + 'a>, InfluxLineError>
+    where
+        R: Read + 'a,
+    {
+        let mut header = [0; Self::ZSTD_MAGIC.len()];
+        let mut filled = 0;
+        while filled < header.len() {
+            match reader.read(&mut header[filled..]) {
+                Ok(0) => break,
+                Ok(count) => filled += count,
+                Err(error) if error.kind() == ErrorKind::Interrupted => (),
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        let header = &header[..filled];
+        let reader = BufReader::new(io::Cursor::new(header.to_vec()).chain(reader));
+        Ok(match Self::detect(header) {
+            Self::None => Box::new(reader),
+            Self::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+            Self::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+        })
+    }
+
+    /// Recognizes the compression by the first bytes of a stream.
+    pub fn detect(header: &[u8]) -> Self {
+        if header.starts_with(Self::GZIP_MAGIC) {
+            Self::Gzip
+        } else if header.starts_with(Self::ZSTD_MAGIC) {
+            Self::Zstd
+        } else {
+            Self::None
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = InfluxLineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" | "identity" => Ok(Self::None),
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(InfluxLineError::BadCompression),
+        }
+    }
+}
+
+impl<W: Write> LineEncoder<W> {
+    pub fn new(writer: W, compression: Compression) -> Result<Self, InfluxLineError> {
+        let stream = match compression {
+            Compression::None => EncoderStream::None(writer),
+            Compression::Gzip => {
+                EncoderStream::Gzip(GzEncoder::new(writer, flate2::Compression::default()))
+            }
+            Compression::Zstd => EncoderStream::Zstd(zstd::Encoder::new(writer, 0)?),
+        };
+        Ok(Self { stream })
+    }
+
+    /// Writes a Line, appending a newline to it if it has none.
+    pub fn write_line(&mut self, line: &InfluxLine) -> Result<(), InfluxLineError> {
+        let encoded = line.to_string();
+        self.write_all(encoded.as_bytes())?;
+        if !encoded.ends_with('\n') {
+            self.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Completes the compressed stream and returns the underlying writer.
+    pub fn finish(self) -> Result<W, InfluxLineError> {
+        let mut writer = match self.stream {
+            EncoderStream::None(writer) => writer,
+            EncoderStream::Gzip(encoder) => encoder.finish()?,
+            EncoderStream::Zstd(encoder) => encoder.finish()?,
+        };
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl LineEncoder<BufWriter<File>> {
+    /// Creates a file, picking the compression by its extension.
+    pub fn create<P>(path: P) -> Result<Self, InfluxLineError>
+    where
+        P: AsRef<Path>,
+    {
+        let compression = Compression::from_path(&path);
+        Self::new(BufWriter::new(File::create(path)?), compression)
+    }
+}
+
+/// Allows writing raw, already encoded Line Protocol as well.
+impl<W: Write> Write for LineEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.stream {
+            EncoderStream::None(writer) => writer.write(buf),
+            EncoderStream::Gzip(encoder) => encoder.write(buf),
+            EncoderStream::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.stream {
+            EncoderStream::None(writer) => writer.flush(),
+            EncoderStream::Gzip(encoder) => encoder.flush(),
+            EncoderStream::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+impl<'a> LineReader<'a> {
+    /// Wraps a reader, detecting its compression.
+    pub fn new<R>(reader: R) -> Result<Self, InfluxLineError>
+    where
+        R: Read + 'a,
+    {
+        Ok(Self {
+            input: Compression::decompress(reader)?,
+            precision: Precision::Nanoseconds,
+            buffer: String::new(),
+            line_number: 0,
+            failed: false,
+        })
+    }
+
+    /// Interprets raw timestamps in a given precision, nanoseconds by default.
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Returns the number of the physical line read last, starting from 1.
+    ///
+    /// Useful to point at the position of a malformed Line.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+}
+
+impl LineReader<'static> {
+    pub fn open<P>(path: P) -> Result<Self, InfluxLineError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl Iterator for LineReader<'_> {
+    type Item = Result<InfluxLine, InfluxLineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            self.buffer.clear();
+            match self.input.read_line(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => self.line_number += 1,
+                Err(error) => {
+                    self.failed = true;
+                    return Some(Err(error.into()));
+                }
+            }
+
+            let raw = self.buffer.trim();
+            if raw.is_empty() || raw.starts_with('#') {
+                continue;
+            }
+            return Some(InfluxLine::parse_with_precision(raw, self.precision));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::{Compression, InfluxLine, InfluxLineError, LineEncoder, LineReader};
+
+    fn lines() -> Vec<InfluxLine> {
+        (0..3)
+            .map(|index| {
+                InfluxLine::try_new("cpu", "index", index as u64)
+                    .unwrap()
+                    .with_timestamp(index)
+            })
+            .collect()
+    }
+
+    #[rstest::rstest]
+    #[case::none(Compression::None)]
+    #[case::gzip(Compression::Gzip)]
+    #[case::zstd(Compression::Zstd)]
+    fn round_trip(#[case] compression: Compression) {
+        let mut encoder = LineEncoder::new(Vec::new(), compression).unwrap();
+        for line in lines() {
+            encoder.write_line(&line).unwrap();
+        }
+        let encoded = encoder.finish().unwrap();
+
+        let decoded: Vec<_> = LineReader::new(encoded.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(compression, Compression::detect(&encoded));
+        assert_eq!(lines(), decoded);
+    }
+
+    #[rstest::rstest]
+    #[case::plain("export.lp", Compression::None)]
+    #[case::gzip("export.lp.gz", Compression::Gzip)]
+    #[case::zstd("export.lp.zst", Compression::Zstd)]
+    fn files(#[case] name: &str, #[case] expected: Compression) {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join(name);
+
+        let mut encoder = LineEncoder::create(&path).unwrap();
+        for line in lines() {
+            encoder.write_line(&line).unwrap();
+        }
+        encoder.finish().unwrap();
+        let decoded: Vec<_> = LineReader::open(&path)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(expected, Compression::from_path(&path));
+        assert_eq!(
+            expected,
+            Compression::detect(&std::fs::read(&path).unwrap())
+        );
+        assert_eq!(lines(), decoded);
+    }
+
+    #[test]
+    fn concatenated_gzip_members() {
+        let mut encoded = Vec::new();
+        for line in lines() {
+            let mut encoder = LineEncoder::new(Vec::new(), Compression::Gzip).unwrap();
+            encoder.write_line(&line).unwrap();
+            encoded.extend(encoder.finish().unwrap());
+        }
+
+        let decoded: Vec<_> = LineReader::new(encoded.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(lines(), decoded);
+    }
+
+    /// Returns a single byte per read, as a slow pipe may.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            (&mut self.0).take(1).read(buf)
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::none(Compression::None)]
+    #[case::gzip(Compression::Gzip)]
+    #[case::zstd(Compression::Zstd)]
+    fn detects_compression_of_short_reads(#[case] compression: Compression) {
+        let mut encoder = LineEncoder::new(Vec::new(), compression).unwrap();
+        for line in lines() {
+            encoder.write_line(&line).unwrap();
+        }
+        let encoded = encoder.finish().unwrap();
+
+        let decoded: Vec<_> = LineReader::new(Trickle(&encoded))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(lines(), decoded);
+    }
+
+    #[rstest::rstest]
+    #[case::empty("")]
+    #[case::shorter_than_magic("a=")]
+    fn keeps_short_plain_streams(#[case] input: &str) {
+        let mut decompressed = String::new();
+        Compression::decompress(input.as_bytes())
+            .unwrap()
+            .read_to_string(&mut decompressed)
+            .unwrap();
+
+        assert_eq!(input, decompressed);
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let mut encoder = LineEncoder::new(Vec::new(), Compression::Gzip).unwrap();
+        encoder
+            .write_all(b"# comment\ncpu index=0u\n\ncpu index=\ncpu index=1u\n")
+            .unwrap();
+        let encoded = encoder.finish().unwrap();
+        let mut reader = LineReader::new(encoded.as_slice()).unwrap();
+
+        let mut results = Vec::new();
+        while let Some(result) = reader.next() {
+            results.push((reader.line_number(), result.is_ok()));
+        }
+
+        assert_eq!(vec![(2, true), (4, false), (5, true)], results);
+    }
+
+    #[test]
+    fn stops_on_corrupted_stream() {
+        let mut encoder = LineEncoder::new(Vec::new(), Compression::Zstd).unwrap();
+        for line in lines() {
+            encoder.write_line(&line).unwrap();
+        }
+        let mut encoded = encoder.finish().unwrap();
+        encoded.truncate(encoded.len() / 2);
+
+        let results: Vec<_> = LineReader::new(encoded.as_slice()).unwrap().collect();
+
+        assert!(matches!(results.last(), Some(Err(InfluxLineError::Io(_)))));
+    }
+}
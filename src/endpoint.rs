@@ -0,0 +1,13 @@
+/// One of the InfluxDB write APIs along with its target database.
+///
+/// Shared by the write client, which targets it,
+/// and the write receiver, which reports which one a request came through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteEndpoint {
+    /// InfluxDB v1 `/write` with a database and an optional retention policy.
+    V1 { db: String, rp: Option<String> },
+    /// InfluxDB v2 `/api/v2/write` with an organization and a bucket.
+    V2 { org: String, bucket: String },
+    /// InfluxDB v3 `/api/v3/write_lp` with a database.
+    V3 { db: String },
+}
@@ -1,3 +1,6 @@
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+
 /// A library level error that occurs when any failure occurs,
 /// such as parse error, or invalid input in constructors or conversion traits.
 #[derive(Debug, thiserror::Error)]
@@ -38,4 +41,82 @@ pub enum InfluxLineError {
     CharactersAfterLineEnd,
     #[error("Failed to convert value to a specified type")]
     TypeConversion,
+    #[error("Encoded line takes {size} bytes, which exceeds the limit of {limit} bytes")]
+    LineTooLarge { size: usize, limit: usize },
+    #[error("Timestamp does not fit into the nanosecond range")]
+    TimestampOutOfRange,
+    #[error("Failed to parse timestamp precision")]
+    BadPrecision,
+    #[error("Unknown compression")]
+    BadCompression,
+    #[error("Spool directory contains unexpected data")]
+    CorruptedSpool,
+    #[error("Spooled record at offset {offset} is not a valid Line")]
+    UnreadableSpoolRecord { offset: u64 },
+    #[error("Cardinality estimator precision must be between 4 and 16")]
+    BadEstimatorPrecision,
+    #[error("Cardinality estimators of different precisions cannot be merged")]
+    EstimatorMismatch,
+    #[error("Window size and step must be positive, and the size must span one to 1024 steps")]
+    BadWindow,
+    #[error("Aggregate suffixes must be distinct, and must form valid field keys")]
+    BadSuffix,
+    #[cfg(feature = "alloc")]
+    #[error("Failed to parse filter at byte {position}: {message}")]
+    BadFilter { position: usize, message: String },
+    #[cfg(feature = "alloc")]
+    #[error("Filter compares field `{field}` with {expected}, but it is {actual}")]
+    FilterTypeMismatch {
+        field: String,
+        expected: &'static str,
+        actual: crate::InfluxValueType,
+    },
+    #[cfg(feature = "alloc")]
+    #[error("Failed to convert field `{field}` to {target}")]
+    FieldConversion {
+        field: String,
+        target: crate::InfluxValueType,
+    },
+    #[cfg(feature = "alloc")]
+    #[error("Failed to load processor pipeline: {0}")]
+    BadPipeline(String),
+    #[error("Lines of different series or timestamps cannot be merged")]
+    SeriesMismatch,
+    #[error("Line {line} of input {input} is out of series and time order")]
+    UnsortedInput { input: usize, line: usize },
+    #[error("No timestamp found")]
+    NoTimestamp,
+    #[error("Field type conflicts with a previously seen type")]
+    FieldTypeConflict,
+    #[error("The same key is used for both a tag and a field")]
+    KeyConflict,
+    #[error("Failed to parse value type name")]
+    BadValueType,
+    #[error("Failed to compile value pattern")]
+    BadPattern,
+    #[error("Failed to parse schema columns file")]
+    BadColumnsFile,
+    #[error("Failed to build a valid URL")]
+    BadUrl,
+    #[error("Failed to build a valid HTTP header")]
+    BadHeader,
+    /// `lines` is the part of the written batch that the rejected request carried.
+    #[cfg(feature = "alloc")]
+    #[error("Server rejected Lines {lines:?} of the write with status {status}: {body}")]
+    WriteRejected {
+        status: u16,
+        body: String,
+        lines: core::ops::Range<usize>,
+    },
+    #[error("Encoded line takes {required} bytes, which exceeds the buffer of {available} bytes")]
+    BufferTooSmall { required: usize, available: usize },
+    #[cfg(feature = "std")]
+    #[error("I/O operation failed")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "client")]
+    #[error("HTTP request failed")]
+    Http(#[from] reqwest::Error),
+    #[cfg(feature = "parquet")]
+    #[error("Failed to write Parquet file")]
+    Parquet(#[from] parquet::errors::ParquetError),
 }
@@ -0,0 +1,398 @@
+mod parser;
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use regex::Regex;
+
+use crate::{Clock, InfluxLine, InfluxLineError, InfluxValue, KeyName, SystemClock, Timestamp};
+
+/// A boolean expression over the parts of a Line,
+/// meant for routing and filtering rules kept in configuration files.
+///
+/// # Syntax
+///
+/// - Subjects are `measurement`, `time`, `tag.<key>` and `field.<key>`.
+///   Keys that are not plain words are quoted, e.g. `tag."data center"`.
+/// - Values are strings in double quotes, numbers, `true` and `false`,
+///   regular expressions in slashes, e.g. `/^web-\d+$/`,
+///   and `now()` shifted by durations, e.g. `now() - 1h30m`.
+///   Duration units are `ns`, `us`, `ms`, `s`, `m`, `h`, `d` and `w`.
+/// - Comparisons are `==`, `!=`, `<`, `<=`, `>`, `>=`,
+///   and `=~` or `!~` for regular expressions, which match anywhere unless anchored.
+/// - Comparisons are combined with `and`, `or`, `not` and parentheses,
+///   where `and` binds tighter than `or`.
+///
+/// A comparison with a tag, field or timestamp missing from the Line is false,
+/// whatever the operator is.
+///
+/// # Types
+///
+/// Measurements and tags are strings, and time compares with `now()`
+/// or integer nanoseconds, so misuses like `tag.host > 10` fail to parse.
+/// Fields are only known when a Line is evaluated:
+/// numbers compare with every numeric field type, strings with string fields,
+/// and booleans with boolean fields, while any other pairing fails with
+/// [`InfluxLineError::FilterTypeMismatch`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::str::FromStr;
+/// use influx_line::*;
+///
+/// let filter = Filter::from_str(
+///     r#"measurement == "cpu" and tag.host =~ /^web-/ and field.usage_idle < 10 and time > now() - 1h"#,
+/// )
+/// .unwrap();
+///
+/// let busy = InfluxLine::try_new("cpu", "usage_idle", 5.5)
+///     .and_then(|line| line.try_with_tag("host", "web-1"))
+///     .unwrap()
+///     .with_timestamp(Timestamp::from(1704067200000000000_i64));
+/// let now = Timestamp::from(1704067260000000000_i64);
+///
+/// assert!(filter.evaluate_at(&busy, now).unwrap());
+/// assert!(!filter.evaluate_at(&busy.clone().try_with_tag("host", "db-1").unwrap(), now).unwrap());
+///
+/// let text = InfluxLine::try_new("cpu", "usage_idle", "high")
+///     .and_then(|line| line.try_with_tag("host", "web-1"))
+///     .unwrap();
+/// assert!(matches!(
+///     filter.evaluate_at(&text, now),
+///     Err(InfluxLineError::FilterTypeMismatch { .. })
+/// ));
+/// ```
+#[derive(Clone)]
+pub struct Filter {
+    source: String,
+    expression: Expression,
+    /// Tells the time of `now()` in [`Filter::evaluate`].
+    clock: Arc<dyn Clock>,
+}
+
+/// A node of a parsed [`Filter`].
+#[derive(Debug, Clone)]
+pub enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Compare(Comparison),
+}
+
+/// A comparison of a part of the Line with a value,
+/// with the subject always on the left, e.g. `10 > field.v` turns into `field.v < 10`.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub subject: Subject,
+    pub operator: Operator,
+    pub value: Literal,
+}
+
+/// A part of the Line a [`Comparison`] looks at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subject {
+    Measurement,
+    Time,
+    Tag(KeyName),
+    Field(KeyName),
+}
+
+/// `==`, `!=`, `<`, `<=`, `>`, `>=`, and `=~` or `!~` for regular expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Matches,
+    NotMatches,
+}
+
+/// A value a [`Subject`] is compared with.
+#[derive(Debug, Clone)]
+pub enum Literal {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Regex(Regex),
+    /// The time of evaluation shifted by nanoseconds.
+    Now(i64),
+}
+
+impl Filter {
+    /// Parses a filter expression.
+    ///
+    /// Fails with [`InfluxLineError::BadFilter`] pointing at the offending byte
+    /// for syntax errors and comparisons that can never be valid.
+    pub fn parse(source: &str) -> Result<Self, InfluxLineError> {
+        Ok(Self {
+            source: source.to_owned(),
+            expression: parser::parse(source)?,
+            clock: Arc::new(SystemClock),
+        })
+    }
+
+    /// Reads another clock for `now()` than the [`SystemClock`],
+    /// e.g. a [`crate::ManualClock`] in tests.
+    pub fn with_clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Returns the expression as it was written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the root of the parsed expression.
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    /// Tells whether the Line matches, with `now()` read from the clock.
+    pub fn evaluate(&self, line: &InfluxLine) -> Result<bool, InfluxLineError> {
+        self.evaluate_at(line, self.clock.now())
+    }
+
+    /// Tells whether the Line matches, with `now()` being the given time.
+    ///
+    /// `and` and `or` evaluate their right side only when needed,
+    /// so type mismatches there are not reported otherwise.
+    pub fn evaluate_at(&self, line: &InfluxLine, now: Timestamp) -> Result<bool, InfluxLineError> {
+        self.expression.evaluate(line, i64::from(now))
+    }
+}
+
+impl Debug for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Filter")
+            .field("source", &self.source)
+            .field("expression", &self.expression)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FromStr for Filter {
+    type Err = InfluxLineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Expression {
+    fn evaluate(&self, line: &InfluxLine, now: i64) -> Result<bool, InfluxLineError> {
+        match self {
+            Expression::And(left, right) => {
+                Ok(left.evaluate(line, now)? && right.evaluate(line, now)?)
+            }
+            Expression::Or(left, right) => {
+                Ok(left.evaluate(line, now)? || right.evaluate(line, now)?)
+            }
+            Expression::Not(inner) => Ok(!inner.evaluate(line, now)?),
+            Expression::Compare(comparison) => comparison.evaluate(line, now),
+        }
+    }
+}
+
+impl Comparison {
+    fn evaluate(&self, line: &InfluxLine, now: i64) -> Result<bool, InfluxLineError> {
+        let ordering = match &self.subject {
+            Subject::Measurement => self.compare_str(line.measurement().as_ref()),
+            Subject::Tag(key) => match line.tag(key.as_ref()) {
+                Some(value) => self.compare_str(value.as_ref()),
+                None => return Ok(false),
+            },
+            Subject::Time => match (line.timestamp(), &self.value) {
+                (Some(timestamp), Literal::Now(offset)) => {
+                    Compared::Ordering(i64::from(timestamp).cmp(&now.saturating_add(*offset)))
+                }
+                (Some(timestamp), Literal::Integer(nanoseconds)) => {
+                    Compared::Ordering(i64::from(timestamp).cmp(nanoseconds))
+                }
+                _ => return Ok(false),
+            },
+            Subject::Field(key) => match line.field(key.as_ref()) {
+                Some(value) => self.compare_value(key, value)?,
+                None => return Ok(false),
+            },
+        };
+
+        Ok(match (ordering, self.operator) {
+            (Compared::Ordering(ordering), Operator::Equal) => ordering == Ordering::Equal,
+            (Compared::Ordering(ordering), Operator::NotEqual) => ordering != Ordering::Equal,
+            (Compared::Ordering(ordering), Operator::Less) => ordering == Ordering::Less,
+            (Compared::Ordering(ordering), Operator::LessOrEqual) => ordering != Ordering::Greater,
+            (Compared::Ordering(ordering), Operator::Greater) => ordering == Ordering::Greater,
+            (Compared::Ordering(ordering), Operator::GreaterOrEqual) => ordering != Ordering::Less,
+            (Compared::Match(matched), Operator::Matches) => matched,
+            (Compared::Match(matched), Operator::NotMatches) => !matched,
+            // NaN is neither less, equal nor greater than anything.
+            (Compared::Unordered, Operator::NotEqual) => true,
+            _ => false,
+        })
+    }
+
+    fn compare_str(&self, actual: &str) -> Compared {
+        match &self.value {
+            Literal::String(expected) => Compared::Ordering(actual.cmp(expected.as_str())),
+            Literal::Regex(regex) => Compared::Match(regex.is_match(actual)),
+            // The parser only allows strings and regular expressions here.
+            _ => Compared::Unordered,
+        }
+    }
+
+    fn compare_value(
+        &self,
+        key: &KeyName,
+        actual: &InfluxValue,
+    ) -> Result<Compared, InfluxLineError> {
+        let mismatch = |expected: &'static str| InfluxLineError::FilterTypeMismatch {
+            field: key.as_ref().to_owned(),
+            expected,
+            actual: actual.value_type(),
+        };
+
+        let compared = match (&self.value, actual) {
+            (Literal::String(_) | Literal::Regex(_), InfluxValue::String(string)) => {
+                self.compare_str(string.as_ref())
+            }
+            (Literal::String(_), _) => return Err(mismatch("a string")),
+            (Literal::Regex(_), _) => return Err(mismatch("a regular expression")),
+            (Literal::Boolean(expected), InfluxValue::Boolean(boolean)) => {
+                Compared::Ordering(bool::from(*boolean).cmp(expected))
+            }
+            (Literal::Boolean(_), _) => return Err(mismatch("a boolean")),
+            (Literal::Integer(expected), InfluxValue::Integer(integer)) => {
+                Compared::Ordering(i64::from(*integer).cmp(expected))
+            }
+            (Literal::Integer(expected), InfluxValue::UInteger(uinteger)) => {
+                Compared::Ordering(i128::from(u64::from(*uinteger)).cmp(&i128::from(*expected)))
+            }
+            (Literal::Integer(expected), InfluxValue::Float(float)) => {
+                compare_floats(*float, *expected as f64)
+            }
+            (Literal::Float(expected), InfluxValue::Integer(integer)) => {
+                compare_floats(i64::from(*integer) as f64, *expected)
+            }
+            (Literal::Float(expected), InfluxValue::UInteger(uinteger)) => {
+                compare_floats(u64::from(*uinteger) as f64, *expected)
+            }
+            (Literal::Float(expected), InfluxValue::Float(float)) => {
+                compare_floats(*float, *expected)
+            }
+            (Literal::Integer(_) | Literal::Float(_), _) => return Err(mismatch("a number")),
+            // The parser only allows time to be compared with `now()`.
+            (Literal::Now(_), _) => return Err(mismatch("a time")),
+        };
+        Ok(compared)
+    }
+}
+
+/// Result of comparing a subject with a literal, before the operator is applied.
+enum Compared {
+    Ordering(Ordering),
+    Match(bool),
+    Unordered,
+}
+
+fn compare_floats(actual: f64, expected: f64) -> Compared {
+    actual
+        .partial_cmp(&expected)
+        .map_or(Compared::Unordered, Compared::Ordering)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{Filter, InfluxLine, InfluxLineError, InfluxValueType, ManualClock, Timestamp};
+
+    const NOW: i64 = 1704067200000000000;
+
+    fn line() -> InfluxLine {
+        InfluxLine::from_str(
+            r#"cpu,host=web-1,data\ center=eu usage=12.5,cores=8i,free=3u,up=true,state="idle" 1704067140000000000"#,
+        )
+        .expect("Must parse here")
+    }
+
+    fn evaluate(source: &str) -> Result<bool, InfluxLineError> {
+        Filter::from_str(source)
+            .expect("Must parse here")
+            .with_clock(ManualClock::new(Timestamp::from(NOW)))
+            .evaluate(&line())
+    }
+
+    #[rstest::rstest]
+    #[case::measurement(r#"measurement == "cpu""#, true)]
+    #[case::measurement_order(r#"measurement < "disk""#, true)]
+    #[case::tag_regex(r"tag.host =~ /^web-\d+$/", true)]
+    #[case::tag_not_regex(r"tag.host !~ /^db-/", true)]
+    #[case::quoted_key(r#"tag."data center" == "eu""#, true)]
+    #[case::missing_tag(r#"tag.zone != "x""#, false)]
+    #[case::float(r"field.usage < 12.6", true)]
+    #[case::float_with_integer(r"field.usage > 12", true)]
+    #[case::integer(r"field.cores == 8", true)]
+    #[case::integer_with_float(r"field.cores < 8.5", true)]
+    #[case::negative(r"field.cores > -1", true)]
+    #[case::unsigned(r"field.free >= 3", true)]
+    #[case::boolean(r"field.up == true", true)]
+    #[case::string(r#"field.state == "idle""#, true)]
+    #[case::string_regex(r"field.state =~ /dl/", true)]
+    #[case::missing_field(r"field.nope > 1", false)]
+    #[case::recent(r"time > now() - 1h", true)]
+    #[case::too_old(r"time > now() - 30s", false)]
+    #[case::compound_duration(r"time >= now() - 1m30s + 30s", true)]
+    #[case::raw_time(r"time == 1704067140000000000", true)]
+    #[case::flipped(r"10 < field.usage", true)]
+    #[case::precedence(
+        r#"measurement == "mem" and field.up == false or tag.host == "web-1""#,
+        true
+    )]
+    #[case::parentheses(
+        r#"measurement == "mem" and (field.up == false or tag.host == "web-1")"#,
+        false
+    )]
+    #[case::not(r#"not measurement == "mem""#, true)]
+    #[case::short_circuit(r#"measurement == "mem" and field.state > 1"#, false)]
+    fn evaluates(#[case] source: &str, #[case] expected: bool) {
+        assert_eq!(expected, evaluate(source).unwrap());
+    }
+
+    #[rstest::rstest]
+    #[case::number_with_string(r"field.state > 1", "a number", InfluxValueType::String)]
+    #[case::string_with_float(r#"field.usage == "12.5""#, "a string", InfluxValueType::Float)]
+    #[case::boolean_with_integer(r"field.cores == true", "a boolean", InfluxValueType::Integer)]
+    #[case::regex_with_boolean(
+        r"field.up =~ /t/",
+        "a regular expression",
+        InfluxValueType::Boolean
+    )]
+    fn reports_type_mismatches(
+        #[case] source: &str,
+        #[case] expected: &str,
+        #[case] actual_type: InfluxValueType,
+    ) {
+        match evaluate(source) {
+            Err(InfluxLineError::FilterTypeMismatch {
+                expected: expected_type,
+                actual,
+                ..
+            }) => {
+                assert_eq!(expected, expected_type);
+                assert_eq!(actual_type, actual);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
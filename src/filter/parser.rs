@@ -0,0 +1,445 @@
+use regex::Regex;
+
+use super::{Comparison, Expression, Literal, Operator, Subject};
+use crate::{InfluxLineError, KeyName};
+
+/// Parses a whole filter, rejecting anything left after the expression.
+pub(super) fn parse(source: &str) -> Result<Expression, InfluxLineError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        index: 0,
+    };
+    let expression = parser.or()?;
+    match parser.peek() {
+        (Token::End, _) => Ok(expression),
+        (_, position) => Err(error(
+            position,
+            "expected `and`, `or` or the end of the filter",
+        )),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    String(String),
+    Regex(String),
+    Integer(i64),
+    Float(f64),
+    /// Nanoseconds.
+    Duration(i64),
+    Operator(Operator),
+    Dot,
+    Plus,
+    Minus,
+    Open,
+    Close,
+    End,
+}
+
+struct Parser {
+    /// Tokens along with their byte positions, always ending with [`Token::End`].
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+}
+
+/// Either side of a comparison before it is known which one is the subject.
+enum Operand {
+    Subject(Subject),
+    Literal(Literal),
+}
+
+impl Parser {
+    fn peek(&self) -> (&Token, usize) {
+        let (token, position) = &self.tokens[self.index];
+        (token, *position)
+    }
+
+    fn next(&mut self) -> (Token, usize) {
+        let (token, position) = self.tokens[self.index].clone();
+        if token != Token::End {
+            self.index += 1;
+        }
+        (token, position)
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), (Token::Word(next), _) if next == word) {
+            self.index += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, expected: Token, description: &str) -> Result<(), InfluxLineError> {
+        match self.next() {
+            (token, _) if token == expected => Ok(()),
+            (_, position) => Err(error(position, &format!("expected {}", description))),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expression, InfluxLineError> {
+        let mut expression = self.and()?;
+        while self.eat_word("or") {
+            expression = Expression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        Ok(expression)
+    }
+
+    fn and(&mut self) -> Result<Expression, InfluxLineError> {
+        let mut expression = self.not()?;
+        while self.eat_word("and") {
+            expression = Expression::And(Box::new(expression), Box::new(self.not()?));
+        }
+        Ok(expression)
+    }
+
+    fn not(&mut self) -> Result<Expression, InfluxLineError> {
+        if self.eat_word("not") {
+            return Ok(Expression::Not(Box::new(self.not()?)));
+        }
+        if matches!(self.peek(), (Token::Open, _)) {
+            self.index += 1;
+            let expression = self.or()?;
+            self.expect(Token::Close, "`)`")?;
+            return Ok(expression);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expression, InfluxLineError> {
+        let (_, position) = self.peek();
+        let left = self.operand()?;
+        let operator = match self.next() {
+            (Token::Operator(operator), _) => operator,
+            (_, position) => return Err(error(position, "expected a comparison operator")),
+        };
+        let right = self.operand()?;
+
+        let comparison = match (left, right) {
+            (Operand::Subject(subject), Operand::Literal(value)) => Comparison {
+                subject,
+                operator,
+                value,
+            },
+            (Operand::Literal(value), Operand::Subject(subject)) => Comparison {
+                subject,
+                operator: flip(operator),
+                value,
+            },
+            (Operand::Subject(_), Operand::Subject(_)) => {
+                return Err(error(
+                    position,
+                    "parts of a Line cannot be compared with each other",
+                ));
+            }
+            (Operand::Literal(_), Operand::Literal(_)) => {
+                return Err(error(
+                    position,
+                    "one side must be measurement, time, a tag or a field",
+                ));
+            }
+        };
+        check(&comparison).map_err(|message| error(position, message))?;
+        Ok(Expression::Compare(comparison))
+    }
+
+    fn operand(&mut self) -> Result<Operand, InfluxLineError> {
+        let (token, position) = self.next();
+        let operand = match token {
+            Token::Word(word) => match word.as_str() {
+                "measurement" => Operand::Subject(Subject::Measurement),
+                "time" => Operand::Subject(Subject::Time),
+                "tag" => Operand::Subject(Subject::Tag(self.key()?)),
+                "field" => Operand::Subject(Subject::Field(self.key()?)),
+                "true" => Operand::Literal(Literal::Boolean(true)),
+                "false" => Operand::Literal(Literal::Boolean(false)),
+                "now" => Operand::Literal(self.now()?),
+                _ => return Err(error(position, &format!("unknown word `{}`", word))),
+            },
+            Token::String(string) => Operand::Literal(Literal::String(string)),
+            Token::Regex(pattern) => Operand::Literal(Literal::Regex(
+                Regex::new(&pattern).map_err(|_| error(position, "invalid regular expression"))?,
+            )),
+            Token::Integer(integer) => Operand::Literal(Literal::Integer(integer)),
+            Token::Float(float) => Operand::Literal(Literal::Float(float)),
+            Token::Minus => match self.next() {
+                (Token::Integer(integer), _) => Operand::Literal(Literal::Integer(-integer)),
+                (Token::Float(float), _) => Operand::Literal(Literal::Float(-float)),
+                (_, position) => return Err(error(position, "expected a number after `-`")),
+            },
+            Token::End => return Err(error(position, "unexpected end of the filter")),
+            _ => return Err(error(position, "expected a value or a part of a Line")),
+        };
+        Ok(operand)
+    }
+
+    /// Parses `.key` or `."quoted key"` after `tag` or `field`.
+    fn key(&mut self) -> Result<KeyName, InfluxLineError> {
+        self.expect(Token::Dot, "`.` followed by a key")?;
+        match self.next() {
+            (Token::Word(key) | Token::String(key), position) => {
+                KeyName::new(key).map_err(|error_| error(position, &error_.to_string()))
+            }
+            (_, position) => Err(error(position, "expected a key")),
+        }
+    }
+
+    /// Parses `()` after `now`, followed by any number of shifts like `- 1h`.
+    fn now(&mut self) -> Result<Literal, InfluxLineError> {
+        self.expect(Token::Open, "`(`")?;
+        self.expect(Token::Close, "`)`")?;
+
+        let mut offset: i64 = 0;
+        loop {
+            let sign = match self.peek() {
+                (Token::Plus, _) => 1,
+                (Token::Minus, _) => -1,
+                _ => return Ok(Literal::Now(offset)),
+            };
+            self.index += 1;
+
+            match self.next() {
+                (Token::Duration(duration), _) => offset = offset.saturating_add(sign * duration),
+                (_, position) => return Err(error(position, "expected a duration, e.g. `1h`")),
+            }
+        }
+    }
+}
+
+/// Rejects comparisons that cannot be valid for any Line.
+fn check(comparison: &Comparison) -> Result<(), &'static str> {
+    let is_match = matches!(
+        comparison.operator,
+        Operator::Matches | Operator::NotMatches
+    );
+    let is_equality = matches!(comparison.operator, Operator::Equal | Operator::NotEqual);
+
+    match (&comparison.value, is_match) {
+        (Literal::Regex(_), false) => return Err("regular expressions need `=~` or `!~`"),
+        (Literal::Regex(_), true) => {}
+        (_, true) => return Err("`=~` and `!~` need a regular expression"),
+        (Literal::Boolean(_), false) if !is_equality => {
+            return Err("booleans can only be compared with `==` or `!=`");
+        }
+        _ => {}
+    }
+
+    match (&comparison.subject, &comparison.value) {
+        (Subject::Measurement | Subject::Tag(_), Literal::String(_) | Literal::Regex(_)) => Ok(()),
+        (Subject::Measurement, _) => Err("measurement can only be compared with strings"),
+        (Subject::Tag(_), _) => Err("tags can only be compared with strings"),
+        (Subject::Time, Literal::Now(_) | Literal::Integer(_)) => Ok(()),
+        (Subject::Time, _) => Err("time can only be compared with `now()` or nanoseconds"),
+        (Subject::Field(_), Literal::Now(_)) => Err("fields cannot be compared with `now()`"),
+        (Subject::Field(_), _) => Ok(()),
+    }
+}
+
+/// Swaps sides of a comparison, e.g. `10 > x` into `x < 10`.
+fn flip(operator: Operator) -> Operator {
+    match operator {
+        Operator::Less => Operator::Greater,
+        Operator::LessOrEqual => Operator::GreaterOrEqual,
+        Operator::Greater => Operator::Less,
+        Operator::GreaterOrEqual => Operator::LessOrEqual,
+        other => other,
+    }
+}
+
+fn error(position: usize, message: &str) -> InfluxLineError {
+    InfluxLineError::BadFilter {
+        position,
+        message: message.to_owned(),
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, InfluxLineError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((position, character)) = chars.next() {
+        let token = match character {
+            _ if character.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '.' => Token::Dot,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '=' | '!' | '<' | '>' => {
+                let next = chars.peek().map(|(_, next)| *next);
+                let (operator, long) = match (character, next) {
+                    ('=', Some('=')) => (Operator::Equal, true),
+                    ('=', Some('~')) => (Operator::Matches, true),
+                    ('!', Some('=')) => (Operator::NotEqual, true),
+                    ('!', Some('~')) => (Operator::NotMatches, true),
+                    ('<', Some('=')) => (Operator::LessOrEqual, true),
+                    ('>', Some('=')) => (Operator::GreaterOrEqual, true),
+                    ('<', _) => (Operator::Less, false),
+                    ('>', _) => (Operator::Greater, false),
+                    _ => {
+                        return Err(error(
+                            position,
+                            "unknown operator, `==` compares for equality",
+                        ));
+                    }
+                };
+                if long {
+                    chars.next();
+                }
+                Token::Operator(operator)
+            }
+            '"' | '/' => {
+                let mut text = String::new();
+                let mut closed = false;
+                while let Some((_, next)) = chars.next() {
+                    match next {
+                        '\\' => match chars.next() {
+                            // Regular expressions keep their own escapes, except for the delimiter.
+                            Some((_, escaped)) if escaped == character || character == '"' => {
+                                text.push(escaped)
+                            }
+                            Some((_, escaped)) => {
+                                text.push('\\');
+                                text.push(escaped);
+                            }
+                            None => break,
+                        },
+                        _ if next == character => {
+                            closed = true;
+                            break;
+                        }
+                        _ => text.push(next),
+                    }
+                }
+                if !closed {
+                    return Err(error(position, "unterminated string or regular expression"));
+                }
+                if character == '"' {
+                    Token::String(text)
+                } else {
+                    Token::Regex(text)
+                }
+            }
+            _ if character.is_ascii_digit() => {
+                let mut number = String::from(character);
+                while let Some((_, next)) = chars.peek() {
+                    let is_exponent_sign =
+                        matches!(next, '+' | '-') && number.ends_with(['e', 'E']);
+                    if next.is_alphanumeric() || *next == '.' || is_exponent_sign {
+                        number.push(*next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                number_token(&number)
+                    .ok_or_else(|| error(position, "invalid number or duration"))?
+            }
+            _ if character.is_alphabetic() || character == '_' => {
+                let mut word = String::from(character);
+                while let Some((_, next)) = chars.peek() {
+                    if next.is_alphanumeric() || *next == '_' {
+                        word.push(*next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Token::Word(word)
+            }
+            _ => {
+                return Err(error(
+                    position,
+                    &format!("unexpected character `{}`", character),
+                ));
+            }
+        };
+        tokens.push((token, position));
+    }
+
+    tokens.push((Token::End, source.len()));
+    Ok(tokens)
+}
+
+/// Parses integers, floats, and durations like `90s` or `1h30m`.
+fn number_token(number: &str) -> Option<Token> {
+    if let Ok(integer) = number.parse::<i64>() {
+        return Some(Token::Integer(integer));
+    }
+    if let Ok(float) = number.parse::<f64>() {
+        return Some(Token::Float(float));
+    }
+
+    let mut nanoseconds: i64 = 0;
+    let mut rest = number;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|character: char| !character.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let units = rest[digits..]
+            .find(|character: char| character.is_ascii_digit())
+            .map_or(rest.len(), |units| digits + units);
+        let unit_nanoseconds: i64 = match &rest[digits..units] {
+            "ns" => 1,
+            "us" | "µs" => 1_000,
+            "ms" => 1_000_000,
+            "s" => 1_000_000_000,
+            "m" => 60 * 1_000_000_000,
+            "h" => 60 * 60 * 1_000_000_000,
+            "d" => 24 * 60 * 60 * 1_000_000_000,
+            "w" => 7 * 24 * 60 * 60 * 1_000_000_000,
+            _ => return None,
+        };
+        let value = rest[..digits].parse::<i64>().ok()?;
+        nanoseconds = nanoseconds.checked_add(value.checked_mul(unit_nanoseconds)?)?;
+        rest = &rest[units..];
+    }
+    Some(Token::Duration(nanoseconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Filter, InfluxLineError};
+
+    #[rstest::rstest]
+    #[case::empty("", 0)]
+    #[case::dangling_and(r#"measurement == "cpu" and"#, 24)]
+    #[case::missing_operator(r#"measurement "cpu""#, 12)]
+    #[case::unknown_word(r#"host == "a""#, 0)]
+    #[case::single_equals(r#"measurement = "cpu""#, 12)]
+    #[case::unterminated(r#"measurement == "cpu"#, 15)]
+    #[case::unclosed_parenthesis(r#"(measurement == "cpu""#, 21)]
+    #[case::tag_with_number(r"tag.host > 10", 0)]
+    #[case::measurement_with_boolean(r"measurement == true", 0)]
+    #[case::time_with_string(r#"time > "yesterday""#, 0)]
+    #[case::field_with_now(r"field.v > now()", 0)]
+    #[case::ordered_boolean(r"field.up < true", 0)]
+    #[case::match_without_regex(r#"tag.host =~ "web""#, 0)]
+    #[case::regex_without_match(r"tag.host == /web/", 0)]
+    #[case::bad_regex(r"tag.host =~ /(/", 12)]
+    #[case::two_subjects(r"field.a > field.b", 0)]
+    #[case::two_literals(r"1 < 2", 0)]
+    #[case::reserved_key(r#"tag._id == "x""#, 4)]
+    #[case::now_without_duration(r"time > now() - 5", 15)]
+    #[case::bad_unit(r"time > now() - 5y", 15)]
+    fn parse_errors(#[case] source: &str, #[case] expected_position: usize) {
+        match Filter::parse(source) {
+            Err(InfluxLineError::BadFilter { position, .. }) => {
+                assert_eq!(expected_position, position)
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::exponent("field.v < 1e3")]
+    #[case::negative_exponent("field.v > 1.5e-3")]
+    #[case::escaped_slash(r"tag.path =~ /^\/var\//")]
+    #[case::escaped_quote(r#"field.s == "say \"hi\"""#)]
+    #[case::unicode_key(r#"tag.zählung == "1""#)]
+    #[case::no_spaces(r#"(measurement=="cpu")and(field.v>=1)"#)]
+    fn parses(#[case] source: &str) {
+        Filter::parse(source).expect("Must parse here");
+    }
+}
@@ -0,0 +1,292 @@
+use core::fmt::Write;
+
+use crate::types::string::{
+    ESCAPE_CHARACTER, KEY_SPECIAL_CHARACTERS, LinearFormatter, MEASUREMENT_SPECIAL_CHARACTERS,
+    QUOTED_SPECIAL_CHARACTERS, check_name,
+};
+use crate::{Boolean, InfluxInteger, InfluxLineError, InfluxUInteger, Timestamp};
+
+/// A Line of borrowed parts that is encoded into a caller-supplied buffer,
+/// without an allocator.
+///
+/// Names and values are given unescaped, and are checked and escaped
+/// the same way as [`crate::KeyName`], [`crate::MeasurementName`]
+/// and [`crate::QuotedString`] do, so the output is identical
+/// to the [`core::fmt::Display`] output of an equivalent [`crate::InfluxLine`].
+/// The output has no trailing newline.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let mut buffer = [0_u8; 64];
+/// let fields = [("usage", BorrowedValue::from(0.5))];
+/// let line = FixedLine::new("cpu", &fields)
+///     .with_tags(&[("host", "web 1")])
+///     .with_timestamp(1704067200000000000_i64);
+///
+/// let size = line.encode(&mut buffer).unwrap();
+///
+/// assert_eq!(
+///     br"cpu,host=web\ 1 usage=0.5 1704067200000000000",
+///     &buffer[..size]
+/// );
+///
+/// let error = line.encode(&mut buffer[..16]).unwrap_err();
+/// assert!(matches!(
+///     error,
+///     InfluxLineError::BufferTooSmall { required: 45, available: 16 }
+/// ));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedLine<'a> {
+    measurement: &'a str,
+    tags: &'a [(&'a str, &'a str)],
+    fields: &'a [(&'a str, BorrowedValue<'a>)],
+    timestamp: Option<Timestamp>,
+}
+
+/// A Field value of a [`FixedLine`], with strings borrowed from the caller.
+#[derive(Debug, Clone, Copy, PartialEq, derive_more::From)]
+pub enum BorrowedValue<'a> {
+    #[from(f32, f64)]
+    Float(f64),
+    #[from(i8, i16, i32, i64)]
+    Integer(i64),
+    #[from(u8, u16, u32, u64)]
+    UInteger(u64),
+    #[from(bool)]
+    Boolean(bool),
+    #[from]
+    String(&'a str),
+}
+
+/// Writes into a slice as long as there is room, and counts every byte regardless,
+/// so that the required size is known even if the slice is too small.
+struct SliceWriter<'b> {
+    buffer: &'b mut [u8],
+    position: usize,
+}
+
+impl<'a> FixedLine<'a> {
+    /// Creates a Line without tags and a timestamp.
+    pub fn new(measurement: &'a str, fields: &'a [(&'a str, BorrowedValue<'a>)]) -> Self {
+        Self {
+            measurement,
+            tags: &[],
+            fields,
+            timestamp: None,
+        }
+    }
+
+    /// Sets the tags, which are written in the given order.
+    pub fn with_tags(mut self, tags: &'a [(&'a str, &'a str)]) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_timestamp<T>(mut self, timestamp: T) -> Self
+    where
+        T: Into<Timestamp>,
+    {
+        self.timestamp.replace(timestamp.into());
+        self
+    }
+
+    /// Returns the number of bytes [`Self::encode`] needs.
+    pub fn encoded_len(&self) -> Result<usize, InfluxLineError> {
+        let mut writer = SliceWriter {
+            buffer: &mut [],
+            position: 0,
+        };
+        self.write(&mut writer)?;
+        Ok(writer.position)
+    }
+
+    /// Writes the Line to the start of the buffer and returns the number of bytes written.
+    ///
+    /// Fails with [`InfluxLineError::BufferTooSmall`] naming the required size
+    /// if the Line does not fit, in which case the content of the buffer is unspecified.
+    pub fn encode(&self, buffer: &mut [u8]) -> Result<usize, InfluxLineError> {
+        let available = buffer.len();
+        let mut writer = SliceWriter {
+            buffer,
+            position: 0,
+        };
+        self.write(&mut writer)?;
+
+        if writer.position > available {
+            return Err(InfluxLineError::BufferTooSmall {
+                required: writer.position,
+                available,
+            });
+        }
+        Ok(writer.position)
+    }
+
+    fn write(&self, writer: &mut SliceWriter<'_>) -> Result<(), InfluxLineError> {
+        check_name(self.measurement)?;
+        if self.fields.is_empty() {
+            return Err(InfluxLineError::NoFields);
+        }
+
+        writer.escaped(self.measurement, &MEASUREMENT_SPECIAL_CHARACTERS);
+
+        for (key, value) in self.tags {
+            check_name(key)?;
+            check_name(value)?;
+            writer.push(",");
+            writer.escaped(key, &KEY_SPECIAL_CHARACTERS);
+            writer.push("=");
+            writer.escaped(value, &KEY_SPECIAL_CHARACTERS);
+        }
+
+        for (index, (key, value)) in self.fields.iter().enumerate() {
+            check_name(key)?;
+            writer.push(if index == 0 { " " } else { "," });
+            writer.escaped(key, &KEY_SPECIAL_CHARACTERS);
+            writer.push("=");
+            writer.value(value);
+        }
+
+        if let Some(timestamp) = self.timestamp {
+            // The writer never fails.
+            let _ = write!(writer, " {}", timestamp);
+        }
+
+        Ok(())
+    }
+}
+
+impl SliceWriter<'_> {
+    fn push(&mut self, s: &str) {
+        // The writer never fails.
+        let _ = self.write_str(s);
+    }
+
+    fn escaped(&mut self, s: &str, special_characters: &[char]) {
+        let formatter = LinearFormatter::new(special_characters, &ESCAPE_CHARACTER);
+        for character in formatter.chars(&s) {
+            // The writer never fails.
+            let _ = self.write_char(character);
+        }
+    }
+
+    /// Writes a Field value as [`crate::InfluxValue`] displays it.
+    fn value(&mut self, value: &BorrowedValue<'_>) {
+        // The writer never fails.
+        let _ = match *value {
+            BorrowedValue::Float(value) => write!(self, "{:?}", value),
+            BorrowedValue::Integer(value) => write!(self, "{}", InfluxInteger::from(value)),
+            BorrowedValue::UInteger(value) => write!(self, "{}", InfluxUInteger::from(value)),
+            BorrowedValue::Boolean(value) => write!(self, "{}", Boolean::from(value)),
+            BorrowedValue::String(value) => {
+                self.push("\"");
+                self.escaped(value, &QUOTED_SPECIAL_CHARACTERS);
+                self.write_str("\"")
+            }
+        };
+    }
+}
+
+impl Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.position + s.len();
+        if let Some(target) = self.buffer.get_mut(self.position..end) {
+            target.copy_from_slice(s.as_bytes());
+        }
+        self.position = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BorrowedValue, FixedLine, InfluxLineError};
+
+    #[rstest::rstest]
+    #[case::minimal(FixedLine::new("m", &[("x", BorrowedValue::Float(1.0))]), "m x=1.0")]
+    #[case::all_values(
+        FixedLine::new("m", &[
+            ("f", BorrowedValue::Float(-0.25)),
+            ("i", BorrowedValue::Integer(-3)),
+            ("u", BorrowedValue::UInteger(3)),
+            ("b", BorrowedValue::Boolean(true)),
+            ("s", BorrowedValue::String("ok")),
+        ]),
+        r#"m f=-0.25,i=-3i,u=3u,b=true,s="ok""#
+    )]
+    #[case::escaped(
+        FixedLine::new("a b,c=d", &[("e f", BorrowedValue::String(r#"say "hi" \o/"#))])
+            .with_tags(&[("g,h", "i=j k")]),
+        r#"a\ b\,c=d,g\,h=i\=j\ k e\ f="say \"hi\" \\o/""#
+    )]
+    #[case::timestamp(
+        FixedLine::new("m", &[("x", BorrowedValue::UInteger(1))]).with_timestamp(-15),
+        "m x=1u -15"
+    )]
+    fn encodes(#[case] line: FixedLine<'_>, #[case] expected: &str) {
+        let mut buffer = [0_u8; 64];
+
+        let size = line.encode(&mut buffer).expect("Must encode here");
+
+        assert_eq!(expected.as_bytes(), &buffer[..size]);
+        assert_eq!(Ok(size), line.encoded_len().map_err(|_| ()));
+    }
+
+    #[rstest::rstest]
+    #[case::empty(0)]
+    #[case::one_byte_short(7)]
+    fn reports_required_size(#[case] available: usize) {
+        let line =
+            FixedLine::new("m", &[("x", BorrowedValue::Float(1.0))]).with_tags(&[("t", "v")]);
+        let mut buffer = [0_u8; 64];
+
+        match line.encode(&mut buffer[..available]) {
+            Err(InfluxLineError::BufferTooSmall {
+                required: 11,
+                available: actual,
+            }) => assert_eq!(available, actual),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::no_fields(FixedLine::new("m", &[]))]
+    #[case::reserved_measurement(FixedLine::new("_m", &[("x", BorrowedValue::Integer(1))]))]
+    #[case::empty_tag_value(
+        FixedLine::new("m", &[("x", BorrowedValue::Integer(1))]).with_tags(&[("t", "")])
+    )]
+    #[case::reserved_field(FixedLine::new("m", &[("_x", BorrowedValue::Integer(1))]))]
+    fn rejects_invalid_lines(#[case] line: FixedLine<'_>) {
+        let mut buffer = [0_u8; 64];
+
+        assert!(line.encode(&mut buffer).is_err());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn matches_influx_line() {
+        use alloc::string::ToString;
+
+        let fields = [
+            ("usage percent", BorrowedValue::from(12.5)),
+            ("state", BorrowedValue::from(r#"a "b" \c"#)),
+        ];
+        let line = FixedLine::new("cpu load", &fields)
+            .with_tags(&[("host", "web,1"), ("dc", "eu=west")])
+            .with_timestamp(1704067200000000000_i64);
+        let mut buffer = [0_u8; 128];
+
+        let size = line.encode(&mut buffer).expect("Must encode here");
+
+        let expected = crate::InfluxLine::try_new("cpu load", "usage percent", 12.5)
+            .and_then(|line| line.try_with_tag("host", "web,1"))
+            .and_then(|line| line.try_with_tag("dc", "eu=west"))
+            .and_then(|line| line.try_with_field("state", r#"a "b" \c"#))
+            .expect("Must build here")
+            .with_timestamp(1704067200000000000_i64);
+        assert_eq!(expected.to_string().as_bytes(), &buffer[..size]);
+    }
+}
@@ -1,11 +1,113 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub(crate) mod aggregate;
+#[cfg(feature = "std")]
+pub(crate) mod batcher;
+#[cfg(feature = "std")]
+pub(crate) mod cardinality;
+#[cfg(feature = "client")]
+pub(crate) mod client;
+#[cfg(feature = "std")]
+pub(crate) mod compactor;
+#[cfg(feature = "compression")]
+pub(crate) mod compression;
+#[cfg(any(feature = "client", feature = "server"))]
+pub(crate) mod endpoint;
 pub(crate) mod error;
+#[cfg(feature = "filter")]
+pub(crate) mod filter;
+pub(crate) mod fixed;
+#[cfg(feature = "std")]
+pub(crate) mod limiter;
+#[cfg(feature = "alloc")]
 pub(crate) mod line;
+#[cfg(feature = "std")]
+pub(crate) mod lint;
+#[cfg(feature = "processor")]
+pub(crate) mod processor;
+#[cfg(feature = "std")]
+pub(crate) mod schema;
+#[cfg(feature = "server")]
+pub(crate) mod server;
+#[cfg(feature = "std")]
+pub(crate) mod sink;
+#[cfg(feature = "std")]
+pub(crate) mod socket;
+#[cfg(feature = "std")]
+pub(crate) mod sort;
+#[cfg(feature = "spool")]
+pub(crate) mod spool;
+#[cfg(feature = "std")]
+pub(crate) mod stamp;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+pub(crate) mod strategy;
 pub(crate) mod types;
+#[cfg(feature = "std")]
+pub(crate) mod udp;
 
 pub use crate::error::InfluxLineError;
-pub use crate::line::InfluxLine;
+pub use crate::fixed::{BorrowedValue, FixedLine};
 pub use crate::types::boolean::Boolean;
 pub use crate::types::integer::{InfluxInteger, InfluxUInteger};
+pub use crate::types::timestamp::{Precision, Timestamp};
+
+#[cfg(feature = "std")]
+pub use crate::aggregate::{Aggregate, Aggregator, AggregatorConfig};
+#[cfg(feature = "std")]
+pub use crate::batcher::{BatchConfig, LineBatcher};
+#[cfg(feature = "std")]
+pub use crate::cardinality::CardinalityEstimator;
+#[cfg(feature = "client")]
+pub use crate::client::{
+    AsyncWriteClient, RejectionReason, WriteClient, WriteConfig, WriteRejection,
+};
+#[cfg(feature = "std")]
+pub use crate::compactor::Compactor;
+#[cfg(feature = "compression")]
+pub use crate::compression::{Compression, LineEncoder, LineReader};
+#[cfg(any(feature = "client", feature = "server"))]
+pub use crate::endpoint::WriteEndpoint;
+#[cfg(feature = "filter")]
+pub use crate::filter::{Comparison, Expression, Filter, Literal, Operator, Subject};
+#[cfg(feature = "std")]
+pub use crate::limiter::{LimitAction, LimitDecision, LimitScope, LimiterConfig, SeriesLimiter};
+#[cfg(feature = "alloc")]
+pub use crate::line::InfluxLine;
+#[cfg(feature = "std")]
+pub use crate::lint::{
+    Finding, FutureTimestamps, KeyCollisions, Linter, NumericStrings, NumericTags, Rule, Severity,
+    TimestampPrecision, UniqueIdTags, UnsortedTags,
+};
+#[cfg(feature = "processor")]
+pub use crate::processor::{
+    Convert, DropKeys, FieldToTag, Pipeline, Processor, RegexReplace, Rename, SetTags, TagToField,
+    TimestampFromField,
+};
+#[cfg(feature = "schema")]
+pub use crate::schema::{KeyPresence, Schema, SchemaSet, SchemaViolation, UnknownKeys};
+#[cfg(feature = "std")]
+pub use crate::schema::{MeasurementSchema, SchemaConflict, SchemaRegistry};
+#[cfg(feature = "server")]
+pub use crate::server::{WriteReceiver, WriteRequest, WriteResponse, WriteServer};
+#[cfg(feature = "parquet")]
+pub use crate::sink::parquet::ParquetSink;
+#[cfg(feature = "std")]
+pub use crate::socket::{OverflowPolicy, SocketConfig, SocketWriter};
+#[cfg(feature = "std")]
+pub use crate::sort::{LineMerger, LineSorter};
+#[cfg(feature = "spool")]
+pub use crate::spool::Spool;
+#[cfg(feature = "std")]
+pub use crate::stamp::{StampMode, Stamper};
+#[cfg(feature = "std")]
+pub use crate::types::clock::{Clock, ManualClock, SystemClock};
+#[cfg(feature = "alloc")]
 pub use crate::types::string::{KeyName, MeasurementName, QuotedString};
-pub use crate::types::timestamp::Timestamp;
-pub use crate::types::value::InfluxValue;
+#[cfg(feature = "alloc")]
+pub use crate::types::value::{InfluxValue, InfluxValueType};
+#[cfg(feature = "std")]
+pub use crate::udp::{UdpDatagram, UdpListener, UdpSink};
@@ -0,0 +1,606 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::cardinality::series_hash;
+use crate::{Clock, InfluxLine, KeyName, MeasurementName, SystemClock};
+
+/// Limits of a [`SeriesLimiter`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use influx_line::*;
+///
+/// let config = LimiterConfig::new(10_000)
+///     .with_scope(LimitScope::Tag(KeyName::new("tenant").unwrap()))
+///     .with_window(Duration::from_secs(3600))
+///     .with_action(LimitAction::DropTags(vec![KeyName::new("request_id").unwrap()]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LimiterConfig {
+    max_series: usize,
+    max_scopes: usize,
+    scope: LimitScope,
+    window: Duration,
+    action: LimitAction,
+    event_measurement: MeasurementName,
+}
+
+/// What the series limit applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum LimitScope {
+    /// Every measurement has its own limit.
+    #[default]
+    Measurement,
+    /// Every value of the tag has its own limit, e.g. per tenant.
+    /// Lines without the tag share a single limit.
+    Tag(KeyName),
+}
+
+/// What happens to Lines of new series once the limit is reached.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum LimitAction {
+    /// Rejects them.
+    #[default]
+    Reject,
+    /// Accepts a share of new series between 0 and 1, and rejects the rest.
+    ///
+    /// The choice depends on the series key only,
+    /// so a series is either always accepted or always rejected.
+    /// Accepted series count against the limit, which they push beyond.
+    Sample(f64),
+    /// Removes the tags, which usually makes the Line join an existing series.
+    ///
+    /// The reduced series is accepted even if it is new,
+    /// so the limit is exceeded by the series of reduced tag sets only.
+    DropTags(Vec<KeyName>),
+}
+
+/// Outcome of [`SeriesLimiter::check`], carrying the Line back.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LimitDecision {
+    /// The Line belongs to a known series, or to a new one within the limit.
+    Accepted(InfluxLine),
+    /// The Line lost some tags due to [`LimitAction::DropTags`].
+    Reduced(InfluxLine),
+    /// The Line would have created a series beyond the limit.
+    Rejected(InfluxLine),
+}
+
+/// Tracks distinct series within a sliding time window,
+/// and stops new series once their number crosses a limit,
+/// so that a misbehaving writer cannot blow up the cardinality of a database.
+///
+/// Lines of series seen within the window always keep flowing.
+/// A series expires once no Lines of it were seen for the whole window,
+/// which frees room for a new one.
+/// Only series hashes are kept, and no more than the limit per scope
+/// unless the action lets more in.
+/// Scopes whose series have all expired are forgotten,
+/// and Lines of new scopes beyond [`LimiterConfig::with_max_scopes`] are rejected,
+/// so unique values of the scope tag cannot blow up the limiter itself.
+///
+/// Every decision other than a plain acceptance is counted,
+/// and [`Self::drain_events`] reports the counts as Lines that can be written
+/// next to the data itself and alerted on.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let mut limiter = SeriesLimiter::new(LimiterConfig::new(2));
+/// let line = |host: &str| InfluxLine::try_new("cpu", "usage", 0.5)
+///     .and_then(|line| line.try_with_tag("host", host))
+///     .unwrap();
+///
+/// assert!(matches!(limiter.check(line("a")), LimitDecision::Accepted(_)));
+/// assert!(matches!(limiter.check(line("b")), LimitDecision::Accepted(_)));
+/// assert!(matches!(limiter.check(line("c")), LimitDecision::Rejected(_)));
+/// // Known series keep flowing.
+/// assert!(matches!(limiter.check(line("a")), LimitDecision::Accepted(_)));
+///
+/// let events = limiter.drain_events();
+/// assert!(events[0].to_string().starts_with(
+///     "series_limiter,scope=measurement,value=cpu,action=rejected count=1i,series=2i,limit=2i "
+/// ));
+/// ```
+#[derive(Clone)]
+pub struct SeriesLimiter {
+    config: LimiterConfig,
+    /// Stamps the events.
+    clock: Arc<dyn Clock>,
+    scopes: HashMap<String, ScopeState>,
+    /// When every scope was swept last.
+    swept: Option<Instant>,
+    events: BTreeMap<(String, &'static str), u64>,
+}
+
+#[derive(Debug, Clone)]
+struct ScopeState {
+    /// When each series was seen last.
+    series: HashMap<u64, Instant>,
+    swept: Instant,
+}
+
+impl LimiterConfig {
+    /// Default length of the window series are tracked in.
+    pub const DEFAULT_WINDOW: Duration = Duration::from_secs(60 * 60);
+    /// Default number of scope values tracked at once.
+    pub const DEFAULT_MAX_SCOPES: usize = 10_000;
+    /// Default measurement of the Lines reporting decisions.
+    pub const DEFAULT_EVENT_MEASUREMENT: &'static str = "series_limiter";
+
+    /// Creates a config limiting series per measurement within [`Self::DEFAULT_WINDOW`],
+    /// which rejects Lines of new series beyond the limit.
+    pub fn new(max_series: usize) -> Self {
+        Self {
+            max_series,
+            max_scopes: Self::DEFAULT_MAX_SCOPES,
+            scope: LimitScope::default(),
+            window: Self::DEFAULT_WINDOW,
+            action: LimitAction::default(),
+            event_measurement: MeasurementName::new(Self::DEFAULT_EVENT_MEASUREMENT)
+                .expect("Default measurement name is valid"),
+        }
+    }
+
+    /// Sets what the limit applies to.
+    pub fn with_scope(mut self, scope: LimitScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Limits the number of scope values with series in the window,
+    /// [`Self::DEFAULT_MAX_SCOPES`] by default.
+    /// Lines of further scope values are rejected.
+    pub fn with_max_scopes(mut self, max_scopes: usize) -> Self {
+        self.max_scopes = max_scopes;
+        self
+    }
+
+    /// Sets how long a series counts against the limit after its last Line.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets what happens to Lines of new series beyond the limit.
+    pub fn with_action(mut self, action: LimitAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Sets the measurement of the Lines reporting decisions.
+    pub fn with_event_measurement(mut self, measurement: MeasurementName) -> Self {
+        self.event_measurement = measurement;
+        self
+    }
+}
+
+impl SeriesLimiter {
+    /// Expired series are swept at most this many times per window.
+    const SWEEPS_PER_WINDOW: u32 = 16;
+    /// Action of Lines rejected for a scope value beyond the limit of scopes.
+    const SCOPE_REJECTED: &'static str = "scope_rejected";
+
+    /// Creates a limiter that has not seen any series yet,
+    /// stamping its events with the [`SystemClock`].
+    pub fn new(config: LimiterConfig) -> Self {
+        Self {
+            config,
+            clock: Arc::new(SystemClock),
+            scopes: HashMap::new(),
+            swept: None,
+            events: BTreeMap::new(),
+        }
+    }
+
+    /// Stamps the events with another clock, e.g. a [`crate::ManualClock`] in tests.
+    ///
+    /// Series still expire by [`Instant`], unless [`Self::check_at`] is used.
+    pub fn with_clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Decides on the Line as of now.
+    pub fn check(&mut self, line: InfluxLine) -> LimitDecision {
+        self.check_at(line, Instant::now())
+    }
+
+    /// Decides on the Line as of the given instant,
+    /// which must not be earlier than the one of any previous check.
+    pub fn check_at(&mut self, line: InfluxLine, now: Instant) -> LimitDecision {
+        self.sweep(now);
+        let scope = self.scope_value(&line);
+        let window = self.config.window;
+        if self.scopes.len() >= self.config.max_scopes && !self.scopes.contains_key(&scope) {
+            *self
+                .events
+                .entry((String::new(), Self::SCOPE_REJECTED))
+                .or_default() += 1;
+            return LimitDecision::Rejected(line);
+        }
+        let state = self
+            .scopes
+            .entry(scope.clone())
+            .or_insert_with(|| ScopeState {
+                series: HashMap::new(),
+                swept: now,
+            });
+
+        let series = series_hash(&line);
+        if state.touch(series, now) {
+            return LimitDecision::Accepted(line);
+        }
+        if state.series.len() >= self.config.max_series {
+            state.sweep(now, window);
+        }
+        if state.series.len() < self.config.max_series {
+            state.series.insert(series, now);
+            return LimitDecision::Accepted(line);
+        }
+
+        let (decision, action) = match &self.config.action {
+            LimitAction::Reject => (LimitDecision::Rejected(line), "rejected"),
+            LimitAction::Sample(share) => {
+                if (series as f64 / u64::MAX as f64) < *share {
+                    state.series.insert(series, now);
+                    (LimitDecision::Accepted(line), "sampled")
+                } else {
+                    (LimitDecision::Rejected(line), "rejected")
+                }
+            }
+            LimitAction::DropTags(keys) => match reduce(&line, keys) {
+                Some(reduced) => {
+                    let series = series_hash(&reduced);
+                    if !state.touch(series, now) {
+                        state.series.insert(series, now);
+                    }
+                    (LimitDecision::Reduced(reduced), "reduced")
+                }
+                // None of the tags to drop are there, so the series stays new.
+                None => (LimitDecision::Rejected(line), "rejected"),
+            },
+        };
+        *self.events.entry((scope, action)).or_default() += 1;
+        decision
+    }
+
+    /// Returns the number of series tracked for a scope value,
+    /// e.g. a measurement name, including ones that have expired but were not swept yet.
+    pub fn series<S>(&self, scope: S) -> usize
+    where
+        S: AsRef<str>,
+    {
+        self.scopes
+            .get(scope.as_ref())
+            .map_or(0, |state| state.series.len())
+    }
+
+    /// Returns a Line per scope value and action counting decisions since the last call,
+    /// e.g. `series_limiter,scope=measurement,value=cpu,action=rejected count=10i,series=100i,limit=100i`.
+    ///
+    /// The `scope` tag is either `measurement` or the limited tag key,
+    /// and the `action` tag is one of `rejected`, `sampled` or `reduced`.
+    /// The `value` tag is missing for Lines without the limited tag.
+    ///
+    /// Lines of scope values beyond [`LimiterConfig::with_max_scopes`]
+    /// are counted with the `scope_rejected` action and without the `value` tag,
+    /// in which case `series` and `limit` count scope values instead.
+    pub fn drain_events(&mut self) -> Vec<InfluxLine> {
+        let timestamp = self.clock.now();
+        let scope = match &self.config.scope {
+            LimitScope::Measurement => "measurement",
+            LimitScope::Tag(key) => key.as_ref(),
+        };
+
+        let mut events = Vec::new();
+        for ((value, action), count) in std::mem::take(&mut self.events) {
+            let mut tags = vec![(tag_key("scope"), tag_key(scope))];
+            if let Ok(value) = KeyName::new(value.as_str()) {
+                tags.push((tag_key("value"), value));
+            }
+            tags.push((tag_key("action"), tag_key(action)));
+
+            let (series, limit) = match action {
+                Self::SCOPE_REJECTED => (self.scopes.len(), self.config.max_scopes),
+                _ => (self.series(&value), self.config.max_series),
+            };
+            let fields = [
+                ("count", count),
+                ("series", series as u64),
+                ("limit", limit as u64),
+            ]
+            .map(|(key, value)| (tag_key(key), (value.min(i64::MAX as u64) as i64).into()));
+
+            events.push(
+                InfluxLine::full(
+                    self.config.event_measurement.clone(),
+                    tags,
+                    fields,
+                    Some(timestamp),
+                )
+                .expect("Event has fields"),
+            );
+        }
+        events
+    }
+
+    /// Sweeps expired series of every scope, and forgets scopes left without series.
+    fn sweep(&mut self, now: Instant) {
+        let window = self.config.window;
+        let due = self
+            .swept
+            .is_none_or(|swept| now.duration_since(swept) >= window / Self::SWEEPS_PER_WINDOW);
+        if !due {
+            return;
+        }
+        self.scopes.retain(|_, state| {
+            state.sweep(now, window);
+            !state.series.is_empty()
+        });
+        self.swept = Some(now);
+    }
+
+    fn scope_value(&self, line: &InfluxLine) -> String {
+        match &self.config.scope {
+            LimitScope::Measurement => line.measurement().as_ref().to_owned(),
+            LimitScope::Tag(key) => line
+                .tag(key.as_ref())
+                .map(|value| value.as_ref().to_owned())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Debug for SeriesLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SeriesLimiter")
+            .field("config", &self.config)
+            .field("scopes", &self.scopes)
+            .field("swept", &self.swept)
+            .field("events", &self.events)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ScopeState {
+    /// Refreshes a known series and tells whether it was known.
+    fn touch(&mut self, series: u64, now: Instant) -> bool {
+        match self.series.get_mut(&series) {
+            Some(seen) => {
+                *seen = now;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn sweep(&mut self, now: Instant, window: Duration) {
+        if now.duration_since(self.swept) < window / SeriesLimiter::SWEEPS_PER_WINDOW {
+            return;
+        }
+        self.series
+            .retain(|_, seen| now.duration_since(*seen) < window);
+        self.swept = now;
+    }
+}
+
+/// Builds keys of event Lines, which are known to be valid.
+fn tag_key(name: &str) -> KeyName {
+    KeyName::new(name).expect("Event keys are valid")
+}
+
+/// Removes the tags from the Line, unless it has none of them.
+fn reduce(line: &InfluxLine, keys: &[KeyName]) -> Option<InfluxLine> {
+    if !line.tags().any(|(key, _)| keys.contains(key)) {
+        return None;
+    }
+
+    let reduced = InfluxLine::full(
+        line.measurement().clone(),
+        line.tags()
+            .filter(|(key, _)| !keys.contains(key))
+            .map(|(key, value)| (key.clone(), value.clone())),
+        line.fields()
+            .map(|(key, value)| (key.clone(), value.clone())),
+        line.timestamp(),
+    )
+    .expect("Fields are kept");
+    Some(reduced)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        InfluxLine, KeyName, LimitAction, LimitDecision, LimitScope, LimiterConfig, ManualClock,
+        SeriesLimiter, Timestamp,
+    };
+
+    fn line(measurement: &str, tenant: &str, host: usize) -> InfluxLine {
+        InfluxLine::try_new(measurement, "usage", 0.5)
+            .and_then(|line| line.try_with_tag("tenant", tenant))
+            .and_then(|line| line.try_with_tag("host", format!("web-{}", host)))
+            .unwrap()
+    }
+
+    fn key(name: &str) -> KeyName {
+        KeyName::new(name).unwrap()
+    }
+
+    fn accepted(decision: &LimitDecision) -> bool {
+        matches!(decision, LimitDecision::Accepted(_))
+    }
+
+    #[test]
+    fn limits_each_measurement_separately() {
+        let mut limiter = SeriesLimiter::new(LimiterConfig::new(3));
+
+        let cpu: Vec<_> = (0..5)
+            .map(|host| accepted(&limiter.check(line("cpu", "a", host))))
+            .collect();
+        let mem: Vec<_> = (0..3)
+            .map(|host| accepted(&limiter.check(line("mem", "a", host))))
+            .collect();
+
+        assert_eq!(vec![true, true, true, false, false], cpu);
+        assert_eq!(vec![true, true, true], mem);
+        assert_eq!(3, limiter.series("cpu"));
+    }
+
+    #[test]
+    fn limits_each_tag_value_separately() {
+        let config = LimiterConfig::new(2).with_scope(LimitScope::Tag(key("tenant")));
+        let mut limiter = SeriesLimiter::new(config);
+
+        for host in 0..4 {
+            limiter.check(line("cpu", "noisy", host));
+        }
+        let quiet = limiter.check(line("mem", "quiet", 0));
+        let untagged = limiter.check(InfluxLine::try_new("cpu", "usage", 1).unwrap());
+
+        assert!(accepted(&quiet));
+        assert!(accepted(&untagged));
+        assert_eq!(2, limiter.series("noisy"));
+        assert_eq!(1, limiter.series(""));
+    }
+
+    #[test]
+    fn series_expire_after_window() {
+        let config = LimiterConfig::new(1).with_window(Duration::from_secs(60));
+        let mut limiter = SeriesLimiter::new(config);
+        let start = Instant::now();
+
+        let first = limiter.check_at(line("cpu", "a", 0), start);
+        let blocked = limiter.check_at(line("cpu", "a", 1), start + Duration::from_secs(30));
+        let refreshed = limiter.check_at(line("cpu", "a", 0), start + Duration::from_secs(50));
+        let still_blocked = limiter.check_at(line("cpu", "a", 1), start + Duration::from_secs(100));
+        let expired = limiter.check_at(line("cpu", "a", 1), start + Duration::from_secs(111));
+
+        assert!(accepted(&first));
+        assert!(!accepted(&blocked));
+        assert!(accepted(&refreshed));
+        assert!(!accepted(&still_blocked));
+        assert!(accepted(&expired));
+    }
+
+    #[test]
+    fn forgets_expired_scopes() {
+        let config = LimiterConfig::new(1)
+            .with_scope(LimitScope::Tag(key("tenant")))
+            .with_window(Duration::from_secs(60));
+        let mut limiter = SeriesLimiter::new(config);
+        let start = Instant::now();
+
+        for tenant in 0..100 {
+            limiter.check_at(line("cpu", &tenant.to_string(), 0), start);
+        }
+        limiter.check_at(line("cpu", "kept", 0), start + Duration::from_secs(30));
+        limiter.check_at(line("cpu", "new", 0), start + Duration::from_secs(61));
+
+        assert_eq!(2, limiter.scopes.len());
+        assert_eq!(0, limiter.series("0"));
+        assert_eq!(1, limiter.series("kept"));
+    }
+
+    #[test]
+    fn limits_scopes() {
+        let config = LimiterConfig::new(10)
+            .with_scope(LimitScope::Tag(key("tenant")))
+            .with_max_scopes(2);
+        let mut limiter = SeriesLimiter::new(config);
+
+        let decisions: Vec<_> = ["a", "b", "c", "a", "d"]
+            .into_iter()
+            .map(|tenant| accepted(&limiter.check(line("cpu", tenant, 0))))
+            .collect();
+        let events: Vec<_> = limiter
+            .drain_events()
+            .iter()
+            .map(|event| event.to_string())
+            .collect();
+
+        assert_eq!(vec![true, true, false, true, false], decisions);
+        assert_eq!(1, events.len());
+        assert!(events[0].starts_with(
+            "series_limiter,scope=tenant,action=scope_rejected count=2i,series=2i,limit=2i "
+        ));
+    }
+
+    #[test]
+    fn drops_tags_of_new_series() {
+        let config = LimiterConfig::new(1).with_action(LimitAction::DropTags(vec![key("host")]));
+        let mut limiter = SeriesLimiter::new(config);
+
+        limiter.check(line("cpu", "a", 0));
+        let reduced = limiter.check(line("cpu", "a", 1));
+        let known = limiter.check(line("cpu", "a", 0));
+
+        assert_eq!(
+            LimitDecision::Reduced(
+                InfluxLine::try_new("cpu", "usage", 0.5)
+                    .and_then(|line| line.try_with_tag("tenant", "a"))
+                    .unwrap()
+            ),
+            reduced
+        );
+        assert!(accepted(&known));
+        assert_eq!(2, limiter.series("cpu"));
+    }
+
+    #[rstest::rstest]
+    #[case::none(0.0, 0)]
+    #[case::all(1.0, 100)]
+    #[case::half(0.5, 50)]
+    fn samples_new_series(#[case] share: f64, #[case] expected: usize) {
+        let config = LimiterConfig::new(0).with_action(LimitAction::Sample(share));
+        let mut limiter = SeriesLimiter::new(config);
+
+        let admitted = (0..100)
+            .filter(|host| accepted(&limiter.check(line("cpu", "a", *host))))
+            .count();
+        // Sampled series are remembered, so they are accepted again.
+        let again = (0..100)
+            .filter(|host| accepted(&limiter.check(line("cpu", "a", *host))))
+            .count();
+
+        assert!(admitted.abs_diff(expected) <= 15, "admitted {}", admitted);
+        assert_eq!(admitted, again);
+    }
+
+    #[test]
+    fn reports_decisions_as_lines() {
+        let config = LimiterConfig::new(1)
+            .with_scope(LimitScope::Tag(key("tenant")))
+            .with_event_measurement("limits".try_into().unwrap());
+        let mut limiter = SeriesLimiter::new(config)
+            .with_clock(ManualClock::new(Timestamp::from(1704067200000000000_i64)));
+
+        for host in 0..3 {
+            limiter.check(line("cpu", "a", host));
+        }
+        let events: Vec<_> = limiter
+            .drain_events()
+            .iter()
+            .map(|event| event.to_string())
+            .collect();
+
+        assert_eq!(
+            vec![
+                "limits,scope=tenant,value=a,action=rejected count=2i,series=1i,limit=1i 1704067200000000000"
+            ],
+            events
+        );
+        assert!(limiter.drain_events().is_empty());
+    }
+}
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use crate::KeyName;
 
 /// Small HashMap-like linear storage intended for small collections
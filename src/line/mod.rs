@@ -1,13 +1,13 @@
 mod hash_like;
 mod parsing;
 
-use std::fmt::{Display, Write};
-use std::str::FromStr;
+use core::fmt::{Display, Write};
+use core::str::FromStr;
 
 use hash_like::KeyValueStorage;
 use parsing::LinearLineParser;
 
-use crate::{InfluxLineError, InfluxValue, KeyName, MeasurementName, Timestamp};
+use crate::{InfluxLineError, InfluxValue, KeyName, MeasurementName, Precision, Timestamp};
 
 /// Implements InfluxDB Line Protocol V2
 /// described [here](https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/).
@@ -342,6 +342,74 @@ impl InfluxLine {
         self.fields.put(field.try_into()?, value.into());
         Ok(self)
     }
+
+    /// Parses a Line whose raw timestamp is given in a coarser precision,
+    /// as agreed upon with the writer, e.g. via the `precision` query parameter.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influx_line::*;
+    ///
+    /// let line = InfluxLine::parse_with_precision("human age=15i 1704067200", Precision::Seconds)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(Some(Timestamp::from(1704067200000000000_i64)), line.timestamp());
+    /// ```
+    pub fn parse_with_precision(s: &str, precision: Precision) -> Result<Self, InfluxLineError> {
+        let line = Self::from_str(s)?;
+        match line.timestamp {
+            Some(timestamp) if precision != Precision::Nanoseconds => {
+                Ok(line.with_timestamp(Timestamp::from_precision(timestamp.into(), precision)?))
+            }
+            _ => Ok(line),
+        }
+    }
+
+    /// Merges a point of the same series and timestamp into this one,
+    /// the way InfluxDB does on write: fields of `other` win,
+    /// and the ones this Line does not have yet are added at the end.
+    ///
+    /// The series is the measurement with the tag set, regardless of the tag order.
+    /// Lines without a timestamp are considered simultaneous,
+    /// as the server stamps a whole write request with a single time,
+    /// so only merge them when they go into the same request.
+    /// Otherwise, fails with [`InfluxLineError::SeriesMismatch`] and leaves this Line unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influx_line::*;
+    ///
+    /// let mut line: InfluxLine = "cpu,host=a,zone=b usage=0.5,cores=4i 10".parse().unwrap();
+    /// let later: InfluxLine = "cpu,zone=b,host=a usage=0.7,temperature=61.5 10".parse().unwrap();
+    /// let other_time: InfluxLine = "cpu,host=a,zone=b usage=0.9 20".parse().unwrap();
+    ///
+    /// line.merge(later).unwrap();
+    ///
+    /// assert_eq!("cpu,host=a,zone=b usage=0.7,cores=4i,temperature=61.5 10", line.to_string());
+    /// assert!(line.merge(other_time).is_err());
+    /// ```
+    pub fn merge(&mut self, other: InfluxLine) -> Result<(), InfluxLineError> {
+        if !self.is_same_point(&other) {
+            return Err(InfluxLineError::SeriesMismatch);
+        }
+
+        for (key, value) in other.fields.iter() {
+            self.fields.put(key.clone(), value.clone());
+        }
+        Ok(())
+    }
+
+    /// Whether both Lines are of the same series and timestamp, see [`Self::merge`].
+    pub(crate) fn is_same_point(&self, other: &InfluxLine) -> bool {
+        self.measurement == other.measurement
+            && self.timestamp == other.timestamp
+            && self.tags.iter().count() == other.tags.iter().count()
+            && other
+                .tags()
+                .all(|(key, value)| self.tags.get(key) == Some(value))
+    }
 }
 
 impl FromStr for InfluxLine {
@@ -354,8 +422,8 @@ impl FromStr for InfluxLine {
 }
 
 impl Display for InfluxLine {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.measurement)?;
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.measurement)?;
 
         for (key, value) in self.tags.iter() {
             write!(f, ",{}={}", key, value)?;
@@ -383,39 +451,39 @@ impl Display for InfluxLine {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use core::str::FromStr;
 
-    use crate::{InfluxLine, Timestamp};
+    use crate::{InfluxLine, InfluxLineError, Timestamp};
 
     #[rstest::rstest]
     #[case::minimal(
         "measurement field1=228u",
-        InfluxLine::try_new("measurement", "field1", 228 as u32).unwrap()
+        InfluxLine::try_new("measurement", "field1", 228_u32).unwrap()
     )]
     #[case::minimal_with_newline(
         "measurement field1=228u\n",
-        InfluxLine::try_new("measurement", "field1", 228 as u32).unwrap()
+        InfluxLine::try_new("measurement", "field1", 228_u32).unwrap()
     )]
     #[case::full(
         "human,language=ru,location=siberia age=25u,is\\ epic=true,balance=-15.57,name=\"Egorka\" 1704067200000000000",
-        InfluxLine::try_new("human", "age", 25 as u32)
+        InfluxLine::try_new("human", "age", 25_u32)
             .and_then(|l| l.try_with_field("is epic", true))
             .and_then(|l| l.try_with_field("balance", -15.57))
             .and_then(|l| l.try_with_field("name", "Egorka"))
             .and_then(|l| l.try_with_tag("language", "ru"))
             .and_then(|l| l.try_with_tag("location", "siberia"))
-            .map(|l| l.with_timestamp(Timestamp::from(1704067200000000000 as i64)))
+            .map(|l| l.with_timestamp(Timestamp::from(1704067200000000000_i64)))
             .unwrap()
     )]
     #[case::full(
         "human,language=ru,location=siberia age=25u,is\\ epic=true,balance=-15.57,name=\"Egorka\" 1704067200000000000\n",
-        InfluxLine::try_new("human", "age", 25 as u32)
+        InfluxLine::try_new("human", "age", 25_u32)
             .and_then(|l| l.try_with_field("is epic", true))
             .and_then(|l| l.try_with_field("balance", -15.57))
             .and_then(|l| l.try_with_field("name", "Egorka"))
             .and_then(|l| l.try_with_tag("language", "ru"))
             .and_then(|l| l.try_with_tag("location", "siberia"))
-            .map(|l| l.with_timestamp(Timestamp::from(1704067200000000000 as i64)))
+            .map(|l| l.with_timestamp(Timestamp::from(1704067200000000000_i64)))
             .unwrap()
     )]
     fn successful_line_parsing(#[case] input: &str, #[case] expected_line: InfluxLine) {
@@ -438,36 +506,42 @@ mod tests {
     #[rstest::rstest]
     #[case::minimal(
         "measurement field1=228u",
-        InfluxLine::try_new("measurement", "field1", 228 as u32).unwrap()
+        InfluxLine::try_new("measurement", "field1", 228_u32).unwrap()
     )]
     #[rstest::rstest]
     #[case::minimal_with_newline(
         "measurement field1=228u\n",
-        InfluxLine::try_new("measurement", "field1", 228 as u32)
+        InfluxLine::try_new("measurement", "field1", 228_u32)
             .map(|l| l.add_newline())
             .unwrap()
     )]
     #[case::full(
         "human,language=ru,location=siberia age=25u,is\\ epic=true,balance=-15.57,name=\"Egorka\" 1704067200000000000",
-        InfluxLine::try_new("human", "age", 25 as u32)
+        InfluxLine::try_new("human", "age", 25_u32)
             .and_then(|l| l.try_with_field("is epic", true))
             .and_then(|l| l.try_with_field("balance", -15.57))
             .and_then(|l| l.try_with_field("name", "Egorka"))
             .and_then(|l| l.try_with_tag("language", "ru"))
             .and_then(|l| l.try_with_tag("location", "siberia"))
-            .map(|l| l.with_timestamp(Timestamp::from(1704067200000000000 as i64)))
+            .map(|l| l.with_timestamp(Timestamp::from(1704067200000000000_i64)))
             .unwrap()
     )]
     #[case::full_with_newline(
         "human,language=ru,location=siberia age=25u,is\\ epic=true,balance=-15.57,name=\"Egorka\" 1704067200000000000\n",
-        InfluxLine::try_new("human", "age", 25 as u32)
+        InfluxLine::try_new("human", "age", 25_u32)
             .and_then(|l| l.try_with_field("is epic", true))
             .and_then(|l| l.try_with_field("balance", -15.57))
             .and_then(|l| l.try_with_field("name", "Egorka"))
             .and_then(|l| l.try_with_tag("language", "ru"))
             .and_then(|l| l.try_with_tag("location", "siberia"))
             .map(|l| l.add_newline())
-            .map(|l| l.with_timestamp(Timestamp::from(1704067200000000000 as i64)))
+            .map(|l| l.with_timestamp(Timestamp::from(1704067200000000000_i64)))
+            .unwrap()
+    )]
+    #[case::escaped_measurement(
+        r#"my\ cpu\,total,host=a v=1i"#,
+        InfluxLine::try_new("my cpu,total", "v", 1)
+            .and_then(|l| l.try_with_tag("host", "a"))
             .unwrap()
     )]
     fn display_line(#[case] expected_str: &str, #[case] line: InfluxLine) {
@@ -475,4 +549,34 @@ mod tests {
 
         assert_eq!(expected_str, actual_str);
     }
+
+    #[rstest::rstest]
+    #[case::same_fields("m,a=1 x=1i 5", "m,a=1 x=2i 5", "m,a=1 x=2i 5")]
+    #[case::new_fields("m,a=1 x=1i,y=1i 5", "m,a=1 z=2i,x=2i 5", "m,a=1 x=2i,y=1i,z=2i 5")]
+    #[case::tag_order("m,a=1,b=2 x=1i", "m,b=2,a=1 y=2i", "m,a=1,b=2 x=1i,y=2i")]
+    fn merge(#[case] first: &str, #[case] second: &str, #[case] expected: &str) {
+        let mut merged = InfluxLine::from_str(first).expect("Must parse here");
+
+        merged
+            .merge(InfluxLine::from_str(second).expect("Must parse here"))
+            .expect("Must merge here");
+
+        assert_eq!(InfluxLine::from_str(expected).unwrap(), merged);
+    }
+
+    #[rstest::rstest]
+    #[case::measurement("m x=1i 5", "n x=2i 5")]
+    #[case::tag_value("m,a=1 x=1i 5", "m,a=2 x=2i 5")]
+    #[case::extra_tag("m,a=1 x=1i 5", "m,a=1,b=2 x=2i 5")]
+    #[case::missing_tag("m,a=1,b=2 x=1i 5", "m,a=1 x=2i 5")]
+    #[case::timestamp("m x=1i 5", "m x=2i 6")]
+    #[case::missing_timestamp("m x=1i 5", "m x=2i")]
+    fn merge_mismatch(#[case] first: &str, #[case] second: &str) {
+        let mut line = InfluxLine::from_str(first).expect("Must parse here");
+
+        let result = line.merge(InfluxLine::from_str(second).expect("Must parse here"));
+
+        assert!(matches!(result, Err(InfluxLineError::SeriesMismatch)));
+        assert_eq!(InfluxLine::from_str(first).unwrap(), line);
+    }
 }
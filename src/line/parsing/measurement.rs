@@ -38,7 +38,6 @@ impl MeasurementParser {
                     self.escaped = Escaped::Yes;
                 }
                 (Escaped::No, _) => (),
-                (Escaped::Yes, '\\') => (),
                 (Escaped::Yes, _) => {
                     self.escaped = Escaped::No;
                 }
@@ -48,3 +47,38 @@ impl MeasurementParser {
         Err(InfluxLineError::NoWhitespaceDelimiter)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{MeasurementParser, MeasurementTail};
+
+    #[rstest::rstest]
+    #[case::fields_tail("cpu field=true", "cpu", MeasurementTail::Fields("field=true"))]
+    #[case::tags_tail(
+        "cpu,tag=1 field=true",
+        "cpu",
+        MeasurementTail::Tags("tag=1 field=true")
+    )]
+    #[case::escaped_delimiters(
+        "c\\ p\\,u field=true",
+        "c\\ p\\,u",
+        MeasurementTail::Fields("field=true")
+    )]
+    #[case::escaped_backslash_before_delimiter(
+        "a\\\\ field=true",
+        "a\\\\",
+        MeasurementTail::Fields("field=true")
+    )]
+    fn successful_measurement_parsing(
+        #[case] input: &str,
+        #[case] expected_measurement: &str,
+        #[case] expected_tail: MeasurementTail,
+    ) {
+        let (actual_measurement, actual_tail) = MeasurementParser::new()
+            .process(input)
+            .expect("Must parse here");
+
+        assert_eq!(expected_measurement, actual_measurement);
+        assert_eq!(expected_tail, actual_tail);
+    }
+}
@@ -3,7 +3,8 @@ mod key;
 mod measurement;
 mod tag;
 
-use std::str::FromStr;
+use alloc::vec::Vec;
+use core::str::FromStr;
 
 use field::FieldParser;
 use measurement::{MeasurementParser, MeasurementTail};
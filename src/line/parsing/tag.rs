@@ -51,9 +51,6 @@ impl TagValueParser {
                     self.escaped = Escaped::Yes;
                 }
                 (Escaped::No, _) => (),
-                (Escaped::Yes, '\\') => {
-                    self.escaped = Escaped::Yes;
-                }
                 (Escaped::Yes, _) => {
                     self.escaped = Escaped::No;
                 }
@@ -99,6 +96,12 @@ mod tests {
         "fr💀",
         TagParserTail::Tag("my=man")
     )]
+    #[case::escaped_backslash_before_delimiter(
+        "tag=a\\\\,tag2=2 field=true",
+        "tag",
+        "a\\\\",
+        TagParserTail::Tag("tag2=2 field=true")
+    )]
     fn successful_tag_parsing(
         #[case] input: &str,
         #[case] expected_key: &str,
@@ -0,0 +1,278 @@
+mod rules;
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::{InfluxLine, Precision};
+
+pub use self::rules::{
+    FutureTimestamps, KeyCollisions, NumericStrings, NumericTags, TimestampPrecision, UniqueIdTags,
+    UnsortedTags,
+};
+
+/// A check of Lines for a single kind of problem.
+///
+/// Rules are fed every Line of the input in order,
+/// so they may remember what they have seen, e.g. keys of earlier Lines.
+/// The [`Linter`] adds the severity and the location to what the rule reports.
+pub trait Rule: Send {
+    /// Returns a short stable name, e.g. `unsorted-tags`, to refer to the rule.
+    fn name(&self) -> &'static str;
+
+    /// Returns the severity of findings unless overridden in the [`Linter`].
+    fn severity(&self) -> Severity;
+
+    /// Checks a Line and returns a message per problem found.
+    fn check(&mut self, line: &InfluxLine) -> Vec<String>;
+}
+
+/// How bad a [`Finding`] is, from the least to the most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum Severity {
+    #[display("info")]
+    Info,
+    #[display("warning")]
+    Warning,
+    #[display("error")]
+    Error,
+}
+
+/// A problem reported by a [`Rule`] for a single Line.
+///
+/// [`Display`] formats it as `<line>: <severity>: <message> [<rule>]`,
+/// so prefixing it with a file name gives the usual compiler-like output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    rule: &'static str,
+    severity: Severity,
+    line: usize,
+    message: String,
+}
+
+/// Runs a set of [`Rule`]s over Lines and collects their [`Finding`]s.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let input = "\
+/// cpu,zone=b,host=a usage=0.5 1704067200000000000
+/// cpu,host=a,request=4f9c2d1e-8a3b-4c5d-9e6f-7a8b9c0d1e2f usage=0.5 1704067200
+/// ";
+///
+/// let findings = Linter::recommended().check_str(input);
+///
+/// let rules: Vec<_> = findings.iter().map(|finding| (finding.line(), finding.rule())).collect();
+/// assert_eq!(
+///     vec![(1, "unsorted-tags"), (2, "unique-id-tags"), (2, "timestamp-precision")],
+///     rules
+/// );
+/// assert_eq!(Severity::Warning, findings[1].severity());
+/// ```
+pub struct Linter {
+    rules: Vec<Box<dyn Rule>>,
+    severities: BTreeMap<&'static str, Severity>,
+    /// Precision of raw timestamps in [`Linter::check_str`].
+    precision: Precision,
+}
+
+impl Linter {
+    /// Name used for findings about Lines that cannot be parsed at all.
+    pub const PARSE_RULE: &'static str = "parse";
+
+    /// Creates a linter without any rules, parsing raw timestamps in nanoseconds.
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            severities: BTreeMap::new(),
+            precision: Precision::Nanoseconds,
+        }
+    }
+
+    /// Creates a linter with every built-in rule in its default configuration.
+    ///
+    /// Timestamps are expected in nanoseconds
+    /// and may be at most [`FutureTimestamps::DEFAULT_SKEW`] ahead of the system clock.
+    pub fn recommended() -> Self {
+        Self::recommended_for(Precision::Nanoseconds)
+    }
+
+    /// Same as [`Self::recommended`], but expects raw timestamps in another precision.
+    pub fn recommended_for(precision: Precision) -> Self {
+        Self::new()
+            .with_precision(precision)
+            .with_rule(UniqueIdTags::new())
+            .with_rule(NumericTags::new())
+            .with_rule(KeyCollisions::new())
+            .with_rule(UnsortedTags::new())
+            .with_rule(TimestampPrecision::new(precision))
+            .with_rule(FutureTimestamps::new())
+            .with_rule(NumericStrings::new())
+    }
+
+    /// Adds a rule, which runs after the ones added before.
+    pub fn with_rule<R>(mut self, rule: R) -> Self
+    where
+        R: Rule + 'static,
+    {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Parses raw timestamps of [`Self::check_str`] in another precision.
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Overrides the severity of findings of a rule by its name.
+    pub fn with_severity(mut self, rule: &'static str, severity: Severity) -> Self {
+        self.severities.insert(rule, severity);
+        self
+    }
+
+    /// Runs every rule over the Line and returns their findings at the given location.
+    pub fn check(&mut self, location: usize, line: &InfluxLine) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for rule in self.rules.iter_mut() {
+            let severity = self
+                .severities
+                .get(rule.name())
+                .copied()
+                .unwrap_or(rule.severity());
+            findings.extend(rule.check(line).into_iter().map(|message| Finding {
+                rule: rule.name(),
+                severity,
+                line: location,
+                message,
+            }));
+        }
+        findings
+    }
+
+    /// Parses and checks every Line of a Line Protocol text,
+    /// locating findings by 1-based line numbers.
+    ///
+    /// Raw timestamps are read in [`Self::with_precision`].
+    /// Blank lines and `#` comments are skipped.
+    /// Lines that fail to parse are reported as [`Severity::Error`]
+    /// findings of [`Self::PARSE_RULE`],
+    /// including ones whose timestamps only fit a finer precision.
+    pub fn check_str(&mut self, input: &str) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for (index, text) in input.lines().enumerate() {
+            let trimmed = text.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            match InfluxLine::parse_with_precision(text, self.precision) {
+                Ok(line) => findings.extend(self.check(index + 1, &line)),
+                Err(error) => findings.push(Finding {
+                    rule: Self::PARSE_RULE,
+                    severity: Severity::Error,
+                    line: index + 1,
+                    message: error.to_string(),
+                }),
+            }
+        }
+        findings
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Finding {
+    /// Returns the name of the rule that reported the finding.
+    pub fn rule(&self) -> &'static str {
+        self.rule
+    }
+
+    /// Returns the severity of the finding.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Returns the location of the Line, usually its 1-based line number.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}: {} [{}]",
+            self.line, self.severity, self.message, self.rule
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Linter, Precision, Severity, UnsortedTags};
+
+    #[test]
+    fn reports_parse_errors_and_skips_comments() {
+        let input = "# comment\n\ncpu usage=0.5\ncpu\n";
+
+        let findings = Linter::recommended().check_str(input);
+
+        assert_eq!(1, findings.len());
+        assert_eq!(4, findings[0].line());
+        assert_eq!(Linter::PARSE_RULE, findings[0].rule());
+        assert_eq!(Severity::Error, findings[0].severity());
+    }
+
+    #[rstest::rstest]
+    #[case::seconds_as_nanoseconds(
+        Precision::Nanoseconds,
+        "cpu v=1 1704067200",
+        "timestamp-precision"
+    )]
+    #[case::nanoseconds_as_seconds(
+        Precision::Seconds,
+        "cpu v=1 1704067200000000000",
+        Linter::PARSE_RULE
+    )]
+    #[case::milliseconds_as_microseconds(
+        Precision::Microseconds,
+        "cpu v=1 1704067200000",
+        "timestamp-precision"
+    )]
+    fn parses_in_precision(
+        #[case] precision: Precision,
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let findings = Linter::recommended_for(precision).check_str(input);
+
+        let rules: Vec<_> = findings.iter().map(|finding| finding.rule()).collect();
+        assert_eq!(vec![expected], rules);
+    }
+
+    #[test]
+    fn overrides_severity() {
+        let mut linter = Linter::new()
+            .with_rule(UnsortedTags::new())
+            .with_severity("unsorted-tags", Severity::Error);
+
+        let findings = linter.check_str("cpu,b=1,a=2 usage=0.5");
+
+        assert_eq!(
+            "1: error: Tag `a` should come before `b` [unsorted-tags]",
+            findings[0].to_string()
+        );
+    }
+}
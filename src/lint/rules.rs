@@ -0,0 +1,568 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{
+    Clock, InfluxLine, InfluxValue, KeyName, ManualClock, MeasurementName, Precision, Rule,
+    Severity, SystemClock, Timestamp,
+};
+
+/// Reports tag values shaped like UUIDs, ULIDs or long hex strings.
+///
+/// Each unique value creates a new series, so such tags grow cardinality
+/// without bounds. They usually belong in fields.
+#[derive(Debug, Clone, Default)]
+pub struct UniqueIdTags;
+
+/// Reports tag values that are numbers, which are usually measured values
+/// that belong in fields, since tags can be neither aggregated nor compared numerically.
+///
+/// Keys that hold numeric codes by design, e.g. `status`, may be allowed.
+#[derive(Debug, Clone, Default)]
+pub struct NumericTags {
+    allowed_keys: BTreeSet<KeyName>,
+}
+
+/// Reports keys used for both a tag and a field of the same measurement,
+/// whether in a single Line or across Lines.
+///
+/// InfluxDB accepts such writes, but queries have to disambiguate the key,
+/// and some tools silently pick one of the two columns.
+/// Each key is reported once per measurement.
+#[derive(Debug, Clone, Default)]
+pub struct KeyCollisions {
+    measurements: BTreeMap<MeasurementName, MeasurementKeys>,
+}
+
+/// Reports Lines whose tags are not sorted by key.
+///
+/// InfluxDB sorts tags itself, but writers that sort them beforehand
+/// save it the work, as its write path documentation recommends.
+#[derive(Debug, Clone, Default)]
+pub struct UnsortedTags;
+
+/// Reports timestamps that are implausible in the expected precision,
+/// but plausible in another one, e.g. seconds written where nanoseconds are expected.
+///
+/// Plausible timestamps lie between the years 2000 and 2100.
+#[derive(Debug, Clone)]
+pub struct TimestampPrecision {
+    precision: Precision,
+}
+
+/// Reports timestamps that are ahead of the current time by more than a skew.
+#[derive(Clone)]
+pub struct FutureTimestamps {
+    skew: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+/// Reports string fields holding numbers, e.g. `value="42"`,
+/// which cannot be aggregated and conflict with numeric writes of the field.
+#[derive(Debug, Clone, Default)]
+pub struct NumericStrings;
+
+#[derive(Debug, Clone, Default)]
+struct MeasurementKeys {
+    tags: BTreeSet<KeyName>,
+    fields: BTreeSet<KeyName>,
+    reported: BTreeSet<KeyName>,
+}
+
+impl UniqueIdTags {
+    /// Creates the rule.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Rule for UniqueIdTags {
+    fn name(&self) -> &'static str {
+        "unique-id-tags"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&mut self, line: &InfluxLine) -> Vec<String> {
+        line.tags()
+            .filter(|(_, value)| looks_like_id(value.as_ref()))
+            .map(|(key, value)| {
+                format!(
+                    "Tag `{}` value `{}` looks like a unique ID, which creates a series per value",
+                    key.as_ref(),
+                    value.as_ref()
+                )
+            })
+            .collect()
+    }
+}
+
+impl NumericTags {
+    /// Creates the rule without any allowed keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows numeric values for a tag key.
+    pub fn with_allowed_key(mut self, key: KeyName) -> Self {
+        self.allowed_keys.insert(key);
+        self
+    }
+}
+
+impl Rule for NumericTags {
+    fn name(&self) -> &'static str {
+        "numeric-tags"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn check(&mut self, line: &InfluxLine) -> Vec<String> {
+        line.tags()
+            .filter(|(key, value)| !self.allowed_keys.contains(*key) && is_number(value.as_ref()))
+            .map(|(key, value)| {
+                format!(
+                    "Tag `{}` holds number `{}`, which may belong in a field",
+                    key.as_ref(),
+                    value.as_ref()
+                )
+            })
+            .collect()
+    }
+}
+
+impl KeyCollisions {
+    /// Creates the rule without any known keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Rule for KeyCollisions {
+    fn name(&self) -> &'static str {
+        "key-collisions"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&mut self, line: &InfluxLine) -> Vec<String> {
+        let keys = self
+            .measurements
+            .entry(line.measurement().clone())
+            .or_default();
+        keys.tags.extend(line.tags().map(|(key, _)| key.clone()));
+        keys.fields
+            .extend(line.fields().map(|(key, _)| key.clone()));
+
+        let colliding: BTreeSet<KeyName> = line
+            .tags()
+            .map(|(key, _)| key)
+            .chain(line.fields().map(|(key, _)| key))
+            .filter(|key| keys.tags.contains(*key) && keys.fields.contains(*key))
+            .cloned()
+            .collect();
+        let mut messages = Vec::new();
+        for key in colliding {
+            if keys.reported.insert(key.clone()) {
+                messages.push(format!(
+                    "Key `{}` of `{}` is used for both a tag and a field",
+                    key.as_ref(),
+                    line.measurement().as_ref()
+                ));
+            }
+        }
+        messages
+    }
+}
+
+impl UnsortedTags {
+    /// Creates the rule.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Rule for UnsortedTags {
+    fn name(&self) -> &'static str {
+        "unsorted-tags"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn check(&mut self, line: &InfluxLine) -> Vec<String> {
+        let keys: Vec<&str> = line.tags().map(|(key, _)| key.as_ref()).collect();
+        keys.windows(2)
+            .find(|pair| pair[0] > pair[1])
+            .map(|pair| format!("Tag `{}` should come before `{}`", pair[1], pair[0]))
+            .into_iter()
+            .collect()
+    }
+}
+
+impl TimestampPrecision {
+    /// Start of the plausible range, 2000-01-01, in seconds.
+    const PLAUSIBLE_FROM: i128 = 946_684_800;
+    /// End of the plausible range, 2100-01-01, in seconds.
+    const PLAUSIBLE_UNTIL: i128 = 4_102_444_800;
+
+    /// Creates the rule for raw timestamps written in the given precision.
+    pub fn new(precision: Precision) -> Self {
+        Self { precision }
+    }
+
+    fn is_plausible(raw: i64, precision: Precision) -> bool {
+        let seconds = raw as i128 * precision.nanoseconds() as i128 / 1_000_000_000;
+        (Self::PLAUSIBLE_FROM..Self::PLAUSIBLE_UNTIL).contains(&seconds)
+    }
+}
+
+impl Rule for TimestampPrecision {
+    fn name(&self) -> &'static str {
+        "timestamp-precision"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&mut self, line: &InfluxLine) -> Vec<String> {
+        let Some(timestamp) = line.timestamp() else {
+            return Vec::new();
+        };
+
+        let raw = timestamp.to_precision(self.precision);
+        if Self::is_plausible(raw, self.precision) {
+            return Vec::new();
+        }
+
+        [
+            Precision::Nanoseconds,
+            Precision::Microseconds,
+            Precision::Milliseconds,
+            Precision::Seconds,
+        ]
+        .into_iter()
+        .find(|precision| Self::is_plausible(raw, *precision))
+        .map(|precision| {
+            format!(
+                "Timestamp `{}` looks like {} rather than {} precision",
+                raw, precision, self.precision
+            )
+        })
+        .into_iter()
+        .collect()
+    }
+}
+
+impl FutureTimestamps {
+    /// Default tolerated difference between clocks of writers and the linter.
+    pub const DEFAULT_SKEW: Duration = Duration::from_secs(5 * 60);
+
+    /// Creates the rule with [`Self::DEFAULT_SKEW`], comparing against the system clock.
+    pub fn new() -> Self {
+        Self {
+            skew: Self::DEFAULT_SKEW,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets the tolerated difference between clocks.
+    pub fn with_skew(mut self, skew: Duration) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    /// Compares against another clock, e.g. a [`ManualClock`] in tests.
+    pub fn with_clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Compares against a fixed instant instead of the system clock,
+    /// e.g. to lint data recorded in the past.
+    pub fn with_now(self, now: Timestamp) -> Self {
+        self.with_clock(ManualClock::new(now))
+    }
+}
+
+impl Debug for FutureTimestamps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FutureTimestamps")
+            .field("skew", &self.skew)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for FutureTimestamps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for FutureTimestamps {
+    fn name(&self) -> &'static str {
+        "future-timestamps"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&mut self, line: &InfluxLine) -> Vec<String> {
+        let Some(timestamp) = line.timestamp() else {
+            return Vec::new();
+        };
+
+        let ahead = i64::from(timestamp).saturating_sub(self.clock.now().into());
+        if ahead <= 0 || (ahead as u128) <= self.skew.as_nanos() {
+            return Vec::new();
+        }
+
+        vec![format!(
+            "Timestamp is {}s in the future, beyond the allowed skew of {}s",
+            ahead / 1_000_000_000,
+            self.skew.as_secs()
+        )]
+    }
+}
+
+impl NumericStrings {
+    /// Creates the rule.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Rule for NumericStrings {
+    fn name(&self) -> &'static str {
+        "numeric-strings"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&mut self, line: &InfluxLine) -> Vec<String> {
+        line.fields()
+            .filter_map(|(key, value)| match value {
+                InfluxValue::String(string) if is_number(string.as_ref()) => Some(format!(
+                    "Field `{}` holds number `{}` as a string",
+                    key.as_ref(),
+                    string.as_ref()
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Accepts decimal integers and floats, but not `inf` or `NaN` that Rust also parses.
+fn is_number(value: &str) -> bool {
+    value.bytes().any(|byte| byte.is_ascii_digit())
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || matches!(byte, b'+' | b'-' | b'.' | b'e' | b'E'))
+        && value.parse::<f64>().is_ok()
+}
+
+fn looks_like_id(value: &str) -> bool {
+    is_uuid(value) || is_ulid(value) || is_long_hex(value)
+}
+
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.bytes().enumerate().all(|(index, byte)| match index {
+            8 | 13 | 18 | 23 => byte == b'-',
+            _ => byte.is_ascii_hexdigit(),
+        })
+}
+
+/// ULIDs use Crockford's base32, which lacks `I`, `L`, `O` and `U`.
+fn is_ulid(value: &str) -> bool {
+    value.len() == 26
+        && value.bytes().any(|byte| byte.is_ascii_digit())
+        && value.bytes().any(|byte| byte.is_ascii_uppercase())
+        && value.bytes().all(|byte| {
+            byte.is_ascii_digit()
+                || (byte.is_ascii_uppercase() && !matches!(byte, b'I' | b'L' | b'O' | b'U'))
+        })
+}
+
+/// Hashes and trace IDs, but not plain numbers or words like `deadbeefcafefeed`.
+fn is_long_hex(value: &str) -> bool {
+    value.len() >= 16
+        && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+        && value.bytes().any(|byte| byte.is_ascii_digit())
+        && value.bytes().any(|byte| byte.is_ascii_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use crate::{
+        FutureTimestamps, InfluxLine, KeyCollisions, ManualClock, NumericStrings, NumericTags,
+        Precision, Rule, Timestamp, TimestampPrecision, UniqueIdTags, UnsortedTags,
+    };
+
+    fn check(rule: &mut impl Rule, input: &str) -> Vec<String> {
+        rule.check(&InfluxLine::from_str(input).expect("Must parse here"))
+    }
+
+    #[rstest::rstest]
+    #[case::uuid("cpu,id=4f9c2d1e-8a3b-4c5d-9e6f-7a8b9c0d1e2f v=1", 1)]
+    #[case::ulid("cpu,id=01ARZ3NDEKTSV4RRFFQ69G5FAV v=1", 1)]
+    #[case::trace_id("cpu,trace=4bf92f3577b34da6a3ce929d0e0e4736 v=1", 1)]
+    #[case::hex_word("cpu,color=deadbeefcafefeed v=1", 0)]
+    #[case::host_name("cpu,host=web-01.example.com v=1", 0)]
+    #[case::short_hex("cpu,commit=a1b2c3d v=1", 0)]
+    fn unique_ids(#[case] input: &str, #[case] expected: usize) {
+        assert_eq!(expected, check(&mut UniqueIdTags::new(), input).len());
+    }
+
+    #[rstest::rstest]
+    #[case::integer("http,status=200 v=1", 1)]
+    #[case::float("cpu,load=0.75 v=1", 1)]
+    #[case::allowed_key("http,code=404 v=1", 0)]
+    #[case::version("app,version=1.2.3 v=1", 0)]
+    #[case::nan("cpu,state=NaN v=1", 0)]
+    fn numeric_tags(#[case] input: &str, #[case] expected: usize) {
+        let mut rule = NumericTags::new().with_allowed_key("code".try_into().unwrap());
+
+        assert_eq!(expected, check(&mut rule, input).len());
+    }
+
+    #[test]
+    fn key_collisions_across_lines_are_reported_once() {
+        let mut rule = KeyCollisions::new();
+
+        assert!(check(&mut rule, "cpu,host=a usage=1").is_empty());
+        assert!(check(&mut rule, "mem host=\"a\"").is_empty());
+        assert_eq!(
+            vec!["Key `host` of `cpu` is used for both a tag and a field"],
+            check(&mut rule, "cpu host=\"a\"")
+        );
+        assert!(check(&mut rule, "cpu,host=b usage=2").is_empty());
+    }
+
+    #[test]
+    fn key_collisions_within_a_line() {
+        let findings = check(&mut KeyCollisions::new(), "cpu,host=a host=\"a\"");
+
+        assert_eq!(1, findings.len());
+    }
+
+    #[rstest::rstest]
+    #[case::sorted("cpu,a=1,b=2,c=3 v=1", 0)]
+    #[case::unsorted("cpu,b=1,a=2,c=3 v=1", 1)]
+    #[case::no_tags("cpu v=1", 0)]
+    fn unsorted_tags(#[case] input: &str, #[case] expected: usize) {
+        assert_eq!(expected, check(&mut UnsortedTags::new(), input).len());
+    }
+
+    #[rstest::rstest]
+    #[case::nanoseconds(Precision::Nanoseconds, "cpu v=1 1704067200000000000", None)]
+    #[case::seconds_as_nanoseconds(Precision::Nanoseconds, "cpu v=1 1704067200", Some("s"))]
+    #[case::milliseconds_as_nanoseconds(
+        Precision::Nanoseconds,
+        "cpu v=1 1704067200000",
+        Some("ms")
+    )]
+    #[case::milliseconds_as_microseconds(
+        Precision::Microseconds,
+        "cpu v=1 1704067200000",
+        Some("ms")
+    )]
+    #[case::tiny(Precision::Nanoseconds, "cpu v=1 1", None)]
+    #[case::no_timestamp(Precision::Nanoseconds, "cpu v=1", None)]
+    fn timestamp_precisions(
+        #[case] expected_precision: Precision,
+        #[case] input: &str,
+        #[case] suggested: Option<&str>,
+    ) {
+        let line =
+            InfluxLine::parse_with_precision(input, expected_precision).expect("Must parse here");
+
+        let findings = TimestampPrecision::new(expected_precision).check(&line);
+
+        let expected: Vec<String> = suggested
+            .map(|suggested| {
+                format!(
+                    "Timestamp `{}` looks like {} rather than {} precision",
+                    input.rsplit(' ').next().unwrap(),
+                    suggested,
+                    expected_precision
+                )
+            })
+            .into_iter()
+            .collect();
+        assert_eq!(expected, findings);
+    }
+
+    #[test]
+    fn timestamp_precision_checks_raw_values() {
+        // Parsed in seconds, so the raw value is still plausible.
+        let line = InfluxLine::parse_with_precision("cpu v=1 1704067200", Precision::Seconds)
+            .expect("Must parse here");
+
+        assert!(
+            TimestampPrecision::new(Precision::Seconds)
+                .check(&line)
+                .is_empty()
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::past("cpu v=1 1704067200000000000", 0)]
+    #[case::within_skew("cpu v=1 1704067260000000000", 0)]
+    #[case::beyond_skew("cpu v=1 1704070800000000000", 1)]
+    fn future_timestamps(#[case] input: &str, #[case] expected: usize) {
+        let mut rule = FutureTimestamps::new()
+            .with_skew(Duration::from_secs(60))
+            .with_now(Timestamp::from(1704067200000000000_i64));
+
+        assert_eq!(expected, check(&mut rule, input).len());
+    }
+
+    #[test]
+    fn future_timestamps_read_the_clock() {
+        let clock = ManualClock::new(Timestamp::from(1704067200000000000_i64));
+        let mut rule = FutureTimestamps::new().with_clock(clock.clone());
+
+        assert_eq!(1, check(&mut rule, "cpu v=1 1704070800000000000").len());
+        clock.advance(Duration::from_secs(3600));
+        assert!(check(&mut rule, "cpu v=1 1704070800000000000").is_empty());
+    }
+
+    #[test]
+    fn future_timestamps_use_system_clock() {
+        assert!(check(&mut FutureTimestamps::new(), "cpu v=1 1704067200000000000").is_empty());
+        assert_eq!(
+            1,
+            check(&mut FutureTimestamps::new(), "cpu v=1 4102444800000000000").len()
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::integer(r#"cpu v="42""#, 1)]
+    #[case::float(r#"cpu v="-0.5e3""#, 1)]
+    #[case::text(r#"cpu v="idle""#, 0)]
+    #[case::infinity(r#"cpu v="inf""#, 0)]
+    #[case::number(r#"cpu v=42"#, 0)]
+    fn numeric_strings(#[case] input: &str, #[case] expected: usize) {
+        assert_eq!(expected, check(&mut NumericStrings::new(), input).len());
+    }
+}
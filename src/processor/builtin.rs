@@ -0,0 +1,745 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+
+use super::{Parts, Processor, put, take};
+use crate::{
+    InfluxLine, InfluxLineError, InfluxValue, InfluxValueType, KeyName, MeasurementName, Precision,
+    Timestamp,
+};
+
+/// Renames the measurement, tags and fields,
+/// overriding keys that already have the new name.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let rename = Rename::new()
+///     .with_measurement(MeasurementName::new("cpu").unwrap(), MeasurementName::new("processor").unwrap())
+///     .with_tag(KeyName::new("hostname").unwrap(), KeyName::new("host").unwrap());
+///
+/// let line: InfluxLine = "cpu,hostname=a usage=0.5".parse().unwrap();
+///
+/// assert_eq!("processor,host=a usage=0.5", rename.process(line).unwrap().to_string());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Rename {
+    measurements: BTreeMap<MeasurementName, MeasurementName>,
+    tags: BTreeMap<KeyName, KeyName>,
+    fields: BTreeMap<KeyName, KeyName>,
+}
+
+impl Rename {
+    /// Creates a processor that renames nothing yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renames a measurement.
+    pub fn with_measurement(mut self, from: MeasurementName, to: MeasurementName) -> Self {
+        self.measurements.insert(from, to);
+        self
+    }
+
+    /// Renames a tag key.
+    pub fn with_tag(mut self, from: KeyName, to: KeyName) -> Self {
+        self.tags.insert(from, to);
+        self
+    }
+
+    /// Renames a field key.
+    pub fn with_field(mut self, from: KeyName, to: KeyName) -> Self {
+        self.fields.insert(from, to);
+        self
+    }
+}
+
+impl Processor for Rename {
+    fn name(&self) -> &'static str {
+        "rename"
+    }
+
+    fn process(&self, line: InfluxLine) -> Result<InfluxLine, InfluxLineError> {
+        let mut parts = Parts::from(line);
+        if let Some(to) = self.measurements.get(&parts.measurement) {
+            parts.measurement = to.clone();
+        }
+        rename_keys(&mut parts.tags, &self.tags);
+        rename_keys(&mut parts.fields, &self.fields);
+        parts.into_line()
+    }
+}
+
+/// Renames keys in place, so that their order is kept,
+/// dropping keys that are overridden by a renamed one.
+fn rename_keys<V>(pairs: &mut Vec<(KeyName, V)>, renames: &BTreeMap<KeyName, KeyName>) {
+    let targets: BTreeSet<KeyName> = pairs
+        .iter()
+        .filter_map(|(key, _)| renames.get(key).cloned())
+        .collect();
+    let mut renamed = Vec::with_capacity(pairs.len());
+    for (key, value) in pairs.drain(..) {
+        match renames.get(&key) {
+            Some(to) => put(&mut renamed, to.clone(), value),
+            None if targets.contains(&key) => {}
+            None => renamed.push((key, value)),
+        }
+    }
+    *pairs = renamed;
+}
+
+/// Adds tags to every Line, overriding existing values unless told otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct SetTags {
+    tags: Vec<(KeyName, KeyName)>,
+    keep_existing: bool,
+}
+
+impl SetTags {
+    /// Creates a processor that sets no tags yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a tag.
+    pub fn with_tag(mut self, key: KeyName, value: KeyName) -> Self {
+        put(&mut self.tags, key, value);
+        self
+    }
+
+    /// Whether tags the Line already has keep their values.
+    pub fn with_keep_existing(mut self, keep_existing: bool) -> Self {
+        self.keep_existing = keep_existing;
+        self
+    }
+}
+
+impl Processor for SetTags {
+    fn name(&self) -> &'static str {
+        "set-tags"
+    }
+
+    fn process(&self, line: InfluxLine) -> Result<InfluxLine, InfluxLineError> {
+        let mut parts = Parts::from(line);
+        for (key, value) in &self.tags {
+            if !(self.keep_existing && parts.tags.iter().any(|(existing, _)| existing == key)) {
+                put(&mut parts.tags, key.clone(), value.clone());
+            }
+        }
+        parts.into_line()
+    }
+}
+
+/// Drops tags and fields whose keys match glob patterns,
+/// where `*` matches any number of characters and `?` a single one.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let drop = DropKeys::new()
+///     .try_with_tag_glob("tmp_*")
+///     .and_then(|drop| drop.try_with_field_glob("debug_?"))
+///     .unwrap();
+///
+/// let line: InfluxLine = "cpu,host=a,tmp_id=1 usage=0.5,debug_1=1i".parse().unwrap();
+///
+/// assert_eq!("cpu,host=a usage=0.5", drop.process(line).unwrap().to_string());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DropKeys {
+    tags: Vec<Regex>,
+    fields: Vec<Regex>,
+}
+
+impl DropKeys {
+    /// Creates a processor that drops nothing yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops tags matching a glob pattern.
+    pub fn try_with_tag_glob(mut self, glob: &str) -> Result<Self, InfluxLineError> {
+        self.tags.push(compile_glob(glob)?);
+        Ok(self)
+    }
+
+    /// Drops fields matching a glob pattern.
+    /// Lines without any fields left fail to process.
+    pub fn try_with_field_glob(mut self, glob: &str) -> Result<Self, InfluxLineError> {
+        self.fields.push(compile_glob(glob)?);
+        Ok(self)
+    }
+}
+
+impl Processor for DropKeys {
+    fn name(&self) -> &'static str {
+        "drop-keys"
+    }
+
+    fn process(&self, line: InfluxLine) -> Result<InfluxLine, InfluxLineError> {
+        let mut parts = Parts::from(line);
+        let matches =
+            |globs: &[Regex], key: &KeyName| globs.iter().any(|glob| glob.is_match(key.as_ref()));
+        parts.tags.retain(|(key, _)| !matches(&self.tags, key));
+        parts.fields.retain(|(key, _)| !matches(&self.fields, key));
+        parts.into_line()
+    }
+}
+
+/// Translates a glob into an anchored regular expression.
+fn compile_glob(glob: &str) -> Result<Regex, InfluxLineError> {
+    let mut pattern = String::from("^");
+    for character in glob.chars() {
+        match character {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            _ => pattern.push_str(&regex::escape(character.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).map_err(|_| InfluxLineError::BadPattern)
+}
+
+/// Replaces matches of a regular expression in the value of a tag,
+/// with `$1` or `${name}` referring to capture groups.
+/// The tag is dropped if nothing is left of its value.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let replace = RegexReplace::try_new(KeyName::new("host").unwrap(), r"\.example\.com$", "")
+///     .unwrap();
+///
+/// let line: InfluxLine = "cpu,host=web.example.com usage=0.5".parse().unwrap();
+///
+/// assert_eq!("cpu,host=web usage=0.5", replace.process(line).unwrap().to_string());
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegexReplace {
+    tag: KeyName,
+    pattern: Regex,
+    replacement: String,
+}
+
+impl RegexReplace {
+    /// Creates a processor replacing every match in the value of a tag.
+    pub fn try_new(
+        tag: KeyName,
+        pattern: &str,
+        replacement: &str,
+    ) -> Result<Self, InfluxLineError> {
+        Ok(Self {
+            tag,
+            pattern: Regex::new(pattern).map_err(|_| InfluxLineError::BadPattern)?,
+            replacement: replacement.to_owned(),
+        })
+    }
+}
+
+impl Processor for RegexReplace {
+    fn name(&self) -> &'static str {
+        "regex-replace"
+    }
+
+    fn process(&self, line: InfluxLine) -> Result<InfluxLine, InfluxLineError> {
+        let mut parts = Parts::from(line);
+        let Some(position) = parts.tags.iter().position(|(key, _)| *key == self.tag) else {
+            return parts.into_line();
+        };
+
+        let value = &parts.tags[position].1;
+        let replaced = self
+            .pattern
+            .replace_all(value.as_ref(), self.replacement.as_str());
+        if replaced.is_empty() {
+            parts.tags.remove(position);
+        } else if replaced != value.as_ref() {
+            parts.tags[position].1 = KeyName::new(replaced.into_owned())?;
+        }
+        parts.into_line()
+    }
+}
+
+/// Converts fields to other types.
+///
+/// Numbers convert to each other as long as they fit into the target type,
+/// with fractions truncated, and to booleans as being non-zero.
+/// Strings are parsed, e.g. `"42"` into an integer, and anything can become a string.
+/// Lines with fields that cannot be converted fail to process.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let convert = Convert::new()
+///     .with_field(KeyName::new("cores").unwrap(), InfluxValueType::Integer)
+///     .with_field(KeyName::new("model").unwrap(), InfluxValueType::String);
+///
+/// let line: InfluxLine = r#"cpu cores=4.0,model=7i"#.parse().unwrap();
+///
+/// assert_eq!(r#"cpu cores=4i,model="7""#, convert.process(line).unwrap().to_string());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Convert {
+    fields: BTreeMap<KeyName, InfluxValueType>,
+}
+
+impl Convert {
+    /// Creates a processor that converts nothing yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Converts a field to a type.
+    pub fn with_field(mut self, field: KeyName, target: InfluxValueType) -> Self {
+        self.fields.insert(field, target);
+        self
+    }
+}
+
+impl Processor for Convert {
+    fn name(&self) -> &'static str {
+        "convert"
+    }
+
+    fn process(&self, line: InfluxLine) -> Result<InfluxLine, InfluxLineError> {
+        let mut parts = Parts::from(line);
+        for (key, value) in parts.fields.iter_mut() {
+            if let Some(target) = self.fields.get(key) {
+                *value = convert(key, value, *target)?;
+            }
+        }
+        parts.into_line()
+    }
+}
+
+/// Converts a value as documented in [`Convert`].
+fn convert(
+    key: &KeyName,
+    value: &InfluxValue,
+    target: InfluxValueType,
+) -> Result<InfluxValue, InfluxLineError> {
+    let converted = match (value, target) {
+        (_, _) if value.value_type() == target => Some(value.clone()),
+        (_, InfluxValueType::String) => Some(InfluxValue::from(to_string(value).as_str())),
+        (InfluxValue::String(string), _) => parse(string.as_ref(), target),
+        (InfluxValue::Boolean(boolean), _) => {
+            let number = u8::from(**boolean);
+            match target {
+                InfluxValueType::Float => Some(InfluxValue::from(f64::from(number))),
+                InfluxValueType::Integer => Some(InfluxValue::from(number as i64)),
+                _ => Some(InfluxValue::from(number as u64)),
+            }
+        }
+        (InfluxValue::Float(float), _) => match target {
+            InfluxValueType::Boolean => Some(InfluxValue::from(*float != 0.0)),
+            // Casts saturate, so the range is checked beforehand.
+            InfluxValueType::Integer => {
+                (float.is_finite() && *float >= i64::MIN as f64 && *float < i64::MAX as f64)
+                    .then(|| InfluxValue::from(*float as i64))
+            }
+            _ => (float.is_finite() && *float > -1.0 && *float < u64::MAX as f64)
+                .then(|| InfluxValue::from(*float as u64)),
+        },
+        (InfluxValue::Integer(integer), _) => {
+            let integer = i64::from(*integer);
+            match target {
+                InfluxValueType::Boolean => Some(InfluxValue::from(integer != 0)),
+                InfluxValueType::Float => Some(InfluxValue::from(integer as f64)),
+                _ => u64::try_from(integer).ok().map(InfluxValue::from),
+            }
+        }
+        (InfluxValue::UInteger(uinteger), _) => {
+            let uinteger = u64::from(*uinteger);
+            match target {
+                InfluxValueType::Boolean => Some(InfluxValue::from(uinteger != 0)),
+                InfluxValueType::Float => Some(InfluxValue::from(uinteger as f64)),
+                _ => i64::try_from(uinteger).ok().map(InfluxValue::from),
+            }
+        }
+    };
+    converted.ok_or_else(|| InfluxLineError::FieldConversion {
+        field: key.to_string(),
+        target,
+    })
+}
+
+/// Formats a value without Line Protocol suffixes and quotes.
+fn to_string(value: &InfluxValue) -> String {
+    match value {
+        InfluxValue::Float(float) => float.to_string(),
+        InfluxValue::Integer(integer) => i64::from(*integer).to_string(),
+        InfluxValue::UInteger(uinteger) => u64::from(*uinteger).to_string(),
+        InfluxValue::Boolean(boolean) => boolean.to_string(),
+        InfluxValue::String(string) => string.as_ref().to_owned(),
+    }
+}
+
+/// Parses a string into a value of a non-string type.
+fn parse(string: &str, target: InfluxValueType) -> Option<InfluxValue> {
+    let string = string.trim();
+    match target {
+        InfluxValueType::Float => string.parse::<f64>().ok().map(InfluxValue::from),
+        InfluxValueType::Integer => string.parse::<i64>().ok().map(InfluxValue::from),
+        InfluxValueType::UInteger => string.parse::<u64>().ok().map(InfluxValue::from),
+        InfluxValueType::Boolean => string.parse::<crate::Boolean>().ok().map(InfluxValue::from),
+        InfluxValueType::String => Some(InfluxValue::from(string)),
+    }
+}
+
+/// Moves a tag into a field of the same key, converting its value from a string.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let to_field = TagToField::new(KeyName::new("version").unwrap())
+///     .with_value_type(InfluxValueType::Integer);
+///
+/// let line: InfluxLine = "app,version=3 up=true".parse().unwrap();
+///
+/// assert_eq!("app up=true,version=3i", to_field.process(line).unwrap().to_string());
+/// ```
+#[derive(Debug, Clone)]
+pub struct TagToField {
+    tag: KeyName,
+    value_type: InfluxValueType,
+}
+
+impl TagToField {
+    /// Creates a processor that moves a tag into a string field.
+    pub fn new(tag: KeyName) -> Self {
+        Self {
+            tag,
+            value_type: InfluxValueType::String,
+        }
+    }
+
+    /// Converts the value to another type, as [`Convert`] does for strings.
+    pub fn with_value_type(mut self, value_type: InfluxValueType) -> Self {
+        self.value_type = value_type;
+        self
+    }
+}
+
+impl Processor for TagToField {
+    fn name(&self) -> &'static str {
+        "tag-to-field"
+    }
+
+    fn process(&self, line: InfluxLine) -> Result<InfluxLine, InfluxLineError> {
+        let mut parts = Parts::from(line);
+        if let Some(value) = take(&mut parts.tags, &self.tag) {
+            let value = convert(
+                &self.tag,
+                &InfluxValue::from(value.as_ref()),
+                self.value_type,
+            )?;
+            put(&mut parts.fields, self.tag.clone(), value);
+        }
+        parts.into_line()
+    }
+}
+
+/// Moves a field into a tag of the same key, formatting its value as a string.
+/// Lines whose only field it is fail to process.
+#[derive(Debug, Clone)]
+pub struct FieldToTag {
+    field: KeyName,
+}
+
+impl FieldToTag {
+    /// Creates a processor that moves a field into a tag.
+    pub fn new(field: KeyName) -> Self {
+        Self { field }
+    }
+}
+
+impl Processor for FieldToTag {
+    fn name(&self) -> &'static str {
+        "field-to-tag"
+    }
+
+    fn process(&self, line: InfluxLine) -> Result<InfluxLine, InfluxLineError> {
+        let mut parts = Parts::from(line);
+        if let Some(value) = take(&mut parts.fields, &self.field) {
+            put(
+                &mut parts.tags,
+                self.field.clone(),
+                KeyName::new(to_string(&value))?,
+            );
+        }
+        parts.into_line()
+    }
+}
+
+/// Sets the timestamp from a field, which is removed unless told otherwise.
+///
+/// Numbers are read in the given precision, and strings as RFC 3339.
+/// Lines without the field are left unchanged.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let from_field = TimestampFromField::new(KeyName::new("observed_at").unwrap())
+///     .with_precision(Precision::Seconds);
+///
+/// let line: InfluxLine = "door open=true,observed_at=1704067200i".parse().unwrap();
+///
+/// assert_eq!(
+///     "door open=true 1704067200000000000",
+///     from_field.process(line).unwrap().to_string()
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimestampFromField {
+    field: KeyName,
+    precision: Precision,
+    keep_field: bool,
+}
+
+impl TimestampFromField {
+    /// Creates a processor reading nanoseconds from a field.
+    pub fn new(field: KeyName) -> Self {
+        Self {
+            field,
+            precision: Precision::Nanoseconds,
+            keep_field: false,
+        }
+    }
+
+    /// Reads numbers in another precision.
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Whether the field stays in the Line.
+    pub fn with_keep_field(mut self, keep_field: bool) -> Self {
+        self.keep_field = keep_field;
+        self
+    }
+
+    fn timestamp(&self, value: &InfluxValue) -> Option<Timestamp> {
+        match value {
+            InfluxValue::Integer(integer) => {
+                Timestamp::from_precision(i64::from(*integer), self.precision).ok()
+            }
+            InfluxValue::UInteger(uinteger) => {
+                let uinteger = i64::try_from(u64::from(*uinteger)).ok()?;
+                Timestamp::from_precision(uinteger, self.precision).ok()
+            }
+            InfluxValue::Float(float) => {
+                let nanoseconds = (float * self.precision.nanoseconds() as f64).round();
+                (nanoseconds >= i64::MIN as f64 && nanoseconds < i64::MAX as f64)
+                    .then(|| Timestamp::from(nanoseconds as i64))
+            }
+            InfluxValue::String(string) => DateTime::parse_from_rfc3339(string.as_ref())
+                .ok()
+                .and_then(|datetime| Timestamp::try_from(datetime.with_timezone(&Utc)).ok()),
+            InfluxValue::Boolean(_) => None,
+        }
+    }
+}
+
+impl Processor for TimestampFromField {
+    fn name(&self) -> &'static str {
+        "timestamp-from-field"
+    }
+
+    fn process(&self, line: InfluxLine) -> Result<InfluxLine, InfluxLineError> {
+        let Some(value) = line.field(&self.field) else {
+            return Ok(line);
+        };
+        let timestamp = self
+            .timestamp(value)
+            .ok_or(InfluxLineError::TimestampNotParsed)?;
+
+        let mut parts = Parts::from(line);
+        parts.timestamp = Some(timestamp);
+        if !self.keep_field {
+            take(&mut parts.fields, &self.field);
+        }
+        parts.into_line()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Convert, FieldToTag, InfluxLine, InfluxLineError, InfluxValueType, KeyName,
+        MeasurementName, Precision, Processor, RegexReplace, Rename, SetTags, TimestampFromField,
+    };
+
+    fn key(name: &str) -> KeyName {
+        KeyName::new(name).unwrap()
+    }
+
+    fn line(source: &str) -> InfluxLine {
+        source.parse().expect("Must parse here")
+    }
+
+    #[rstest::rstest]
+    #[case::in_place("cpu,a=1,b=2 x=1i", "cpu,c=1,b=2 x=1i")]
+    #[case::onto_existing("cpu,a=1,c=2 x=1i", "cpu,c=1 x=1i")]
+    #[case::missing("cpu,b=2 x=1i", "cpu,b=2 x=1i")]
+    fn renames_tags(#[case] input: &str, #[case] expected: &str) {
+        let rename = Rename::new().with_tag(key("a"), key("c"));
+
+        assert_eq!(line(expected), rename.process(line(input)).unwrap());
+    }
+
+    #[test]
+    fn renames_measurements() {
+        let rename = Rename::new()
+            .with_measurement(
+                MeasurementName::new("mem").unwrap(),
+                MeasurementName::new("memory").unwrap(),
+            )
+            .with_field(key("x"), key("y"));
+
+        assert_eq!(
+            line("memory y=1i"),
+            rename.process(line("mem x=1i")).unwrap()
+        );
+        assert_eq!(line("cpu y=1i"), rename.process(line("cpu x=1i")).unwrap());
+    }
+
+    #[rstest::rstest]
+    #[case::overrides(false, "cpu,env=dev,host=a x=1i", "cpu,env=prod,host=a x=1i")]
+    #[case::keeps_existing(true, "cpu,env=dev,host=a x=1i", "cpu,env=dev,host=a x=1i")]
+    #[case::adds(true, "cpu,host=a x=1i", "cpu,host=a,env=prod x=1i")]
+    fn sets_tags(#[case] keep_existing: bool, #[case] input: &str, #[case] expected: &str) {
+        let set = SetTags::new()
+            .with_tag(key("env"), key("prod"))
+            .with_keep_existing(keep_existing);
+
+        assert_eq!(line(expected), set.process(line(input)).unwrap());
+    }
+
+    #[rstest::rstest]
+    #[case::whole_match(r"^web-(\d+)$", "node-$1", "node-01")]
+    #[case::every_match("-", ".", "web.01")]
+    #[case::no_match("^db", "", "web-01")]
+    #[case::nothing_left(".*", "", "")]
+    fn replaces(#[case] pattern: &str, #[case] replacement: &str, #[case] expected: &str) {
+        let replace = RegexReplace::try_new(key("host"), pattern, replacement).unwrap();
+
+        let processed = replace.process(line("cpu,host=web-01 x=1i")).unwrap();
+
+        assert_eq!(
+            expected,
+            processed.tag("host").map_or("", |value| value.as_ref())
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::float_to_integer("x=-2.9", InfluxValueType::Integer, "x=-2i")]
+    #[case::float_to_unsigned("x=2.9", InfluxValueType::UInteger, "x=2u")]
+    #[case::float_to_string("x=2.5", InfluxValueType::String, r#"x="2.5""#)]
+    #[case::integer_to_float("x=2i", InfluxValueType::Float, "x=2.0")]
+    #[case::integer_to_boolean("x=0i", InfluxValueType::Boolean, "x=false")]
+    #[case::unsigned_to_integer("x=2u", InfluxValueType::Integer, "x=2i")]
+    #[case::boolean_to_integer("x=true", InfluxValueType::Integer, "x=1i")]
+    #[case::boolean_to_string("x=true", InfluxValueType::String, r#"x="true""#)]
+    #[case::string_to_float(r#"x=" 1.5""#, InfluxValueType::Float, "x=1.5")]
+    #[case::string_to_boolean(r#"x="T""#, InfluxValueType::Boolean, "x=true")]
+    #[case::same_type("x=2i", InfluxValueType::Integer, "x=2i")]
+    fn converts(#[case] fields: &str, #[case] target: InfluxValueType, #[case] expected: &str) {
+        let convert = Convert::new().with_field(key("x"), target);
+
+        let processed = convert.process(line(&format!("m {}", fields))).unwrap();
+
+        assert_eq!(line(&format!("m {}", expected)), processed);
+    }
+
+    #[rstest::rstest]
+    #[case::negative_to_unsigned("x=-1i", InfluxValueType::UInteger)]
+    #[case::large_to_integer("x=18446744073709551615u", InfluxValueType::Integer)]
+    #[case::infinity_to_integer("x=1e400", InfluxValueType::Integer)]
+    #[case::text_to_integer(r#"x="many""#, InfluxValueType::Integer)]
+    fn conversion_errors(#[case] fields: &str, #[case] target: InfluxValueType) {
+        let convert = Convert::new().with_field(key("x"), target);
+
+        match convert.process(line(&format!("m {}", fields))) {
+            Err(InfluxLineError::FieldConversion {
+                field,
+                target: actual,
+            }) => {
+                assert_eq!("x", field);
+                assert_eq!(target, actual);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn moves_fields_to_tags() {
+        let to_tag = FieldToTag::new(key("status"));
+
+        assert_eq!(
+            line("app,status=200 up=true"),
+            to_tag.process(line("app up=true,status=200i")).unwrap()
+        );
+        assert!(matches!(
+            to_tag.process(line("app status=200i")),
+            Err(InfluxLineError::NoFields)
+        ));
+    }
+
+    #[rstest::rstest]
+    #[case::seconds("t=1704067200i", Precision::Seconds, 1704067200000000000)]
+    #[case::unsigned_milliseconds("t=1704067200000u", Precision::Milliseconds, 1704067200000000000)]
+    #[case::fractional_seconds("t=1704067200.5", Precision::Seconds, 1704067200500000000)]
+    #[case::rfc3339(
+        r#"t="2024-01-01T01:00:00+01:00""#,
+        Precision::Seconds,
+        1704067200000000000
+    )]
+    fn timestamps_from_fields(
+        #[case] fields: &str,
+        #[case] precision: Precision,
+        #[case] expected: i64,
+    ) {
+        let from_field = TimestampFromField::new(key("t")).with_precision(precision);
+
+        let processed = from_field
+            .process(line(&format!("m x=1i,{} 5", fields)))
+            .unwrap();
+
+        assert_eq!(Some(expected.into()), processed.timestamp());
+        assert_eq!(None, processed.field("t"));
+    }
+
+    #[test]
+    fn timestamp_from_field_errors() {
+        let from_field = TimestampFromField::new(key("t"));
+
+        assert!(matches!(
+            from_field.process(line("m x=1i,t=true")),
+            Err(InfluxLineError::TimestampNotParsed)
+        ));
+        assert!(matches!(
+            from_field.process(line("m t=1i")),
+            Err(InfluxLineError::NoFields)
+        ));
+        assert_eq!(
+            line("m t=1i 1"),
+            from_field
+                .with_keep_field(true)
+                .process(line("m t=1i"))
+                .unwrap()
+        );
+    }
+}
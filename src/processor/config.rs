@@ -0,0 +1,213 @@
+use toml::{Table, Value};
+
+use super::Processor;
+use crate::{
+    Convert, DropKeys, FieldToTag, InfluxLineError, InfluxValueType, KeyName, MeasurementName,
+    Precision, RegexReplace, Rename, SetTags, TagToField, TimestampFromField,
+};
+
+/// Parses the `[[processors]]` tables of a pipeline file in order.
+pub(super) fn parse(source: &str) -> Result<Vec<Box<dyn Processor>>, InfluxLineError> {
+    let mut document: Table = source
+        .parse()
+        .map_err(|error: toml::de::Error| bad(error.message()))?;
+    let Some(Value::Array(tables)) = document.remove("processors") else {
+        return Err(bad("expected an array of `[[processors]]` tables"));
+    };
+    if let Some(key) = document.keys().next() {
+        return Err(bad(&format!("unknown setting `{}`", key)));
+    }
+
+    tables
+        .into_iter()
+        .enumerate()
+        .map(|(index, table)| {
+            let Value::Table(table) = table else {
+                return Err(bad(&format!("processor {} is not a table", index + 1)));
+            };
+            Settings { index, table }.processor()
+        })
+        .collect()
+}
+
+fn bad(message: &str) -> InfluxLineError {
+    InfluxLineError::BadPipeline(message.to_owned())
+}
+
+/// Settings of a single processor, which are removed as they are read,
+/// so that misspelled ones are reported rather than ignored.
+struct Settings {
+    index: usize,
+    table: Table,
+}
+
+impl Settings {
+    fn processor(mut self) -> Result<Box<dyn Processor>, InfluxLineError> {
+        let kind = self.string("type")?;
+        let processor: Box<dyn Processor> = match kind.as_str() {
+            "rename" => {
+                let mut rename = Rename::new();
+                for (from, to) in self.optional_map("measurement")? {
+                    rename =
+                        rename.with_measurement(self.measurement(from)?, self.measurement(to)?);
+                }
+                for (from, to) in self.optional_map("tags")? {
+                    rename = rename.with_tag(self.key(from)?, self.key(to)?);
+                }
+                for (from, to) in self.optional_map("fields")? {
+                    rename = rename.with_field(self.key(from)?, self.key(to)?);
+                }
+                Box::new(rename)
+            }
+            "set-tags" => {
+                let mut set = SetTags::new()
+                    .with_keep_existing(self.optional_boolean("keep_existing")?.unwrap_or(false));
+                for (key, value) in self.map("tags")? {
+                    set = set.with_tag(self.key(key)?, self.key(value)?);
+                }
+                Box::new(set)
+            }
+            "drop-keys" => {
+                let mut drop = DropKeys::new();
+                for glob in self.optional_strings("tags")? {
+                    drop = drop
+                        .try_with_tag_glob(&glob)
+                        .map_err(|_| self.invalid("tags"))?;
+                }
+                for glob in self.optional_strings("fields")? {
+                    drop = drop
+                        .try_with_field_glob(&glob)
+                        .map_err(|_| self.invalid("fields"))?;
+                }
+                Box::new(drop)
+            }
+            "regex-replace" => {
+                let tag = self.string("tag")?;
+                let pattern = self.string("pattern")?;
+                let replacement = self.string("replacement")?;
+                Box::new(
+                    RegexReplace::try_new(self.key(tag)?, &pattern, &replacement)
+                        .map_err(|_| self.invalid("pattern"))?,
+                )
+            }
+            "convert" => {
+                let mut convert = Convert::new();
+                for (field, target) in self.map("fields")? {
+                    convert = convert.with_field(self.key(field)?, self.value_type(&target)?);
+                }
+                Box::new(convert)
+            }
+            "tag-to-field" => {
+                let tag = self.string("tag")?;
+                let mut to_field = TagToField::new(self.key(tag)?);
+                if let Some(value_type) = self.optional_string("value_type")? {
+                    to_field = to_field.with_value_type(self.value_type(&value_type)?);
+                }
+                Box::new(to_field)
+            }
+            "field-to-tag" => {
+                let field = self.string("field")?;
+                Box::new(FieldToTag::new(self.key(field)?))
+            }
+            "timestamp-from-field" => {
+                let field = self.string("field")?;
+                let mut from_field = TimestampFromField::new(self.key(field)?)
+                    .with_keep_field(self.optional_boolean("keep_field")?.unwrap_or(false));
+                if let Some(precision) = self.optional_string("precision")? {
+                    let precision: Precision =
+                        precision.parse().map_err(|_| self.invalid("precision"))?;
+                    from_field = from_field.with_precision(precision);
+                }
+                Box::new(from_field)
+            }
+            _ => return Err(self.error(&format!("unknown type `{}`", kind))),
+        };
+
+        match self.table.keys().next() {
+            Some(key) => Err(self.error(&format!("unknown setting `{}`", key))),
+            None => Ok(processor),
+        }
+    }
+
+    fn error(&self, message: &str) -> InfluxLineError {
+        bad(&format!("processor {}: {}", self.index + 1, message))
+    }
+
+    fn invalid(&self, setting: &str) -> InfluxLineError {
+        self.error(&format!("invalid `{}`", setting))
+    }
+
+    fn optional_string(&mut self, setting: &str) -> Result<Option<String>, InfluxLineError> {
+        match self.table.remove(setting) {
+            None => Ok(None),
+            Some(Value::String(string)) => Ok(Some(string)),
+            Some(_) => Err(self.error(&format!("`{}` must be a string", setting))),
+        }
+    }
+
+    fn string(&mut self, setting: &str) -> Result<String, InfluxLineError> {
+        self.optional_string(setting)?
+            .ok_or_else(|| self.error(&format!("missing `{}`", setting)))
+    }
+
+    fn optional_boolean(&mut self, setting: &str) -> Result<Option<bool>, InfluxLineError> {
+        match self.table.remove(setting) {
+            None => Ok(None),
+            Some(Value::Boolean(boolean)) => Ok(Some(boolean)),
+            Some(_) => Err(self.error(&format!("`{}` must be a boolean", setting))),
+        }
+    }
+
+    fn optional_strings(&mut self, setting: &str) -> Result<Vec<String>, InfluxLineError> {
+        let value = self.table.remove(setting);
+        let error = || self.error(&format!("`{}` must be an array of strings", setting));
+        match value {
+            None => Ok(Vec::new()),
+            Some(Value::Array(array)) => array
+                .into_iter()
+                .map(|value| match value {
+                    Value::String(string) => Ok(string),
+                    _ => Err(error()),
+                })
+                .collect(),
+            Some(_) => Err(error()),
+        }
+    }
+
+    fn optional_map(&mut self, setting: &str) -> Result<Vec<(String, String)>, InfluxLineError> {
+        let value = self.table.remove(setting);
+        let error = || self.error(&format!("`{}` must be a table of strings", setting));
+        match value {
+            None => Ok(Vec::new()),
+            Some(Value::Table(table)) => table
+                .into_iter()
+                .map(|(key, value)| match value {
+                    Value::String(string) => Ok((key, string)),
+                    _ => Err(error()),
+                })
+                .collect(),
+            Some(_) => Err(error()),
+        }
+    }
+
+    fn map(&mut self, setting: &str) -> Result<Vec<(String, String)>, InfluxLineError> {
+        if !self.table.contains_key(setting) {
+            return Err(self.error(&format!("missing `{}`", setting)));
+        }
+        self.optional_map(setting)
+    }
+
+    fn key(&self, name: String) -> Result<KeyName, InfluxLineError> {
+        KeyName::new(&name).map_err(|_| self.error(&format!("invalid key `{}`", name)))
+    }
+
+    fn measurement(&self, name: String) -> Result<MeasurementName, InfluxLineError> {
+        MeasurementName::new(&name)
+            .map_err(|_| self.error(&format!("invalid measurement `{}`", name)))
+    }
+
+    fn value_type(&self, name: &str) -> Result<InfluxValueType, InfluxLineError> {
+        name.parse()
+            .map_err(|_| self.error(&format!("unknown value type `{}`", name)))
+    }
+}
@@ -0,0 +1,306 @@
+mod builtin;
+mod config;
+
+use std::path::Path;
+
+use crate::{InfluxLine, InfluxLineError, InfluxValue, KeyName, MeasurementName, Timestamp};
+
+pub use self::builtin::{
+    Convert, DropKeys, FieldToTag, RegexReplace, Rename, SetTags, TagToField, TimestampFromField,
+};
+
+/// A transformation of Lines, e.g. renaming a tag.
+///
+/// Processors are chained in a [`Pipeline`], each one receiving the output of the previous one.
+/// A processor leaves Lines that it does not apply to unchanged,
+/// and fails instead of producing a Line without fields.
+pub trait Processor: Send + Sync {
+    /// Returns a short stable name, e.g. `rename`, which is also its type in a pipeline file.
+    fn name(&self) -> &'static str;
+
+    /// Transforms a Line.
+    fn process(&self, line: InfluxLine) -> Result<InfluxLine, InfluxLineError>;
+}
+
+/// Runs Lines through a chain of [`Processor`]s in order.
+///
+/// A pipeline can be built in code, or loaded from a TOML file
+/// with a `[[processors]]` table per processor:
+///
+/// ```toml
+/// [[processors]]
+/// type = "rename"
+/// measurement = { cpu = "processor" }
+/// tags = { hostname = "host" }
+/// fields = { usage_idle = "idle" }
+///
+/// [[processors]]
+/// type = "set-tags"
+/// tags = { env = "prod" }
+/// keep_existing = true          # Optional, existing tags are overridden by default.
+///
+/// [[processors]]
+/// type = "drop-keys"
+/// tags = ["tmp_*"]
+/// fields = ["debug_?"]
+///
+/// [[processors]]
+/// type = "regex-replace"
+/// tag = "host"
+/// pattern = '^(\w+)\.example\.com$'
+/// replacement = "$1"
+///
+/// [[processors]]
+/// type = "convert"
+/// fields = { cores = "integer", healthy = "boolean" }
+///
+/// [[processors]]
+/// type = "tag-to-field"
+/// tag = "version"
+/// value_type = "integer"        # Optional, strings by default.
+///
+/// [[processors]]
+/// type = "field-to-tag"
+/// field = "status"
+///
+/// [[processors]]
+/// type = "timestamp-from-field"
+/// field = "observed_at"
+/// precision = "s"               # Optional, nanoseconds by default.
+/// keep_field = false            # Optional.
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let pipeline = Pipeline::from_toml(r#"
+///     [[processors]]
+///     type = "rename"
+///     tags = { hostname = "host" }
+///
+///     [[processors]]
+///     type = "set-tags"
+///     tags = { env = "prod" }
+/// "#).unwrap();
+///
+/// let line: InfluxLine = "cpu,hostname=a usage=0.5".parse().unwrap();
+/// let processed = pipeline.process(line).unwrap();
+///
+/// assert_eq!("cpu,host=a,env=prod usage=0.5", processed.to_string());
+/// ```
+#[derive(Default)]
+pub struct Pipeline {
+    processors: Vec<Box<dyn Processor>>,
+}
+
+impl Pipeline {
+    /// Creates a pipeline that passes Lines through unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a pipeline from the TOML format described in [`Self`].
+    pub fn from_toml(source: &str) -> Result<Self, InfluxLineError> {
+        Ok(Self {
+            processors: config::parse(source)?,
+        })
+    }
+
+    /// Reads and parses a TOML pipeline file.
+    pub fn load<P>(path: P) -> Result<Self, InfluxLineError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Adds a processor, which runs after the ones added before.
+    pub fn with_processor<P>(mut self, processor: P) -> Self
+    where
+        P: Processor + 'static,
+    {
+        self.processors.push(Box::new(processor));
+        self
+    }
+
+    /// Returns the names of the processors in order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> {
+        self.processors.iter().map(|processor| processor.name())
+    }
+
+    /// Runs a Line through every processor, stopping at the first failure.
+    pub fn process(&self, line: InfluxLine) -> Result<InfluxLine, InfluxLineError> {
+        self.processors
+            .iter()
+            .try_fold(line, |line, processor| processor.process(line))
+    }
+}
+
+/// An unpacked Line that processors edit in place before packing it again.
+struct Parts {
+    measurement: MeasurementName,
+    tags: Vec<(KeyName, KeyName)>,
+    fields: Vec<(KeyName, InfluxValue)>,
+    timestamp: Option<Timestamp>,
+}
+
+impl Parts {
+    /// Validates the edited Line, which fails if no fields are left.
+    fn into_line(self) -> Result<InfluxLine, InfluxLineError> {
+        InfluxLine::full(self.measurement, self.tags, self.fields, self.timestamp)
+    }
+}
+
+impl From<InfluxLine> for Parts {
+    fn from(line: InfluxLine) -> Self {
+        Self {
+            measurement: line.measurement().clone(),
+            tags: line
+                .tags()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            fields: line
+                .fields()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            timestamp: line.timestamp(),
+        }
+    }
+}
+
+/// Puts a key-value pair in place of an existing key, or at the end.
+fn put<V>(pairs: &mut Vec<(KeyName, V)>, key: KeyName, value: V) {
+    match pairs.iter_mut().find(|(existing, _)| *existing == key) {
+        Some((_, existing)) => *existing = value,
+        None => pairs.push((key, value)),
+    }
+}
+
+/// Removes a key-value pair and returns the value, if there was one.
+fn take<V>(pairs: &mut Vec<(KeyName, V)>, key: &KeyName) -> Option<V> {
+    let position = pairs.iter().position(|(existing, _)| existing == key)?;
+    Some(pairs.remove(position).1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InfluxLine, InfluxLineError, InfluxValue, Pipeline};
+
+    const PIPELINE: &str = r#"
+        [[processors]]
+        type = "rename"
+        measurement = { cpu = "processor" }
+        tags = { hostname = "host" }
+        fields = { usage_idle = "idle" }
+
+        [[processors]]
+        type = "set-tags"
+        tags = { env = "prod", host = "ignored" }
+        keep_existing = true
+
+        [[processors]]
+        type = "drop-keys"
+        tags = ["tmp_*"]
+        fields = ["debug_?"]
+
+        [[processors]]
+        type = "regex-replace"
+        tag = "host"
+        pattern = '^(\w+)\.example\.com$'
+        replacement = "$1"
+
+        [[processors]]
+        type = "convert"
+        fields = { cores = "integer" }
+
+        [[processors]]
+        type = "tag-to-field"
+        tag = "version"
+        value_type = "integer"
+
+        [[processors]]
+        type = "field-to-tag"
+        field = "status"
+
+        [[processors]]
+        type = "timestamp-from-field"
+        field = "observed_at"
+        precision = "s"
+    "#;
+
+    #[test]
+    fn from_toml() {
+        let pipeline = Pipeline::from_toml(PIPELINE).expect("Must parse here");
+        let line: InfluxLine = "cpu,hostname=web.example.com,tmp_id=1,version=3 \
+            usage_idle=0.5,debug_1=1i,cores=4.0,status=\"ok\",observed_at=1704067200i"
+            .parse()
+            .expect("Must parse here");
+
+        let processed = pipeline.process(line).expect("Must process here");
+
+        assert_eq!(
+            "processor,host=web,env=prod,status=ok idle=0.5,cores=4i,version=3i \
+            1704067200000000000",
+            processed.to_string()
+        );
+        assert_eq!(
+            vec![
+                "rename",
+                "set-tags",
+                "drop-keys",
+                "regex-replace",
+                "convert",
+                "tag-to-field",
+                "field-to-tag",
+                "timestamp-from-field",
+            ],
+            pipeline.names().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn keeps_a_field() {
+        let pipeline = Pipeline::from_toml(
+            r#"
+            [[processors]]
+            type = "drop-keys"
+            fields = ["*"]
+            "#,
+        )
+        .expect("Must parse here");
+        let line =
+            InfluxLine::try_new("cpu", "usage", InfluxValue::from(0.5)).expect("Must create here");
+
+        assert!(matches!(
+            pipeline.process(line),
+            Err(InfluxLineError::NoFields)
+        ));
+    }
+
+    #[rstest::rstest]
+    #[case::not_toml("[[processors]")]
+    #[case::no_processors("[processor]\ntype = \"rename\"")]
+    #[case::no_type("[[processors]]\ntags = { a = \"b\" }")]
+    #[case::unknown_type("[[processors]]\ntype = \"explode\"")]
+    #[case::unknown_setting(
+        "[[processors]]\ntype = \"field-to-tag\"\nfield = \"a\"\nfeild = \"b\""
+    )]
+    #[case::missing_setting("[[processors]]\ntype = \"field-to-tag\"")]
+    #[case::wrong_setting_type("[[processors]]\ntype = \"drop-keys\"\ntags = \"a*\"")]
+    #[case::bad_key("[[processors]]\ntype = \"rename\"\ntags = { _a = \"b\" }")]
+    #[case::bad_value_type("[[processors]]\ntype = \"convert\"\nfields = { a = \"decimal\" }")]
+    #[case::bad_pattern(
+        "[[processors]]\ntype = \"regex-replace\"\ntag = \"a\"\npattern = \"(\"\nreplacement = \"\""
+    )]
+    #[case::bad_precision(
+        "[[processors]]\ntype = \"timestamp-from-field\"\nfield = \"a\"\nprecision = \"h\""
+    )]
+    fn bad_pipelines(#[case] source: &str) {
+        assert!(matches!(
+            Pipeline::from_toml(source),
+            Err(InfluxLineError::BadPipeline(_))
+        ));
+    }
+}
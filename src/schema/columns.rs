@@ -0,0 +1,239 @@
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::{InfluxLineError, InfluxValueType, KeyName, KeyPresence, MeasurementName, Schema};
+
+/// A single entry of an explicit bucket schema columns file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Column {
+    name: String,
+    column_type: String,
+    data_type: Option<String>,
+}
+
+impl Schema {
+    /// Loads a schema from an InfluxDB
+    /// [explicit bucket schema](https://docs.influxdata.com/influxdb/cloud/admin/buckets/bucket-schema/)
+    /// columns file, as accepted by `influx bucket-schema create --columns-file`.
+    ///
+    /// All three formats are detected automatically:
+    ///
+    /// - CSV with a `name,type,data_type` header.
+    /// - JSON array of `{"name": ..., "type": ..., "dataType": ...}` objects.
+    /// - NDJSON with one such object per line.
+    ///
+    /// Just like in InfluxDB, every tag and field is optional,
+    /// and keys that are not listed are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influx_line::*;
+    ///
+    /// let columns = "name,type,data_type\ntime,timestamp,\nhost,tag,\nusage,field,float\n";
+    /// let schema = Schema::from_columns(MeasurementName::new("cpu").unwrap(), columns).unwrap();
+    ///
+    /// let line = InfluxLine::try_new("cpu", "usage", 0.5)
+    ///     .and_then(|line| line.try_with_tag("host", "a"))
+    ///     .unwrap();
+    /// assert!(schema.validate(&line).is_ok());
+    /// ```
+    pub fn from_columns(
+        measurement: MeasurementName,
+        columns: &str,
+    ) -> Result<Self, InfluxLineError> {
+        let columns = match columns.trim_start().chars().next() {
+            Some('[') => parse_json(columns)?,
+            Some('{') => parse_ndjson(columns)?,
+            _ => parse_csv(columns)?,
+        };
+
+        let mut has_timestamp = false;
+        let mut schema = Schema::new(measurement);
+        for column in columns {
+            match (column.column_type.as_str(), column.data_type.as_deref()) {
+                ("timestamp", _) if column.name == "time" && !has_timestamp => {
+                    has_timestamp = true;
+                }
+                ("tag", None | Some("string")) => {
+                    let key = KeyName::new(column.name)?;
+                    schema = schema.with_tag(key, KeyPresence::Optional);
+                }
+                ("field", Some(data_type)) => {
+                    let value_type = InfluxValueType::from_str(data_type)?;
+                    let key = KeyName::new(column.name)?;
+                    schema = schema.with_field(key, value_type, KeyPresence::Optional);
+                }
+                _ => return Err(InfluxLineError::BadColumnsFile),
+            }
+        }
+
+        if !has_timestamp {
+            return Err(InfluxLineError::BadColumnsFile);
+        }
+
+        Ok(schema)
+    }
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<Column>, InfluxLineError> {
+    let mut records = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(split_csv_record);
+
+    let header = records.next().ok_or(InfluxLineError::BadColumnsFile)??;
+    let position = |name: &str| header.iter().position(|column| column.trim() == name);
+    let name_index = position("name").ok_or(InfluxLineError::BadColumnsFile)?;
+    let type_index = position("type").ok_or(InfluxLineError::BadColumnsFile)?;
+    let data_type_index = position("data_type");
+
+    records
+        .map(|record| {
+            let record = record?;
+            let cell = |index: usize| record.get(index).map(|cell| cell.trim().to_owned());
+            Ok(Column {
+                name: cell(name_index).ok_or(InfluxLineError::BadColumnsFile)?,
+                column_type: cell(type_index).ok_or(InfluxLineError::BadColumnsFile)?,
+                data_type: data_type_index
+                    .and_then(cell)
+                    .filter(|data_type| !data_type.is_empty()),
+            })
+        })
+        .collect()
+}
+
+/// Splits a CSV record, handling double-quoted cells with `""` escapes.
+fn split_csv_record(record: &str) -> Result<Vec<String>, InfluxLineError> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut characters = record.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match (quoted, character) {
+            (true, '"') if characters.peek() == Some(&'"') => {
+                characters.next();
+                cell.push('"');
+            }
+            (true, '"') => quoted = false,
+            (false, '"') if cell.is_empty() => quoted = true,
+            (false, ',') => cells.push(std::mem::take(&mut cell)),
+            (_, character) => cell.push(character),
+        }
+    }
+
+    if quoted {
+        return Err(InfluxLineError::BadColumnsFile);
+    }
+    cells.push(cell);
+    Ok(cells)
+}
+
+fn parse_json(contents: &str) -> Result<Vec<Column>, InfluxLineError> {
+    let value: Value =
+        serde_json::from_str(contents).map_err(|_| InfluxLineError::BadColumnsFile)?;
+    value
+        .as_array()
+        .ok_or(InfluxLineError::BadColumnsFile)?
+        .iter()
+        .map(json_column)
+        .collect()
+}
+
+fn parse_ndjson(contents: &str) -> Result<Vec<Column>, InfluxLineError> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let value: Value =
+                serde_json::from_str(line).map_err(|_| InfluxLineError::BadColumnsFile)?;
+            json_column(&value)
+        })
+        .collect()
+}
+
+fn json_column(value: &Value) -> Result<Column, InfluxLineError> {
+    let text = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_owned);
+    Ok(Column {
+        name: text("name").ok_or(InfluxLineError::BadColumnsFile)?,
+        column_type: text("type").ok_or(InfluxLineError::BadColumnsFile)?,
+        data_type: text("dataType"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InfluxLine, MeasurementName, Schema};
+
+    use super::split_csv_record;
+
+    fn measurement() -> MeasurementName {
+        MeasurementName::new("cpu").unwrap()
+    }
+
+    fn line() -> InfluxLine {
+        InfluxLine::try_new("cpu", "usage user", 0.5)
+            .and_then(|line| line.try_with_field("cores", 4_u32))
+            .and_then(|line| line.try_with_tag("host", "a"))
+            .unwrap()
+    }
+
+    #[rstest::rstest]
+    #[case::csv(
+        "name,type,data_type\ntime,timestamp,\nhost,tag,\nusage user,field,float\ncores,field,unsigned\n"
+    )]
+    #[case::csv_reordered_and_quoted(
+        "type,name,data_type\r\ntimestamp,time,\r\ntag,\"host\",\r\nfield,\"usage user\",float\r\nfield,cores,unsigned\r\n"
+    )]
+    #[case::json(
+        r#"[
+            {"name": "time", "type": "timestamp"},
+            {"name": "host", "type": "tag"},
+            {"name": "usage user", "type": "field", "dataType": "float"},
+            {"name": "cores", "type": "field", "dataType": "unsigned"}
+        ]"#
+    )]
+    #[case::ndjson(
+        r#"{"name": "time", "type": "timestamp"}
+{"name": "host", "type": "tag"}
+{"name": "usage user", "type": "field", "dataType": "float"}
+{"name": "cores", "type": "field", "dataType": "unsigned"}"#
+    )]
+    fn successful_parsing(#[case] columns: &str) {
+        let schema = Schema::from_columns(measurement(), columns).expect("Must parse here");
+
+        schema.validate(&line()).expect("Must be valid here");
+        schema
+            .validate(&line().try_with_tag("dc", "x").unwrap())
+            .expect_err("Unknown tags must be rejected");
+        schema
+            .validate(&line().try_with_field("cores", 4).unwrap())
+            .expect_err("Field types must be enforced");
+    }
+
+    #[rstest::rstest]
+    #[case::empty("")]
+    #[case::no_header("time,timestamp,\n")]
+    #[case::no_timestamp("name,type,data_type\nhost,tag,\n")]
+    #[case::field_without_type("name,type,data_type\ntime,timestamp,\nusage,field,\n")]
+    #[case::unknown_data_type("name,type,data_type\ntime,timestamp,\nusage,field,decimal\n")]
+    #[case::unknown_column_type("name,type,data_type\ntime,timestamp,\nusage,column,float\n")]
+    #[case::reserved_name("name,type,data_type\ntime,timestamp,\n_usage,field,float\n")]
+    #[case::unclosed_quote("name,type,data_type\ntime,timestamp,\n\"usage,field,float\n")]
+    #[case::json_not_array(r#"[{"name": "time"}"#)]
+    #[case::json_missing_type(r#"[{"name": "time"}]"#)]
+    fn parsing_error(#[case] columns: &str) {
+        let _error = Schema::from_columns(measurement(), columns).expect_err("Must fail here");
+    }
+
+    #[rstest::rstest]
+    #[case::plain("a,b,c", vec!["a", "b", "c"])]
+    #[case::empty_cells("a,,", vec!["a", "", ""])]
+    #[case::quoted("\"a,b\",c", vec!["a,b", "c"])]
+    #[case::escaped_quote("\"say \"\"hi\"\"\",c", vec!["say \"hi\"", "c"])]
+    fn csv_records(#[case] record: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, split_csv_record(record).expect("Must parse here"));
+    }
+}
@@ -0,0 +1,544 @@
+use std::collections::BTreeMap;
+
+use regex::Regex;
+
+use crate::{
+    InfluxLine, InfluxLineError, InfluxValue, InfluxValueType, KeyName, MeasurementName,
+    MeasurementSchema,
+};
+
+/// A declared schema of a single measurement that Lines are validated against.
+///
+/// Tags may be required or optional, and may restrict their values with a pattern.
+/// Fields may be required or optional, and always have a fixed [`InfluxValueType`].
+/// Keys that are not declared are rejected unless allowed explicitly.
+///
+/// A schema can also be loaded from an InfluxDB explicit bucket schema
+/// columns file via [`Self::from_columns`],
+/// or derived from a [`MeasurementSchema`] learned by a [`crate::SchemaRegistry`].
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let schema = Schema::new(MeasurementName::new("cpu").unwrap())
+///     .with_tag(KeyName::new("host").unwrap(), KeyPresence::Required)
+///     .try_with_tag_pattern(KeyName::new("host").unwrap(), "web-[0-9]+")
+///     .unwrap()
+///     .with_field(
+///         KeyName::new("usage").unwrap(),
+///         InfluxValueType::Float,
+///         KeyPresence::Required,
+///     );
+///
+/// let good = InfluxLine::try_new("cpu", "usage", 0.5)
+///     .and_then(|line| line.try_with_tag("host", "web-1"))
+///     .unwrap();
+/// let bad = InfluxLine::try_new("cpu", "usage", 1)
+///     .and_then(|line| line.try_with_tag("host", "db-1"))
+///     .unwrap();
+///
+/// assert!(schema.validate(&good).is_ok());
+/// assert_eq!(2, schema.validate(&bad).unwrap_err().len());
+///
+/// // Integers are safely converted to floats.
+/// let coerced = schema.coerce(InfluxLine::try_new("cpu", "usage", 1)
+///     .and_then(|line| line.try_with_tag("host", "web-1"))
+///     .unwrap())
+///     .unwrap();
+/// assert_eq!(Some(&InfluxValue::Float(1.0)), coerced.field("usage"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Schema {
+    measurement: MeasurementName,
+    tags: BTreeMap<KeyName, TagRule>,
+    fields: BTreeMap<KeyName, FieldRule>,
+    unknown_tags: UnknownKeys,
+    unknown_fields: UnknownKeys,
+}
+
+/// A collection of [`Schema`]s, one per measurement.
+///
+/// Lines of measurements without a schema are rejected unless allowed explicitly.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaSet {
+    schemas: BTreeMap<MeasurementName, Schema>,
+    unknown_measurements: UnknownKeys,
+}
+
+/// Whether a declared tag or field must be present in every Line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyPresence {
+    Required,
+    Optional,
+}
+
+/// Whether keys that are not declared in a schema are accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownKeys {
+    Allow,
+    #[default]
+    Forbid,
+}
+
+/// A single reason for a Line not to match its [`Schema`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SchemaViolation {
+    #[error("Measurement `{0}` has no schema")]
+    UnknownMeasurement(MeasurementName),
+    #[error("Required tag `{0}` is missing")]
+    MissingTag(KeyName),
+    #[error("Tag `{0}` is not declared")]
+    UnknownTag(KeyName),
+    #[error("Tag `{key}` value `{value}` does not match the allowed pattern")]
+    TagValueMismatch { key: KeyName, value: KeyName },
+    #[error("Required field `{0}` is missing")]
+    MissingField(KeyName),
+    #[error("Field `{0}` is not declared")]
+    UnknownField(KeyName),
+    #[error("Field `{key}` must be {expected}, but {actual} was found")]
+    FieldType {
+        key: KeyName,
+        expected: InfluxValueType,
+        actual: InfluxValueType,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct TagRule {
+    presence: KeyPresence,
+    pattern: Option<Regex>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FieldRule {
+    presence: KeyPresence,
+    value_type: InfluxValueType,
+}
+
+impl Schema {
+    /// Creates a schema without any keys, which forbids unknown keys.
+    pub fn new(measurement: MeasurementName) -> Self {
+        Self {
+            measurement,
+            tags: BTreeMap::new(),
+            fields: BTreeMap::new(),
+            unknown_tags: UnknownKeys::Forbid,
+            unknown_fields: UnknownKeys::Forbid,
+        }
+    }
+
+    /// Returns the measurement name.
+    pub fn measurement(&self) -> &MeasurementName {
+        &self.measurement
+    }
+
+    /// Declares a tag, keeping its value pattern if it has one already.
+    pub fn with_tag(mut self, key: KeyName, presence: KeyPresence) -> Self {
+        self.tags
+            .entry(key)
+            .and_modify(|rule| rule.presence = presence)
+            .or_insert(TagRule {
+                presence,
+                pattern: None,
+            });
+        self
+    }
+
+    /// Restricts tag values to the ones matching a regular expression as a whole.
+    ///
+    /// Declares an optional tag if it is not declared yet.
+    /// Fails with [`InfluxLineError::BadPattern`] if the expression does not compile.
+    pub fn try_with_tag_pattern(
+        mut self,
+        key: KeyName,
+        pattern: &str,
+    ) -> Result<Self, InfluxLineError> {
+        let pattern =
+            Regex::new(&format!("^(?:{})$", pattern)).map_err(|_| InfluxLineError::BadPattern)?;
+        self.tags
+            .entry(key)
+            .or_insert(TagRule {
+                presence: KeyPresence::Optional,
+                pattern: None,
+            })
+            .pattern
+            .replace(pattern);
+        Ok(self)
+    }
+
+    /// Declares a field of a fixed type.
+    pub fn with_field(
+        mut self,
+        key: KeyName,
+        value_type: InfluxValueType,
+        presence: KeyPresence,
+    ) -> Self {
+        self.fields.insert(
+            key,
+            FieldRule {
+                presence,
+                value_type,
+            },
+        );
+        self
+    }
+
+    /// Decides whether tags that are not declared are accepted.
+    pub fn with_unknown_tags(mut self, unknown_tags: UnknownKeys) -> Self {
+        self.unknown_tags = unknown_tags;
+        self
+    }
+
+    /// Decides whether fields that are not declared are accepted.
+    pub fn with_unknown_fields(mut self, unknown_fields: UnknownKeys) -> Self {
+        self.unknown_fields = unknown_fields;
+        self
+    }
+
+    /// Checks the Line against the schema, reporting every violation found.
+    pub fn validate(&self, line: &InfluxLine) -> Result<(), Vec<SchemaViolation>> {
+        let mut violations = Vec::new();
+
+        if line.measurement() != &self.measurement {
+            violations.push(SchemaViolation::UnknownMeasurement(
+                line.measurement().clone(),
+            ));
+        }
+
+        for (key, rule) in self.tags.iter() {
+            match line.tag(key) {
+                None if rule.presence == KeyPresence::Required => {
+                    violations.push(SchemaViolation::MissingTag(key.clone()));
+                }
+                Some(value) if !rule.allows(value) => {
+                    violations.push(SchemaViolation::TagValueMismatch {
+                        key: key.clone(),
+                        value: value.clone(),
+                    });
+                }
+                _ => (),
+            }
+        }
+        if self.unknown_tags == UnknownKeys::Forbid {
+            violations.extend(
+                line.tags()
+                    .filter(|(key, _)| !self.tags.contains_key(*key))
+                    .map(|(key, _)| SchemaViolation::UnknownTag(key.clone())),
+            );
+        }
+
+        for (key, rule) in self.fields.iter() {
+            match line.field(key) {
+                None if rule.presence == KeyPresence::Required => {
+                    violations.push(SchemaViolation::MissingField(key.clone()));
+                }
+                Some(value) if value.value_type() != rule.value_type => {
+                    violations.push(SchemaViolation::FieldType {
+                        key: key.clone(),
+                        expected: rule.value_type,
+                        actual: value.value_type(),
+                    });
+                }
+                _ => (),
+            }
+        }
+        if self.unknown_fields == UnknownKeys::Forbid {
+            violations.extend(
+                line.fields()
+                    .filter(|(key, _)| !self.fields.contains_key(*key))
+                    .map(|(key, _)| SchemaViolation::UnknownField(key.clone())),
+            );
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Converts field values to their declared types where no information is lost,
+    /// then validates the resulting Line.
+    ///
+    /// Safe conversions are:
+    ///
+    /// - Integer and Unsigned Integer to Float, if the value is exactly representable.
+    /// - Unsigned Integer to Integer and back, if the value fits into the target type.
+    pub fn coerce(&self, mut line: InfluxLine) -> Result<InfluxLine, Vec<SchemaViolation>> {
+        let conversions: Vec<_> = self
+            .fields
+            .iter()
+            .filter_map(|(key, rule)| {
+                let value = line.field(key)?;
+                let converted = coerce_value(value, rule.value_type)?;
+                Some((key.clone(), converted))
+            })
+            .collect();
+
+        for (key, value) in conversions {
+            line = line.with_field(key, value);
+        }
+
+        self.validate(&line)?;
+        Ok(line)
+    }
+}
+
+impl From<&MeasurementSchema> for Schema {
+    /// Declares every learned tag and field as optional and forbids unknown keys.
+    fn from(learned: &MeasurementSchema) -> Self {
+        let schema = learned
+            .tags()
+            .fold(Schema::new(learned.measurement().clone()), |schema, tag| {
+                schema.with_tag(tag.clone(), KeyPresence::Optional)
+            });
+        learned
+            .fields()
+            .fold(schema, |schema, (field, value_type)| {
+                schema.with_field(field.clone(), value_type, KeyPresence::Optional)
+            })
+    }
+}
+
+impl SchemaSet {
+    /// Creates an empty set which rejects every measurement.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a schema, replacing the previous schema of the same measurement.
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schemas.insert(schema.measurement.clone(), schema);
+        self
+    }
+
+    /// Decides whether Lines of measurements without a schema are accepted.
+    pub fn with_unknown_measurements(mut self, unknown_measurements: UnknownKeys) -> Self {
+        self.unknown_measurements = unknown_measurements;
+        self
+    }
+
+    /// Returns the schema of a measurement.
+    pub fn schema<S>(&self, measurement: S) -> Option<&Schema>
+    where
+        S: AsRef<str>,
+    {
+        let measurement = MeasurementName::new(measurement.as_ref()).ok()?;
+        self.schemas.get(&measurement)
+    }
+
+    /// Checks the Line against the schema of its measurement.
+    pub fn validate(&self, line: &InfluxLine) -> Result<(), Vec<SchemaViolation>> {
+        match self.schemas.get(line.measurement()) {
+            Some(schema) => schema.validate(line),
+            None => self.unknown_measurement(line),
+        }
+    }
+
+    /// Coerces the Line using the schema of its measurement. See [`Schema::coerce`].
+    pub fn coerce(&self, line: InfluxLine) -> Result<InfluxLine, Vec<SchemaViolation>> {
+        match self.schemas.get(line.measurement()) {
+            Some(schema) => schema.coerce(line),
+            None => self.unknown_measurement(&line).map(|_| line),
+        }
+    }
+
+    fn unknown_measurement(&self, line: &InfluxLine) -> Result<(), Vec<SchemaViolation>> {
+        match self.unknown_measurements {
+            UnknownKeys::Allow => Ok(()),
+            UnknownKeys::Forbid => Err(vec![SchemaViolation::UnknownMeasurement(
+                line.measurement().clone(),
+            )]),
+        }
+    }
+}
+
+impl TagRule {
+    fn allows(&self, value: &KeyName) -> bool {
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(value))
+    }
+}
+
+fn coerce_value(value: &InfluxValue, target: InfluxValueType) -> Option<InfluxValue> {
+    /// Integers with a greater magnitude may not survive a round trip through `f64`.
+    const MAX_EXACT_FLOAT: u64 = 1 << f64::MANTISSA_DIGITS;
+
+    match (value, target) {
+        (InfluxValue::Integer(integer), InfluxValueType::Float) => {
+            let integer = i64::from(*integer);
+            (integer.unsigned_abs() <= MAX_EXACT_FLOAT).then(|| InfluxValue::from(integer as f64))
+        }
+        (InfluxValue::UInteger(uinteger), InfluxValueType::Float) => {
+            let uinteger = u64::from(*uinteger);
+            (uinteger <= MAX_EXACT_FLOAT).then(|| InfluxValue::from(uinteger as f64))
+        }
+        (InfluxValue::UInteger(uinteger), InfluxValueType::Integer) => {
+            i64::try_from(u64::from(*uinteger))
+                .ok()
+                .map(InfluxValue::from)
+        }
+        (InfluxValue::Integer(integer), InfluxValueType::UInteger) => {
+            u64::try_from(i64::from(*integer))
+                .ok()
+                .map(InfluxValue::from)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        InfluxLine, InfluxValue, InfluxValueType, KeyName, KeyPresence, MeasurementName, Schema,
+        SchemaRegistry, SchemaSet, SchemaViolation, UnknownKeys,
+    };
+
+    fn key(name: &str) -> KeyName {
+        KeyName::new(name).unwrap()
+    }
+
+    fn schema() -> Schema {
+        Schema::new(MeasurementName::new("cpu").unwrap())
+            .with_tag(key("host"), KeyPresence::Required)
+            .try_with_tag_pattern(key("region"), "eu-[a-z]+")
+            .unwrap()
+            .with_field(key("usage"), InfluxValueType::Float, KeyPresence::Required)
+            .with_field(
+                key("cores"),
+                InfluxValueType::UInteger,
+                KeyPresence::Optional,
+            )
+    }
+
+    fn line() -> InfluxLine {
+        InfluxLine::try_new("cpu", "usage", 0.5)
+            .and_then(|line| line.try_with_tag("host", "a"))
+            .unwrap()
+    }
+
+    #[rstest::rstest]
+    #[case::minimal(line())]
+    #[case::optional_keys(
+        line()
+            .with_tag(key("region"), key("eu-west"))
+            .with_field(key("cores"), 8_u32)
+    )]
+    fn valid_lines(#[case] line: InfluxLine) {
+        schema().validate(&line).expect("Must be valid here");
+    }
+
+    #[rstest::rstest]
+    #[case::missing_tag(
+        InfluxLine::try_new("cpu", "usage", 0.5).unwrap(),
+        SchemaViolation::MissingTag(key("host"))
+    )]
+    #[case::pattern_mismatch(
+        line().with_tag(key("region"), key("us-east")),
+        SchemaViolation::TagValueMismatch { key: key("region"), value: key("us-east") }
+    )]
+    #[case::partial_pattern_match(
+        line().with_tag(key("region"), key("eu-west-1")),
+        SchemaViolation::TagValueMismatch { key: key("region"), value: key("eu-west-1") }
+    )]
+    #[case::unknown_tag(line().with_tag(key("dc"), key("x")), SchemaViolation::UnknownTag(key("dc")))]
+    #[case::missing_field(
+        InfluxLine::try_new("cpu", "cores", 8_u32)
+            .and_then(|line| line.try_with_tag("host", "a"))
+            .unwrap(),
+        SchemaViolation::MissingField(key("usage"))
+    )]
+    #[case::unknown_field(line().with_field(key("idle"), 0.5), SchemaViolation::UnknownField(key("idle")))]
+    #[case::field_type(
+        line().with_field(key("cores"), "eight"),
+        SchemaViolation::FieldType {
+            key: key("cores"),
+            expected: InfluxValueType::UInteger,
+            actual: InfluxValueType::String,
+        }
+    )]
+    #[case::measurement(
+        InfluxLine::try_new("mem", "usage", 0.5)
+            .and_then(|line| line.try_with_tag("host", "a"))
+            .unwrap(),
+        SchemaViolation::UnknownMeasurement(MeasurementName::new("mem").unwrap())
+    )]
+    fn violations(#[case] line: InfluxLine, #[case] expected_violation: SchemaViolation) {
+        let violations = schema().validate(&line).expect_err("Must be invalid here");
+
+        assert_eq!(vec![expected_violation], violations);
+    }
+
+    #[test]
+    fn unknown_keys_may_be_allowed() {
+        let schema = schema()
+            .with_unknown_tags(UnknownKeys::Allow)
+            .with_unknown_fields(UnknownKeys::Allow);
+        let line = line()
+            .with_tag(key("dc"), key("x"))
+            .with_field(key("idle"), 0.5);
+
+        schema.validate(&line).expect("Must be valid here");
+    }
+
+    #[rstest::rstest]
+    #[case::integer_to_float(1, InfluxValueType::Float, Some(InfluxValue::Float(1.0)))]
+    #[case::unsigned_to_float(2_u32, InfluxValueType::Float, Some(InfluxValue::Float(2.0)))]
+    #[case::huge_integer_to_float(i64::MAX, InfluxValueType::Float, None)]
+    #[case::unsigned_to_integer(3_u32, InfluxValueType::Integer, Some(InfluxValue::from(3)))]
+    #[case::huge_unsigned_to_integer(u64::MAX, InfluxValueType::Integer, None)]
+    #[case::integer_to_unsigned(4, InfluxValueType::UInteger, Some(InfluxValue::from(4_u32)))]
+    #[case::negative_to_unsigned(-4, InfluxValueType::UInteger, None)]
+    #[case::float_to_integer(1.0, InfluxValueType::Integer, None)]
+    #[case::boolean_to_string(true, InfluxValueType::String, None)]
+    fn coercion(
+        #[case] value: impl Into<InfluxValue>,
+        #[case] target: InfluxValueType,
+        #[case] expected: Option<InfluxValue>,
+    ) {
+        let schema = Schema::new(MeasurementName::new("m").unwrap()).with_field(
+            key("f"),
+            target,
+            KeyPresence::Required,
+        );
+        let line = InfluxLine::try_new("m", "f", value).unwrap();
+
+        let actual = schema
+            .coerce(line)
+            .ok()
+            .and_then(|line| line.field("f").cloned());
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn schema_set_routes_by_measurement() {
+        let schemas = SchemaSet::new().with_schema(schema());
+        let unknown = InfluxLine::try_new("mem", "free", 1).unwrap();
+
+        schemas.validate(&line()).expect("Must be valid here");
+        schemas
+            .validate(&unknown)
+            .expect_err("Must be invalid here");
+        schemas
+            .with_unknown_measurements(UnknownKeys::Allow)
+            .validate(&unknown)
+            .expect("Must be valid here");
+    }
+
+    #[test]
+    fn derived_from_learned_schema() {
+        let mut registry = SchemaRegistry::new();
+        registry.observe(&line()).unwrap();
+
+        let schema = Schema::from(registry.schema("cpu").unwrap());
+
+        schema.validate(&line()).expect("Must be valid here");
+        schema
+            .validate(&line().with_field(key("idle"), 1.0))
+            .expect_err("Must be invalid here");
+    }
+}
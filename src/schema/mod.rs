@@ -0,0 +1,9 @@
+#[cfg(feature = "schema")]
+mod columns;
+#[cfg(feature = "schema")]
+mod definition;
+mod registry;
+
+#[cfg(feature = "schema")]
+pub use self::definition::{KeyPresence, Schema, SchemaSet, SchemaViolation, UnknownKeys};
+pub use self::registry::{MeasurementSchema, SchemaConflict, SchemaRegistry};
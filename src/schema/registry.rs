@@ -0,0 +1,318 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use crate::{InfluxLine, InfluxValueType, KeyName, MeasurementName};
+
+/// Learns the schema of every measurement from the Lines it observes,
+/// and reports Lines that InfluxDB would reject due to field type conflicts.
+///
+/// InfluxDB fixes the type of a field on its first write into a shard.
+/// Writing another type afterwards, e.g. `1i` after `1.0`,
+/// fails the request and may drop the whole batch.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let mut registry = SchemaRegistry::new();
+///
+/// let float = InfluxLine::try_new("cpu", "usage", 1.0).unwrap();
+/// let integer = InfluxLine::try_new("cpu", "usage", 1).unwrap();
+///
+/// registry.observe(&float).unwrap();
+/// let conflicts = registry.observe(&integer).unwrap_err();
+///
+/// assert_eq!(conflicts[0].field().as_str(), "usage");
+/// assert_eq!(conflicts[0].expected(), InfluxValueType::Float);
+/// assert_eq!(conflicts[0].actual(), InfluxValueType::Integer);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaRegistry {
+    measurements: BTreeMap<MeasurementName, MeasurementSchema>,
+}
+
+/// Tag keys and field types learned for a single measurement.
+///
+/// [`Display`] formats the schema as an InfluxDB
+/// [explicit bucket schema](https://docs.influxdata.com/influxdb/cloud/admin/buckets/bucket-schema/)
+/// columns file in CSV, so it can be fed to `influx bucket-schema create --columns-file`.
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let mut registry = SchemaRegistry::new();
+/// let line = InfluxLine::try_new("cpu", "usage", 1.0)
+///     .and_then(|line| line.try_with_tag("host", "a"))
+///     .unwrap();
+/// registry.observe(&line).unwrap();
+///
+/// let expected = "name,type,data_type\ntime,timestamp,\nhost,tag,\nusage,field,float\n";
+/// assert_eq!(expected, registry.schema("cpu").unwrap().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeasurementSchema {
+    measurement: MeasurementName,
+    tags: BTreeSet<KeyName>,
+    fields: BTreeMap<KeyName, InfluxValueType>,
+}
+
+/// A field whose type differs from the one already known for its measurement.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Field `{field}` of `{measurement}` is {expected}, but {actual} was found")]
+pub struct SchemaConflict {
+    measurement: MeasurementName,
+    field: KeyName,
+    expected: InfluxValueType,
+    actual: InfluxValueType,
+}
+
+impl SchemaRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compares the Line against the known schema without learning anything from it.
+    pub fn check(&self, line: &InfluxLine) -> Result<(), Vec<SchemaConflict>> {
+        match self.measurements.get(line.measurement()) {
+            Some(schema) => schema.check(line),
+            None => Ok(()),
+        }
+    }
+
+    /// Learns tag keys and field types from the Line.
+    ///
+    /// A conflicting Line is reported and not learned from at all,
+    /// so that a single bad Line does not poison the registry.
+    pub fn observe(&mut self, line: &InfluxLine) -> Result<(), Vec<SchemaConflict>> {
+        self.check(line)?;
+
+        self.measurements
+            .entry(line.measurement().clone())
+            .or_insert_with(|| MeasurementSchema::new(line.measurement().clone()))
+            .learn(line);
+
+        Ok(())
+    }
+
+    /// Returns the schema learned for a measurement.
+    pub fn schema<S>(&self, measurement: S) -> Option<&MeasurementSchema>
+    where
+        S: AsRef<str>,
+    {
+        let measurement = MeasurementName::new(measurement.as_ref()).ok()?;
+        self.measurements.get(&measurement)
+    }
+
+    /// Returns an iterator over all learned schemas, ordered by measurement name.
+    pub fn schemas(&self) -> impl Iterator<Item = &MeasurementSchema> {
+        self.measurements.values()
+    }
+}
+
+impl MeasurementSchema {
+    /// Creates an empty schema for a measurement.
+    pub fn new(measurement: MeasurementName) -> Self {
+        Self {
+            measurement,
+            tags: BTreeSet::new(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the measurement name.
+    pub fn measurement(&self) -> &MeasurementName {
+        &self.measurement
+    }
+
+    /// Returns an iterator over the known tag keys in sorted order.
+    pub fn tags(&self) -> impl Iterator<Item = &KeyName> {
+        self.tags.iter()
+    }
+
+    /// Returns an iterator over the known field keys and their types in sorted order.
+    pub fn fields(&self) -> impl Iterator<Item = (&KeyName, InfluxValueType)> {
+        self.fields
+            .iter()
+            .map(|(key, value_type)| (key, *value_type))
+    }
+
+    /// Returns the known type of a field.
+    pub fn field_type<S>(&self, name: S) -> Option<InfluxValueType>
+    where
+        S: AsRef<str>,
+    {
+        let name = KeyName::new(name.as_ref()).ok()?;
+        self.fields.get(&name).copied()
+    }
+
+    fn check(&self, line: &InfluxLine) -> Result<(), Vec<SchemaConflict>> {
+        let conflicts: Vec<_> = line
+            .fields()
+            .filter_map(|(key, value)| {
+                let expected = *self.fields.get(key)?;
+                let actual = value.value_type();
+                (expected != actual).then(|| SchemaConflict {
+                    measurement: self.measurement.clone(),
+                    field: key.clone(),
+                    expected,
+                    actual,
+                })
+            })
+            .collect();
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(conflicts)
+        }
+    }
+
+    fn learn(&mut self, line: &InfluxLine) {
+        self.tags.extend(line.tags().map(|(key, _)| key.clone()));
+        for (key, value) in line.fields() {
+            self.fields
+                .entry(key.clone())
+                .or_insert_with(|| value.value_type());
+        }
+    }
+}
+
+impl Display for MeasurementSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "name,type,data_type")?;
+        writeln!(f, "time,timestamp,")?;
+        for tag in self.tags.iter() {
+            writeln!(f, "{},tag,", csv_escape(tag))?;
+        }
+        for (field, value_type) in self.fields.iter() {
+            writeln!(f, "{},field,{}", csv_escape(field), value_type)?;
+        }
+        Ok(())
+    }
+}
+
+impl SchemaConflict {
+    /// Returns the measurement name.
+    pub fn measurement(&self) -> &MeasurementName {
+        &self.measurement
+    }
+
+    /// Returns the conflicting field key.
+    pub fn field(&self) -> &KeyName {
+        &self.field
+    }
+
+    /// Returns the type already known for the field.
+    pub fn expected(&self) -> InfluxValueType {
+        self.expected
+    }
+
+    /// Returns the type found in the Line.
+    pub fn actual(&self) -> InfluxValueType {
+        self.actual
+    }
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InfluxLine, InfluxValueType, SchemaRegistry};
+
+    fn line(field: &str, value: impl Into<crate::InfluxValue>) -> InfluxLine {
+        InfluxLine::try_new("cpu", field, value)
+            .and_then(|line| line.try_with_tag("host", "a"))
+            .unwrap()
+    }
+
+    #[rstest::rstest]
+    #[case::integer_after_float(line("v", 1.0), line("v", 1), InfluxValueType::Integer)]
+    #[case::float_after_integer(line("v", 1), line("v", 1.0), InfluxValueType::Float)]
+    #[case::unsigned_after_integer(line("v", 1), line("v", 1_u32), InfluxValueType::UInteger)]
+    #[case::string_after_boolean(line("v", true), line("v", "true"), InfluxValueType::String)]
+    fn reports_conflicts(
+        #[case] first: InfluxLine,
+        #[case] second: InfluxLine,
+        #[case] expected_actual: InfluxValueType,
+    ) {
+        let mut registry = SchemaRegistry::new();
+        registry
+            .observe(&first)
+            .expect("First line defines the schema");
+
+        let conflicts = registry.observe(&second).expect_err("Must conflict here");
+
+        assert_eq!(1, conflicts.len());
+        assert_eq!(expected_actual, conflicts[0].actual());
+        assert_eq!(
+            Some(conflicts[0].expected()),
+            first.field("v").map(|v| v.value_type())
+        );
+    }
+
+    #[test]
+    fn same_types_and_other_measurements_do_not_conflict() {
+        let mut registry = SchemaRegistry::new();
+        let other_measurement = InfluxLine::try_new("mem", "v", 1).unwrap();
+
+        registry.observe(&line("v", 1.0)).unwrap();
+        registry.observe(&line("v", 2.5)).unwrap();
+        registry.observe(&other_measurement).unwrap();
+
+        assert_eq!(2, registry.schemas().count());
+    }
+
+    #[test]
+    fn conflicting_line_is_not_learned() {
+        let mut registry = SchemaRegistry::new();
+        let conflicting = line("v", 1).with_field("new".try_into().unwrap(), 1);
+
+        registry.observe(&line("v", 1.0)).unwrap();
+        registry.observe(&conflicting).unwrap_err();
+
+        let schema = registry.schema("cpu").unwrap();
+        assert_eq!(None, schema.field_type("new"));
+        assert_eq!(Some(InfluxValueType::Float), schema.field_type("v"));
+    }
+
+    #[test]
+    fn check_does_not_learn() {
+        let registry = SchemaRegistry::new();
+
+        registry.check(&line("v", 1.0)).unwrap();
+
+        assert_eq!(None, registry.schema("cpu"));
+    }
+
+    #[test]
+    fn exports_columns_file() {
+        let mut registry = SchemaRegistry::new();
+        registry
+            .observe(&line("usage", 1.0).with_field("core, id".try_into().unwrap(), 3_u32))
+            .unwrap();
+        registry
+            .observe(
+                &line("state", "idle").with_tag("dc".try_into().unwrap(), "x".try_into().unwrap()),
+            )
+            .unwrap();
+
+        let expected = concat!(
+            "name,type,data_type\n",
+            "time,timestamp,\n",
+            "dc,tag,\n",
+            "host,tag,\n",
+            "\"core, id\",field,unsigned\n",
+            "state,field,string\n",
+            "usage,field,float\n",
+        );
+        assert_eq!(expected, registry.schema("cpu").unwrap().to_string());
+    }
+}
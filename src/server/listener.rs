@@ -0,0 +1,171 @@
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+
+use tiny_http::{Header, Response, Server};
+
+use crate::InfluxLineError;
+
+use super::{WriteReceiver, WriteRequest};
+
+/// Serves a [`WriteReceiver`] over plain HTTP.
+///
+/// Requests are handled one at a time on the thread calling [`Self::run`],
+/// so a slow callback delays the following requests
+/// and eventually makes clients retry.
+///
+/// Clones share the same socket, so one of them may be kept around
+/// to [`Self::shutdown`] the server from another thread.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use influx_line::*;
+///
+/// let server = WriteServer::bind("127.0.0.1:8086").unwrap();
+/// let receiver = WriteReceiver::new(|request: WriteRequest| {
+///     for line in request.lines() {
+///         println!("{}", line);
+///     }
+///     Ok(())
+/// });
+///
+/// server.run(&receiver).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct WriteServer {
+    server: Arc<Server>,
+}
+
+impl WriteServer {
+    /// Binds a listening socket, e.g. `127.0.0.1:0` to pick a free port.
+    pub fn bind<A>(address: A) -> Result<Self, InfluxLineError>
+    where
+        A: ToSocketAddrs,
+    {
+        let server = Server::http(address).map_err(io::Error::other)?;
+        Ok(Self {
+            server: Arc::new(server),
+        })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Handles requests until [`Self::shutdown`] is called.
+    ///
+    /// Failures to answer a single client are ignored,
+    /// since the client is gone by then anyway.
+    pub fn run<F>(&self, receiver: &WriteReceiver<F>) -> Result<(), InfluxLineError>
+    where
+        F: Fn(WriteRequest) -> Result<(), InfluxLineError>,
+    {
+        for mut request in self.server.incoming_requests() {
+            let method = request.method().to_string();
+            let target = request.url().to_owned();
+            let headers: Vec<(String, String)> = request
+                .headers()
+                .iter()
+                .map(|header| (header.field.to_string(), header.value.to_string()))
+                .collect();
+
+            let response = receiver.handle(
+                &method,
+                &target,
+                headers
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str())),
+                request.as_reader(),
+            );
+
+            let _ = match response.body {
+                Some(body) => {
+                    let content_type =
+                        Header::from_bytes("Content-Type", "application/json; charset=utf-8")
+                            .expect("Static header must be valid");
+                    request.respond(
+                        Response::from_string(body)
+                            .with_status_code(response.status)
+                            .with_header(content_type),
+                    )
+                }
+                None => request.respond(Response::empty(response.status)),
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Makes [`Self::run`] return once the current request is handled.
+    pub fn shutdown(&self) {
+        self.server.unblock();
+    }
+}
+
+impl std::fmt::Debug for WriteServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WriteServer")
+            .field("local_addr", &self.local_addr())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use crate::{InfluxLine, WriteReceiver, WriteRequest, WriteServer};
+
+    fn post(server: &WriteServer, target: &str, body: &str) -> String {
+        let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            target,
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serves_requests() {
+        let server = WriteServer::bind("127.0.0.1:0").unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let handle = {
+            let server = server.clone();
+            let received = received.clone();
+            thread::spawn(move || {
+                let receiver = WriteReceiver::new(move |request: WriteRequest| {
+                    received.lock().unwrap().extend(request.into_lines());
+                    Ok(())
+                });
+                server.run(&receiver)
+            })
+        };
+
+        let accepted = post(&server, "/api/v2/write?org=o&bucket=b", "cpu usage=1\n");
+        let rejected = post(&server, "/write?db=db", "cpu usage=1\ncpu\n");
+        server.shutdown();
+        handle.join().unwrap().unwrap();
+
+        assert!(accepted.starts_with("HTTP/1.1 204"));
+        assert!(rejected.starts_with("HTTP/1.1 400"));
+        assert!(rejected.contains("application/json"));
+        assert!(rejected.contains(r#""line":2"#));
+        assert_eq!(
+            vec![InfluxLine::try_new("cpu", "usage", 1.0).unwrap()],
+            *received.lock().unwrap()
+        );
+    }
+}
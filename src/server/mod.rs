@@ -0,0 +1,553 @@
+mod listener;
+
+use std::io::Read;
+use std::str::FromStr;
+
+use flate2::read::GzDecoder;
+use serde_json::json;
+
+use crate::{InfluxLine, InfluxLineError, Precision, Stamper, WriteEndpoint};
+
+pub use self::listener::WriteServer;
+
+/// A batch of Lines received by [`WriteReceiver`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteRequest {
+    endpoint: WriteEndpoint,
+    token: Option<String>,
+    lines: Vec<InfluxLine>,
+}
+
+/// An InfluxDB-compatible answer to a write request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteResponse {
+    status: u16,
+    body: Option<String>,
+}
+
+/// Accepts InfluxDB write requests and passes the parsed Lines to a callback.
+///
+/// Both `POST /api/v2/write` (requires `bucket` and `org` or `orgID`)
+/// and `POST /write` (requires `db`, accepts `rp`) are supported.
+/// Bodies may be compressed with gzip,
+/// and raw timestamps are scaled according to the `precision` parameter.
+/// Lines without a timestamp are left so, unless [`Self::with_stamper`]
+/// stamps them with the time they were received.
+///
+/// Responses mimic InfluxDB:
+///
+/// - `204 No Content` once the callback succeeds.
+/// - `400 Bad Request` with a JSON body naming the first malformed Line,
+///   in which case the callback is not called at all.
+/// - `413 Payload Too Large` for bodies over [`Self::with_max_body_size`].
+/// - Status and body of [`InfluxLineError::WriteRejected`] returned by the callback,
+///   and `500 Internal Server Error` for any other callback error.
+///
+/// The receiver does no I/O on its own, so it can be plugged into any HTTP server
+/// via [`Self::handle`], or served with the bundled [`WriteServer`].
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let receiver = WriteReceiver::new(|request: WriteRequest| {
+///     assert_eq!(1, request.lines().len());
+///     Ok(())
+/// });
+///
+/// let response = receiver.handle(
+///     "POST",
+///     "/api/v2/write?org=acme&bucket=telemetry&precision=s",
+///     [("Authorization", "Token secret")],
+///     "cpu,host=web-1 usage=0.5 1704067200\n".as_bytes(),
+/// );
+///
+/// assert_eq!(204, response.status());
+/// ```
+#[derive(Debug, Clone)]
+pub struct WriteReceiver<F> {
+    callback: F,
+    max_body_size: usize,
+    stamper: Option<Stamper>,
+}
+
+impl WriteRequest {
+    /// The endpoint and the target database the request came through.
+    pub fn endpoint(&self) -> &WriteEndpoint {
+        &self.endpoint
+    }
+
+    /// The token from the `Authorization` header or the v1 `p` parameter, if any.
+    ///
+    /// The receiver does not check it, leaving authorization to the callback.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    pub fn lines(&self) -> &[InfluxLine] {
+        &self.lines
+    }
+
+    pub fn into_lines(self) -> Vec<InfluxLine> {
+        self.lines
+    }
+}
+
+impl WriteResponse {
+    fn no_content() -> Self {
+        Self {
+            status: 204,
+            body: None,
+        }
+    }
+
+    fn error(status: u16, code: &str, message: String) -> Self {
+        Self {
+            status,
+            body: Some(json!({ "code": code, "message": message }).to_string()),
+        }
+    }
+
+    fn invalid_line(line: usize, text: &str, error: InfluxLineError) -> Self {
+        let body = json!({
+            "code": "invalid",
+            "line": line,
+            "message": format!("unable to parse '{}': {}", text, error),
+        });
+        Self {
+            status: 400,
+            body: Some(body.to_string()),
+        }
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// JSON body describing the error, if any.
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_deref()
+    }
+}
+
+impl<F> WriteReceiver<F>
+where
+    F: Fn(WriteRequest) -> Result<(), InfluxLineError>,
+{
+    const DEFAULT_MAX_BODY_SIZE: usize = 32 * 1024 * 1024;
+
+    pub fn new(callback: F) -> Self {
+        Self {
+            callback,
+            max_body_size: Self::DEFAULT_MAX_BODY_SIZE,
+            stamper: None,
+        }
+    }
+
+    /// Limits the size of a request body after decompression, 32 MiB by default.
+    pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Stamps Lines without a timestamp on receipt,
+    /// with [`crate::StampMode::EachBatch`] once per request.
+    pub fn with_stamper(mut self, stamper: Stamper) -> Self {
+        self.stamper = Some(stamper);
+        self
+    }
+
+    /// Handles a single request given its method, target (path and query),
+    /// headers and raw body.
+    pub fn handle<'a, H>(
+        &self,
+        method: &str,
+        target: &str,
+        headers: H,
+        body: impl Read,
+    ) -> WriteResponse
+    where
+        H: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        match self.try_handle(method, target, headers, body) {
+            Ok(response) | Err(response) => response,
+        }
+    }
+
+    fn try_handle<'a, H>(
+        &self,
+        method: &str,
+        target: &str,
+        headers: H,
+        body: impl Read,
+    ) -> Result<WriteResponse, WriteResponse>
+    where
+        H: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let params: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+
+        if method != "POST" && matches!(path, "/api/v2/write" | "/write") {
+            return Err(WriteResponse::error(
+                405,
+                "method not allowed",
+                format!("method not allowed: {}", method),
+            ));
+        }
+
+        let endpoint = match path {
+            "/api/v2/write" => WriteEndpoint::V2 {
+                org: param("org")
+                    .or_else(|| param("orgID"))
+                    .ok_or_else(|| missing_parameter("org"))?,
+                bucket: param("bucket").ok_or_else(|| missing_parameter("bucket"))?,
+            },
+            "/write" => WriteEndpoint::V1 {
+                db: param("db").ok_or_else(|| missing_parameter("db"))?,
+                rp: param("rp"),
+            },
+            _ => {
+                return Err(WriteResponse::error(
+                    404,
+                    "not found",
+                    format!("path not found: {}", path),
+                ));
+            }
+        };
+
+        let precision = match param("precision") {
+            Some(precision) => Precision::from_str(&precision).map_err(|_| {
+                WriteResponse::error(400, "invalid", format!("invalid precision: {}", precision))
+            })?,
+            None => Precision::Nanoseconds,
+        };
+
+        let mut token = param("p");
+        let mut gzip = false;
+        for (name, value) in headers {
+            if name.eq_ignore_ascii_case("authorization") {
+                token = value
+                    .strip_prefix("Token ")
+                    .or_else(|| value.strip_prefix("Bearer "))
+                    .map(str::to_owned)
+                    .or(token);
+            } else if name.eq_ignore_ascii_case("content-encoding") {
+                gzip = match value.trim() {
+                    "gzip" => true,
+                    "identity" | "" => false,
+                    other => {
+                        return Err(WriteResponse::error(
+                            415,
+                            "unsupported media type",
+                            format!("unsupported content encoding: {}", other),
+                        ));
+                    }
+                };
+            }
+        }
+
+        let text = if gzip {
+            self.read_body(GzDecoder::new(body))?
+        } else {
+            self.read_body(body)?
+        };
+        let mut lines = parse_lines(&text, precision)?;
+        if lines.is_empty() {
+            return Ok(WriteResponse::no_content());
+        }
+        if let Some(stamper) = &self.stamper {
+            lines = stamper.stamp_batch(lines);
+        }
+
+        let request = WriteRequest {
+            endpoint,
+            token,
+            lines,
+        };
+        match (self.callback)(request) {
+            Ok(()) => Ok(WriteResponse::no_content()),
+            Err(InfluxLineError::WriteRejected { status, body, .. }) => Err(WriteResponse {
+                status,
+                body: Some(body).filter(|body| !body.is_empty()),
+            }),
+            Err(error) => Err(WriteResponse::error(
+                500,
+                "internal error",
+                error.to_string(),
+            )),
+        }
+    }
+
+    fn read_body(&self, body: impl Read) -> Result<String, WriteResponse> {
+        let mut bytes = Vec::new();
+        body.take(self.max_body_size as u64 + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|error| {
+                WriteResponse::error(400, "invalid", format!("unable to read body: {}", error))
+            })?;
+
+        if bytes.len() > self.max_body_size {
+            return Err(WriteResponse::error(
+                413,
+                "request too large",
+                format!("body exceeds {} bytes", self.max_body_size),
+            ));
+        }
+
+        String::from_utf8(bytes)
+            .map_err(|_| WriteResponse::error(400, "invalid", "body is not valid UTF-8".to_owned()))
+    }
+}
+
+fn missing_parameter(name: &str) -> WriteResponse {
+    WriteResponse::error(400, "invalid", format!("missing parameter: {}", name))
+}
+
+/// Parses the whole body, skipping blank lines and comments.
+/// Line numbers in errors start from 1, as in InfluxDB.
+fn parse_lines(text: &str, precision: Precision) -> Result<Vec<InfluxLine>, WriteResponse> {
+    let mut lines = Vec::new();
+    for (index, raw) in text.split('\n').enumerate() {
+        let raw = raw.trim();
+        if raw.is_empty() || raw.starts_with('#') {
+            continue;
+        }
+
+        let line = InfluxLine::parse_with_precision(raw, precision)
+            .map_err(|error| WriteResponse::invalid_line(index + 1, raw, error))?;
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    use crate::{
+        InfluxLine, InfluxLineError, ManualClock, StampMode, Stamper, Timestamp, WriteEndpoint,
+        WriteReceiver, WriteRequest,
+    };
+
+    fn receive(
+        target: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> (u16, Option<String>, Option<WriteRequest>) {
+        let received = std::cell::RefCell::new(None);
+        let receiver = WriteReceiver::new(|request| {
+            received.replace(Some(request));
+            Ok(())
+        });
+
+        let response = receiver.handle("POST", target, headers.iter().copied(), body);
+
+        (
+            response.status(),
+            response.body().map(str::to_owned),
+            received.into_inner(),
+        )
+    }
+
+    #[rstest::rstest]
+    #[case::v2(
+        "/api/v2/write?org=acme&bucket=telemetry",
+        WriteEndpoint::V2 { org: "acme".into(), bucket: "telemetry".into() }
+    )]
+    #[case::v2_org_id(
+        "/api/v2/write?orgID=0a1b&bucket=telemetry",
+        WriteEndpoint::V2 { org: "0a1b".into(), bucket: "telemetry".into() }
+    )]
+    #[case::v1("/write?db=telemetry", WriteEndpoint::V1 { db: "telemetry".into(), rp: None })]
+    #[case::v1_with_rp(
+        "/write?db=telemetry&rp=one+week",
+        WriteEndpoint::V1 { db: "telemetry".into(), rp: Some("one week".into()) }
+    )]
+    fn endpoints(#[case] target: &str, #[case] expected: WriteEndpoint) {
+        let (status, _, request) = receive(target, &[], b"cpu usage=1 1\n");
+
+        let request = request.expect("Must receive here");
+        assert_eq!(204, status);
+        assert_eq!(&expected, request.endpoint());
+        assert_eq!(
+            vec![
+                InfluxLine::try_new("cpu", "usage", 1.0)
+                    .unwrap()
+                    .with_timestamp(1)
+            ],
+            request.into_lines()
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::nanoseconds("ns", 1_704_067_200)]
+    #[case::v1_microseconds("u", 1_704_067_200_000)]
+    #[case::milliseconds("ms", 1_704_067_200_000_000)]
+    #[case::seconds("s", 1_704_067_200_000_000_000)]
+    fn precisions(#[case] precision: &str, #[case] expected: i64) {
+        let target = format!("/write?db=db&precision={}", precision);
+
+        let (_, _, request) = receive(&target, &[], b"cpu usage=1 1704067200");
+
+        let request = request.expect("Must receive here");
+        assert_eq!(Some(expected.into()), request.lines()[0].timestamp());
+    }
+
+    #[test]
+    fn precision_overflow() {
+        let (status, body, _) = receive("/write?db=db&precision=s", &[], b"cpu usage=1 1e18");
+
+        assert_eq!(400, status);
+        assert!(body.unwrap().contains(r#""line":1"#));
+    }
+
+    #[test]
+    fn gzip_body() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"cpu usage=1\nmem used=2\n").unwrap();
+        let body = encoder.finish().unwrap();
+
+        let (status, _, request) = receive(
+            "/write?db=db",
+            &[
+                ("Content-Encoding", "gzip"),
+                ("Authorization", "Token secret"),
+            ],
+            &body,
+        );
+
+        let request = request.expect("Must receive here");
+        assert_eq!(204, status);
+        assert_eq!(Some("secret"), request.token());
+        assert_eq!(2, request.lines().len());
+    }
+
+    #[test]
+    fn stamps_on_receipt() {
+        let received = std::cell::RefCell::new(None);
+        let receiver = WriteReceiver::new(|request: WriteRequest| {
+            received.replace(Some(request));
+            Ok(())
+        })
+        .with_stamper(
+            Stamper::new()
+                .with_clock(ManualClock::new(Timestamp::from(42)))
+                .with_mode(StampMode::EachBatch),
+        );
+
+        let response = receiver.handle(
+            "POST",
+            "/write?db=db&precision=s",
+            [],
+            "cpu usage=1\ncpu usage=2 1\n".as_bytes(),
+        );
+
+        let request = received.into_inner().expect("Must receive here");
+        let timestamps: Vec<_> = request.lines().iter().map(InfluxLine::timestamp).collect();
+        assert_eq!(204, response.status());
+        assert_eq!(
+            vec![
+                Some(Timestamp::from(42)),
+                Some(Timestamp::from(1_000_000_000))
+            ],
+            timestamps
+        );
+    }
+
+    #[test]
+    fn skips_blank_lines_and_comments() {
+        let (status, _, request) = receive(
+            "/write?db=db",
+            &[],
+            b"# header\r\n\r\ncpu usage=1\r\n\n  \nmem used=2",
+        );
+
+        assert_eq!(204, status);
+        assert_eq!(2, request.expect("Must receive here").lines().len());
+    }
+
+    #[test]
+    fn names_failing_line() {
+        let (status, body, request) = receive("/write?db=db", &[], b"cpu usage=1\n\ncpu usage=\n");
+
+        let body: serde_json::Value = serde_json::from_str(&body.unwrap()).unwrap();
+        assert_eq!(400, status);
+        assert!(request.is_none());
+        assert_eq!("invalid", body["code"]);
+        assert_eq!(3, body["line"]);
+        assert!(
+            body["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("unable to parse 'cpu usage=': ")
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::unknown_path("/query?db=db", &[], 404)]
+    #[case::no_bucket("/api/v2/write?org=acme", &[], 400)]
+    #[case::no_db("/write", &[], 400)]
+    #[case::bad_precision("/write?db=db&precision=h", &[], 400)]
+    #[case::unknown_encoding("/write?db=db", &[("Content-Encoding", "br")], 415)]
+    fn bad_requests(
+        #[case] target: &str,
+        #[case] headers: &[(&str, &str)],
+        #[case] expected_status: u16,
+    ) {
+        let (status, body, request) = receive(target, headers, b"cpu usage=1");
+
+        assert_eq!(expected_status, status);
+        assert!(body.is_some());
+        assert!(request.is_none());
+    }
+
+    #[test]
+    fn wrong_method() {
+        let receiver = WriteReceiver::new(|_| Ok(()));
+
+        let response = receiver.handle("GET", "/write?db=db", [], "".as_bytes());
+
+        assert_eq!(405, response.status());
+    }
+
+    #[test]
+    fn body_too_large() {
+        let receiver = WriteReceiver::new(|_| Ok(())).with_max_body_size(8);
+
+        let response = receiver.handle("POST", "/write?db=db", [], "cpu usage=1".as_bytes());
+
+        assert_eq!(413, response.status());
+    }
+
+    #[rstest::rstest]
+    #[case::rejected(
+        InfluxLineError::WriteRejected {
+            status: 401,
+            body: r#"{"code":"unauthorized"}"#.into(),
+            lines: 0..1,
+        },
+        401
+    )]
+    #[case::failed(InfluxLineError::Failed, 500)]
+    fn callback_errors(#[case] error: InfluxLineError, #[case] expected_status: u16) {
+        let error = std::cell::RefCell::new(Some(error));
+        let receiver = WriteReceiver::new(|_| Err(error.take().unwrap()));
+
+        let response = receiver.handle("POST", "/write?db=db", [], "cpu usage=1".as_bytes());
+
+        assert_eq!(expected_status, response.status());
+        assert!(response.body().is_some());
+    }
+}
@@ -0,0 +1,2 @@
+#[cfg(feature = "parquet")]
+pub(crate) mod parquet;
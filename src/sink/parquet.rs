@@ -0,0 +1,683 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use parquet::basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType};
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DataType, DoubleType, Int64Type};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{SerializedColumnWriter, SerializedFileWriter};
+use parquet::schema::types::Type;
+
+use crate::{InfluxLine, InfluxLineError, InfluxValue, InfluxValueType, KeyName, MeasurementName};
+
+/// Collects Lines and archives them to Parquet files
+/// using a Hive-style layout:
+/// `<root>/measurement=<m>/date=<yyyy-mm-dd>/part-<N>.parquet`.
+///
+/// Lines are buffered per partition, i.e., per measurement and UTC date of the timestamp.
+/// A partition is written out as a new part file once it reaches either limit:
+///
+/// - [`Self::with_max_rows`] - the number of buffered Lines.
+/// - [`Self::with_max_bytes`] - the Line Protocol size of buffered Lines.
+///
+/// Column chunks are compressed with Snappy.
+/// The schema of every file is inferred from the buffered Lines:
+/// a required `time` column in nanoseconds, then one optional column per tag key,
+/// then one optional column per field key typed after its [`InfluxValueType`].
+/// Since a partition must keep a single type per field,
+/// Lines that would change a field type are rejected with
+/// [`InfluxLineError::FieldTypeConflict`].
+///
+/// Part files are written under a temporary name and renamed once complete,
+/// so a failed write leaves no truncated file behind and can be retried.
+///
+/// Remaining Lines are written on [`Self::flush`], [`Self::close`] or on drop.
+/// Flushing forgets the flushed partitions, so that a long-running sink
+/// does not keep one for every past day,
+/// and field types are only checked between flushes.
+/// Dropping the sink ignores errors, so prefer closing it explicitly.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let root = std::env::temp_dir().join("influx-line-parquet-doc");
+/// let mut sink = ParquetSink::new(&root).with_max_rows(10_000);
+///
+/// let line = InfluxLine::try_new("human", "age", 15)
+///     .map(|line| line.with_timestamp(1704067200000000000_i64))
+///     .unwrap();
+/// sink.write(&line).unwrap();
+///
+/// let files = sink.close().unwrap();
+/// assert!(files[0].ends_with("measurement=human/date=2024-01-01/part-0.parquet"));
+/// # std::fs::remove_dir_all(root).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ParquetSink {
+    root: PathBuf,
+    max_rows: usize,
+    max_bytes: usize,
+    partitions: BTreeMap<PartitionKey, Partition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct PartitionKey {
+    measurement: MeasurementName,
+    date: NaiveDate,
+}
+
+#[derive(Debug, Default)]
+struct Partition {
+    lines: Vec<InfluxLine>,
+    bytes: usize,
+    tags: BTreeSet<KeyName>,
+    fields: BTreeMap<KeyName, InfluxValueType>,
+    next_part: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Column<'a> {
+    Time,
+    Tag(&'a KeyName),
+    Field(&'a KeyName, InfluxValueType),
+}
+
+impl ParquetSink {
+    const DEFAULT_MAX_ROWS: usize = 1_000_000;
+    const DEFAULT_MAX_BYTES: usize = 128 * 1024 * 1024;
+    const TIME_COLUMN: &'static str = "time";
+
+    /// Creates a sink that writes partitions under the `root` directory.
+    pub fn new<P>(root: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            max_rows: Self::DEFAULT_MAX_ROWS,
+            max_bytes: Self::DEFAULT_MAX_BYTES,
+            partitions: BTreeMap::new(),
+        }
+    }
+
+    /// Limits the number of rows in a single part file.
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows.max(1);
+        self
+    }
+
+    /// Limits the size of a single part file,
+    /// measured by the Line Protocol representation of its Lines.
+    ///
+    /// The resulting file is usually smaller due to columnar encoding and compression.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes.max(1);
+        self
+    }
+
+    /// Buffers a Line in its partition.
+    ///
+    /// Returns the path of a part file if this Line made the partition roll over.
+    /// If writing that part fails, the Line is not kept, so it can be written again.
+    pub fn write(&mut self, line: &InfluxLine) -> Result<Option<PathBuf>, InfluxLineError> {
+        let timestamp = line.timestamp().ok_or(InfluxLineError::NoTimestamp)?;
+        let key = PartitionKey {
+            measurement: line.measurement().clone(),
+            date: DateTime::<Utc>::from(timestamp).date_naive(),
+        };
+
+        let partition = self.partitions.entry(key.clone()).or_default();
+        let added = partition.accept(line)?;
+
+        if partition.lines.len() >= self.max_rows || partition.bytes >= self.max_bytes {
+            return match partition.write_part(&self.root, &key) {
+                Ok(path) => Ok(Some(path)),
+                Err(error) => {
+                    partition.retract_last(added);
+                    Err(error)
+                }
+            };
+        }
+
+        Ok(None)
+    }
+
+    /// Writes all buffered Lines to new part files, returning their paths,
+    /// and forgets the partitions.
+    ///
+    /// Partitions that fail to be written are kept, along with the ones after them.
+    pub fn flush(&mut self) -> Result<Vec<PathBuf>, InfluxLineError> {
+        let mut paths = Vec::new();
+        let mut result = Ok(());
+        for (key, partition) in self.partitions.iter_mut() {
+            if !partition.lines.is_empty() {
+                match partition.write_part(&self.root, key) {
+                    Ok(path) => paths.push(path),
+                    Err(error) => {
+                        result = Err(error);
+                        break;
+                    }
+                }
+            }
+        }
+        self.partitions
+            .retain(|_, partition| !partition.lines.is_empty());
+        result.map(|()| paths)
+    }
+
+    /// Flushes the remaining Lines and closes the sink.
+    pub fn close(mut self) -> Result<Vec<PathBuf>, InfluxLineError> {
+        self.flush()
+    }
+}
+
+impl Drop for ParquetSink {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl PartitionKey {
+    fn directory(&self, root: &Path) -> PathBuf {
+        root.join(format!(
+            "measurement={}",
+            escape_path_segment(&self.measurement)
+        ))
+        .join(format!("date={}", self.date.format("%Y-%m-%d")))
+    }
+}
+
+impl Partition {
+    /// Buffers a Line, returning the keys it added to the schema.
+    fn accept(&mut self, line: &InfluxLine) -> Result<Vec<KeyName>, InfluxLineError> {
+        for (key, _) in line.tags() {
+            if key.as_str() == ParquetSink::TIME_COLUMN
+                || self.fields.contains_key(key)
+                || line.field(key).is_some()
+            {
+                return Err(InfluxLineError::KeyConflict);
+            }
+        }
+        for (key, value) in line.fields() {
+            if key.as_str() == ParquetSink::TIME_COLUMN || self.tags.contains(key) {
+                return Err(InfluxLineError::KeyConflict);
+            }
+            match self.fields.get(key) {
+                Some(known) if *known != value.value_type() => {
+                    return Err(InfluxLineError::FieldTypeConflict);
+                }
+                _ => (),
+            }
+        }
+
+        let mut added = Vec::new();
+        for (key, _) in line.tags() {
+            if self.tags.insert(key.clone()) {
+                added.push(key.clone());
+            }
+        }
+        for (key, value) in line.fields() {
+            if self
+                .fields
+                .insert(key.clone(), value.value_type())
+                .is_none()
+            {
+                added.push(key.clone());
+            }
+        }
+        self.bytes += line.to_string().len() + 1;
+        self.lines.push(line.clone());
+
+        Ok(added)
+    }
+
+    /// Takes back the last buffered Line along with the keys it added to the schema.
+    fn retract_last(&mut self, added: Vec<KeyName>) {
+        if let Some(line) = self.lines.pop() {
+            self.bytes -= line.to_string().len() + 1;
+        }
+        for key in added {
+            self.tags.remove(&key);
+            self.fields.remove(&key);
+        }
+    }
+
+    fn write_part(&mut self, root: &Path, key: &PartitionKey) -> Result<PathBuf, InfluxLineError> {
+        let directory = key.directory(root);
+        fs::create_dir_all(&directory)?;
+
+        let part = match self.next_part {
+            Some(part) => part,
+            None => next_part_number(&directory)?,
+        };
+        let path = directory.join(format!("part-{}.parquet", part));
+        let temporary = directory.join(format!("part-{}.parquet.tmp", part));
+
+        let written = File::create(&temporary)
+            .map_err(InfluxLineError::from)
+            .and_then(|file| Ok(self.write_lines(file)?))
+            .and_then(|()| Ok(fs::rename(&temporary, &path)?));
+        if let Err(error) = written {
+            // The file is not there if creating it failed.
+            let _ = fs::remove_file(&temporary);
+            return Err(error);
+        }
+
+        self.next_part = Some(part + 1);
+        self.lines.clear();
+        self.bytes = 0;
+
+        Ok(path)
+    }
+
+    fn write_lines(&self, file: File) -> Result<(), ParquetError> {
+        let columns = self.columns();
+        let schema = Type::group_type_builder("schema")
+            .with_fields(
+                columns
+                    .iter()
+                    .map(|column| column.parquet_type().map(Arc::new))
+                    .collect::<Result<_, _>>()?,
+            )
+            .build()?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+
+        let mut writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties))?;
+        let mut row_group = writer.next_row_group()?;
+        let mut columns = columns.into_iter();
+        while let Some(mut column_writer) = row_group.next_column()? {
+            let column = columns
+                .next()
+                .ok_or_else(|| ParquetError::General("Unexpected column".into()))?;
+            column.write(&mut column_writer, &self.lines)?;
+            column_writer.close()?;
+        }
+        row_group.close()?;
+        writer.close()?;
+
+        Ok(())
+    }
+
+    /// Only keys present in the buffered Lines make it into the file schema.
+    fn columns(&self) -> Vec<Column<'_>> {
+        let tags: BTreeSet<&KeyName> = self
+            .lines
+            .iter()
+            .flat_map(|line| line.tags().map(|(key, _)| key))
+            .collect();
+        let fields: BTreeSet<&KeyName> = self
+            .lines
+            .iter()
+            .flat_map(|line| line.fields().map(|(key, _)| key))
+            .collect();
+
+        std::iter::once(Column::Time)
+            .chain(tags.into_iter().map(Column::Tag))
+            .chain(
+                fields
+                    .into_iter()
+                    .map(|key| Column::Field(key, self.fields[key])),
+            )
+            .collect()
+    }
+}
+
+impl Column<'_> {
+    fn name(&self) -> &str {
+        match self {
+            Column::Time => ParquetSink::TIME_COLUMN,
+            Column::Tag(key) => key.as_str(),
+            Column::Field(key, _) => key.as_str(),
+        }
+    }
+
+    fn parquet_type(&self) -> Result<Type, ParquetError> {
+        let (physical, logical, repetition) = match self {
+            Column::Time => (
+                PhysicalType::INT64,
+                Some(LogicalType::timestamp(true, TimeUnit::NANOS)),
+                Repetition::REQUIRED,
+            ),
+            Column::Tag(_) => (
+                PhysicalType::BYTE_ARRAY,
+                Some(LogicalType::String),
+                Repetition::OPTIONAL,
+            ),
+            Column::Field(_, value_type) => {
+                let (physical, logical) = match value_type {
+                    InfluxValueType::Float => (PhysicalType::DOUBLE, None),
+                    InfluxValueType::Integer => (PhysicalType::INT64, None),
+                    InfluxValueType::UInteger => {
+                        (PhysicalType::INT64, Some(LogicalType::integer(64, false)))
+                    }
+                    InfluxValueType::Boolean => (PhysicalType::BOOLEAN, None),
+                    InfluxValueType::String => {
+                        (PhysicalType::BYTE_ARRAY, Some(LogicalType::String))
+                    }
+                };
+                (physical, logical, Repetition::OPTIONAL)
+            }
+        };
+
+        Type::primitive_type_builder(self.name(), physical)
+            .with_logical_type(logical)
+            .with_repetition(repetition)
+            .build()
+    }
+
+    fn write(
+        &self,
+        writer: &mut SerializedColumnWriter<'_>,
+        lines: &[InfluxLine],
+    ) -> Result<(), ParquetError> {
+        match *self {
+            Column::Time => {
+                let values: Vec<i64> = lines
+                    .iter()
+                    .map(|line| line.timestamp().map(i64::from).unwrap_or_default())
+                    .collect();
+                writer
+                    .typed::<Int64Type>()
+                    .write_batch(&values, None, None)?;
+            }
+            Column::Tag(key) => write_optional::<ByteArrayType>(writer, lines, |line| {
+                line.tag(key).map(|value| ByteArray::from(value.as_str()))
+            })?,
+            Column::Field(key, InfluxValueType::Float) => {
+                write_optional::<DoubleType>(writer, lines, |line| match line.field(key) {
+                    Some(InfluxValue::Float(value)) => Some(*value),
+                    _ => None,
+                })?
+            }
+            Column::Field(key, InfluxValueType::Integer) => {
+                write_optional::<Int64Type>(writer, lines, |line| match line.field(key) {
+                    Some(InfluxValue::Integer(value)) => Some(i64::from(*value)),
+                    _ => None,
+                })?
+            }
+            Column::Field(key, InfluxValueType::UInteger) => {
+                // Unsigned values are stored as INT64 bits annotated with UINT_64.
+                write_optional::<Int64Type>(writer, lines, |line| match line.field(key) {
+                    Some(InfluxValue::UInteger(value)) => Some(u64::from(*value) as i64),
+                    _ => None,
+                })?
+            }
+            Column::Field(key, InfluxValueType::Boolean) => {
+                write_optional::<BoolType>(writer, lines, |line| match line.field(key) {
+                    Some(InfluxValue::Boolean(value)) => Some(bool::from(*value)),
+                    _ => None,
+                })?
+            }
+            Column::Field(key, InfluxValueType::String) => {
+                write_optional::<ByteArrayType>(writer, lines, |line| match line.field(key) {
+                    Some(InfluxValue::String(value)) => Some(ByteArray::from(value.as_str())),
+                    _ => None,
+                })?
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_optional<T>(
+    writer: &mut SerializedColumnWriter<'_>,
+    lines: &[InfluxLine],
+    extract: impl Fn(&InfluxLine) -> Option<T::T>,
+) -> Result<(), ParquetError>
+where
+    T: DataType,
+{
+    let mut values = Vec::with_capacity(lines.len());
+    let mut definition_levels = Vec::with_capacity(lines.len());
+    for line in lines {
+        match extract(line) {
+            Some(value) => {
+                values.push(value);
+                definition_levels.push(1);
+            }
+            None => definition_levels.push(0),
+        }
+    }
+
+    writer
+        .typed::<T>()
+        .write_batch(&values, Some(&definition_levels), None)?;
+    Ok(())
+}
+
+/// Looks for existing `part-<N>.parquet` files,
+/// so that a restarted sink never overwrites earlier archives.
+fn next_part_number(directory: &Path) -> Result<usize, std::io::Error> {
+    let mut next = 0;
+    for entry in fs::read_dir(directory)? {
+        let name = entry?.file_name();
+        let number = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("part-"))
+            .and_then(|name| name.strip_suffix(".parquet"))
+            .and_then(|number| number.parse::<usize>().ok());
+        if let Some(number) = number {
+            next = next.max(number + 1);
+        }
+    }
+    Ok(next)
+}
+
+/// Percent-encodes characters that cannot be a part of a single path segment.
+fn escape_path_segment(segment: &str) -> String {
+    let mut escaped = String::with_capacity(segment.len());
+    for character in segment.chars() {
+        if matches!(character, '/' | '\\' | '%') || character.is_control() {
+            let mut buffer = [0; 4];
+            for byte in character.encode_utf8(&mut buffer).bytes() {
+                escaped.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            escaped.push(character);
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::path::Path;
+
+    use parquet::basic::Type as PhysicalType;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use crate::{InfluxLine, InfluxLineError, Timestamp};
+
+    use super::{ParquetSink, escape_path_segment};
+
+    const JAN_1: i64 = 1704067200000000000;
+    const JAN_2: i64 = 1704153600000000000;
+
+    fn line(measurement: &str, timestamp: i64) -> InfluxLine {
+        InfluxLine::try_new(measurement, "value", 1.5)
+            .and_then(|line| line.try_with_tag("host", "a"))
+            .map(|line| line.with_timestamp(Timestamp::from(timestamp)))
+            .unwrap()
+    }
+
+    fn rows(path: &Path) -> i64 {
+        let reader = SerializedFileReader::new(File::open(path).unwrap()).unwrap();
+        reader.metadata().file_metadata().num_rows()
+    }
+
+    #[test]
+    fn partitions_by_measurement_and_date() {
+        let root = tempfile::tempdir().unwrap();
+        let mut sink = ParquetSink::new(root.path());
+
+        sink.write(&line("cpu", JAN_1)).unwrap();
+        sink.write(&line("cpu", JAN_1 + 1)).unwrap();
+        sink.write(&line("cpu", JAN_2)).unwrap();
+        sink.write(&line("disk io", JAN_1)).unwrap();
+        let files = sink.close().unwrap();
+
+        let expected = [
+            ("measurement=cpu/date=2024-01-01/part-0.parquet", 2),
+            ("measurement=cpu/date=2024-01-02/part-0.parquet", 1),
+            ("measurement=disk io/date=2024-01-01/part-0.parquet", 1),
+        ];
+        assert_eq!(expected.len(), files.len());
+        for (path, expected_rows) in expected {
+            assert_eq!(expected_rows, rows(&root.path().join(path)));
+        }
+    }
+
+    #[test]
+    fn rolls_over_by_row_count() {
+        let root = tempfile::tempdir().unwrap();
+        let mut sink = ParquetSink::new(root.path()).with_max_rows(2);
+
+        let rolled: Vec<_> = (0..5)
+            .filter_map(|offset| sink.write(&line("cpu", JAN_1 + offset)).unwrap())
+            .collect();
+        let flushed = sink.close().unwrap();
+
+        assert_eq!(2, rolled.len());
+        assert_eq!(1, flushed.len());
+        assert_eq!(2, rows(&rolled[0]));
+        assert_eq!(2, rows(&rolled[1]));
+        assert_eq!(1, rows(&flushed[0]));
+        assert!(flushed[0].ends_with("part-2.parquet"));
+    }
+
+    #[test]
+    fn rolls_over_by_size() {
+        let root = tempfile::tempdir().unwrap();
+        let mut sink = ParquetSink::new(root.path()).with_max_bytes(1);
+
+        let rolled = sink.write(&line("cpu", JAN_1)).unwrap();
+
+        assert_eq!(1, rows(&rolled.unwrap()));
+    }
+
+    #[test]
+    fn continues_numbering_after_restart() {
+        let root = tempfile::tempdir().unwrap();
+        ParquetSink::new(root.path())
+            .write(&line("cpu", JAN_1))
+            .unwrap();
+
+        let mut sink = ParquetSink::new(root.path());
+        sink.write(&line("cpu", JAN_1)).unwrap();
+        let files = sink.close().unwrap();
+
+        assert!(files[0].ends_with("part-1.parquet"));
+    }
+
+    #[test]
+    fn forgets_flushed_partitions() {
+        let root = tempfile::tempdir().unwrap();
+        let mut sink = ParquetSink::new(root.path());
+
+        sink.write(&line("cpu", JAN_1)).unwrap();
+        sink.write(&line("mem", JAN_2)).unwrap();
+        sink.flush().unwrap();
+        sink.write(&line("cpu", JAN_1)).unwrap();
+
+        assert_eq!(1, sink.partitions.len());
+        let files = sink.close().unwrap();
+        assert!(files[0].ends_with("measurement=cpu/date=2024-01-01/part-1.parquet"));
+    }
+
+    #[test]
+    fn retries_failed_parts() {
+        let root = tempfile::tempdir().unwrap();
+        let directory = root.path().join("measurement=cpu/date=2024-01-01");
+        let mut sink = ParquetSink::new(root.path()).with_max_rows(1);
+        sink.write(&line("cpu", JAN_1)).unwrap();
+        // A directory in place of the next part makes renaming fail.
+        std::fs::create_dir(directory.join("part-1.parquet")).unwrap();
+        std::fs::write(directory.join("part-1.parquet").join("blocker"), "").unwrap();
+
+        assert!(sink.write(&line("cpu", JAN_1)).is_err());
+        assert!(!directory.join("part-1.parquet.tmp").exists());
+
+        std::fs::remove_dir_all(directory.join("part-1.parquet")).unwrap();
+        let retried = sink.write(&line("cpu", JAN_1)).unwrap().unwrap();
+        assert!(retried.ends_with("part-1.parquet"));
+        assert_eq!(1, rows(&retried));
+        assert!(sink.close().unwrap().is_empty());
+    }
+
+    #[test]
+    fn infers_schema_from_field_types() {
+        let root = tempfile::tempdir().unwrap();
+        let mut sink = ParquetSink::new(root.path());
+        let line = InfluxLine::try_new("human", "age", 25)
+            .and_then(|line| line.try_with_field("height", 1.82))
+            .and_then(|line| line.try_with_field("steps", 100_u64))
+            .and_then(|line| line.try_with_field("alive", true))
+            .and_then(|line| line.try_with_field("name", "Egorka"))
+            .and_then(|line| line.try_with_tag("city", "omsk"))
+            .map(|line| line.with_timestamp(Timestamp::from(JAN_1)))
+            .unwrap();
+
+        sink.write(&line).unwrap();
+        let files = sink.close().unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&files[0]).unwrap()).unwrap();
+        let schema = reader.metadata().file_metadata().schema_descr_ptr();
+        let actual: Vec<_> = schema
+            .columns()
+            .iter()
+            .map(|column| (column.name().to_owned(), column.physical_type()))
+            .collect();
+        let expected = vec![
+            ("time".to_owned(), PhysicalType::INT64),
+            ("city".to_owned(), PhysicalType::BYTE_ARRAY),
+            ("age".to_owned(), PhysicalType::INT64),
+            ("alive".to_owned(), PhysicalType::BOOLEAN),
+            ("height".to_owned(), PhysicalType::DOUBLE),
+            ("name".to_owned(), PhysicalType::BYTE_ARRAY),
+            ("steps".to_owned(), PhysicalType::INT64),
+        ];
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn rejects_field_type_conflict() {
+        let root = tempfile::tempdir().unwrap();
+        let mut sink = ParquetSink::new(root.path());
+        let integer = InfluxLine::try_new("cpu", "value", 1)
+            .map(|line| line.with_timestamp(Timestamp::from(JAN_1)))
+            .unwrap();
+
+        sink.write(&line("cpu", JAN_1)).unwrap();
+        let error = sink.write(&integer).unwrap_err();
+
+        assert!(matches!(error, InfluxLineError::FieldTypeConflict));
+    }
+
+    #[test]
+    fn rejects_lines_without_timestamp() {
+        let root = tempfile::tempdir().unwrap();
+        let mut sink = ParquetSink::new(root.path());
+
+        let error = sink
+            .write(&InfluxLine::try_new("cpu", "value", 1).unwrap())
+            .unwrap_err();
+
+        assert!(matches!(error, InfluxLineError::NoTimestamp));
+    }
+
+    #[rstest::rstest]
+    #[case::plain("cpu", "cpu")]
+    #[case::slashes("a/b\\c", "a%2Fb%5Cc")]
+    #[case::percent("100%", "100%25")]
+    fn path_segment_escaping(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, escape_path_segment(input));
+    }
+}
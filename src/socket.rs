@@ -0,0 +1,635 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::{InfluxLine, InfluxLineError};
+
+/// What [`SocketWriter::write`] does when the queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Discards the oldest queued Line to make room for the new one.
+    #[default]
+    DropOldest,
+    /// Discards the new Line.
+    DropNewest,
+    /// Waits until the queue has room again.
+    Block,
+}
+
+/// Settings of a [`SocketWriter`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use influx_line::*;
+///
+/// let config = SocketConfig::new("tcp://localhost:8094")
+///     .with_capacity(100_000)
+///     .with_overflow(OverflowPolicy::Block)
+///     .with_backoff(Duration::from_millis(100), Duration::from_secs(10));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SocketConfig {
+    address: String,
+    capacity: usize,
+    overflow: OverflowPolicy,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+/// Streams newline-terminated Lines to a `tcp://` or `unix://` socket,
+/// e.g. the one of Telegraf's `socket_listener`.
+///
+/// Lines are queued and written by a background thread,
+/// which reconnects with exponential backoff whenever the connection drops.
+/// The queue is bounded, see [`OverflowPolicy`] for what happens when it is full.
+///
+/// Lines written right before the peer drops the connection may be lost,
+/// since stream sockets do not acknowledge what the peer has actually read.
+/// Lines that did not make it into the socket are written again after reconnecting,
+/// and failed writes back off like failed connections until a write succeeds.
+/// The peer may thus receive the beginning of the Line that was cut off
+/// before receiving it in full over the new connection.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::io::{BufRead, BufReader};
+/// use std::net::TcpListener;
+/// use influx_line::*;
+///
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let address = format!("tcp://{}", listener.local_addr().unwrap());
+/// let writer = SocketWriter::new(SocketConfig::new(address)).unwrap();
+///
+/// writer.write(&InfluxLine::try_new("cpu", "usage", 0.5).unwrap());
+/// writer.close();
+///
+/// let (stream, _) = listener.accept().unwrap();
+/// let received: Vec<String> = BufReader::new(stream).lines().map(Result::unwrap).collect();
+/// assert_eq!(vec!["cpu usage=0.5"], received);
+/// ```
+#[derive(Debug)]
+pub struct SocketWriter {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+enum SocketAddress {
+    Tcp(String),
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Unix(PathBuf),
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    /// Signals the worker about new Lines and shutdown.
+    not_empty: Condvar,
+    /// Signals blocked writers about freed room.
+    not_full: Condvar,
+    capacity: usize,
+    overflow: OverflowPolicy,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+}
+
+#[derive(Debug)]
+struct State {
+    queue: VecDeque<String>,
+    closed: bool,
+}
+
+impl SocketConfig {
+    const DEFAULT_CAPACITY: usize = 10_000;
+    const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+    const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+    /// Creates a configuration for `tcp://host:port` or `unix:///path/to/socket`.
+    pub fn new<S>(address: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            address: address.into(),
+            capacity: Self::DEFAULT_CAPACITY,
+            overflow: OverflowPolicy::default(),
+            initial_backoff: Self::DEFAULT_INITIAL_BACKOFF,
+            max_backoff: Self::DEFAULT_MAX_BACKOFF,
+        }
+    }
+
+    /// Limits the number of queued Lines, 10 000 by default.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Sets the delay before the first reconnection attempt
+    /// and the upper bound for the following ones.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+impl FromStr for SocketAddress {
+    type Err = InfluxLineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(address) = s.strip_prefix("tcp://") {
+            if address.is_empty() {
+                return Err(InfluxLineError::BadUrl);
+            }
+            return Ok(Self::Tcp(address.to_owned()));
+        }
+
+        #[cfg(unix)]
+        if let Some(path) = s.strip_prefix("unix://") {
+            if path.is_empty() {
+                return Err(InfluxLineError::BadUrl);
+            }
+            return Ok(Self::Unix(PathBuf::from(path)));
+        }
+
+        Err(InfluxLineError::BadUrl)
+    }
+}
+
+impl SocketAddress {
+    fn connect(&self) -> std::io::Result<Box<dyn Write + Send>> {
+        match self {
+            Self::Tcp(address) => Ok(Box::new(TcpStream::connect(address)?)),
+            #[cfg(unix)]
+            Self::Unix(path) => Ok(Box::new(UnixStream::connect(path)?)),
+            #[cfg(not(unix))]
+            Self::Unix(_) => Err(std::io::ErrorKind::Unsupported.into()),
+        }
+    }
+}
+
+impl SocketWriter {
+    /// Validates the address and starts the background thread.
+    ///
+    /// Does not wait for the connection, so it succeeds even if the peer is down.
+    pub fn new(config: SocketConfig) -> Result<Self, InfluxLineError> {
+        let address = SocketAddress::from_str(&config.address)?;
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                closed: false,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity: config.capacity,
+            overflow: config.overflow,
+            sent: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        });
+
+        let worker = {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name("influx-line-socket".to_owned())
+                .spawn(move || run(&address, &config, &shared))?
+        };
+
+        Ok(Self {
+            shared,
+            worker: Some(worker),
+        })
+    }
+
+    /// Queues a Line, appending a newline to it if it has none.
+    pub fn write(&self, line: &InfluxLine) {
+        let mut encoded = String::new();
+        // Writing to a String never fails.
+        let _ = write!(encoded, "{}", line);
+        if !encoded.ends_with('\n') {
+            encoded.push('\n');
+        }
+
+        let capacity = self.shared.capacity;
+        let mut state = self.shared.lock();
+        if state.queue.len() >= capacity {
+            match self.shared.overflow {
+                OverflowPolicy::DropOldest => {
+                    state.queue.pop_front();
+                    self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowPolicy::DropNewest => {
+                    self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                OverflowPolicy::Block => {
+                    while state.queue.len() >= capacity {
+                        state = self
+                            .shared
+                            .not_full
+                            .wait(state)
+                            .unwrap_or_else(PoisonError::into_inner);
+                    }
+                }
+            }
+        }
+
+        state.queue.push_back(encoded);
+        self.shared.not_empty.notify_one();
+    }
+
+    /// The number of Lines successfully written to the socket.
+    pub fn sent(&self) -> u64 {
+        self.shared.sent.load(Ordering::Relaxed)
+    }
+
+    /// The number of Lines discarded due to overflow or shutdown.
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// The number of Lines waiting to be written.
+    pub fn queued(&self) -> usize {
+        self.shared.lock().queue.len()
+    }
+
+    /// Writes the queued Lines and stops the background thread.
+    ///
+    /// Lines still queued while the peer is unreachable are counted as dropped.
+    /// Dropping the writer closes it as well.
+    pub fn close(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.not_empty.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for SocketWriter {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Takes the next batch, waiting for one.
+    /// Returns nothing once the writer is closed and the queue is empty.
+    fn next_batch(&self) -> Option<Vec<String>> {
+        let state = self.lock();
+        let mut state = self
+            .not_empty
+            .wait_while(state, |state| state.queue.is_empty() && !state.closed)
+            .unwrap_or_else(PoisonError::into_inner);
+        if state.queue.is_empty() {
+            return None;
+        }
+
+        let count = state.queue.len().min(BATCH_SIZE);
+        let batch = state.queue.drain(..count).collect();
+        self.not_full.notify_all();
+        Some(batch)
+    }
+
+    /// Puts an undelivered batch back in front of the queue,
+    /// applying the overflow policy to Lines queued in the meantime.
+    fn requeue(&self, batch: Vec<String>) {
+        let mut state = self.lock();
+        for line in batch.into_iter().rev() {
+            state.queue.push_front(line);
+        }
+
+        let excess = state.queue.len().saturating_sub(self.capacity);
+        let dropped = match self.overflow {
+            OverflowPolicy::DropOldest => state.queue.drain(..excess).count(),
+            OverflowPolicy::DropNewest => {
+                let keep = state.queue.len() - excess;
+                state.queue.drain(keep..).count()
+            }
+            // Writers are blocked until the queue shrinks anyway.
+            OverflowPolicy::Block => 0,
+        };
+        self.dropped.fetch_add(dropped as u64, Ordering::Relaxed);
+    }
+
+    /// Waits before the next connection attempt.
+    /// Returns `false` if the writer is closed, discarding the queue.
+    fn backoff(&self, delay: Duration) -> bool {
+        let state = self.lock();
+        let (mut state, _) = self
+            .not_empty
+            .wait_timeout_while(state, delay, |state| !state.closed)
+            .unwrap_or_else(PoisonError::into_inner);
+        if !state.closed {
+            return true;
+        }
+
+        let dropped = state.queue.drain(..).count();
+        self.dropped.fetch_add(dropped as u64, Ordering::Relaxed);
+        self.not_full.notify_all();
+        false
+    }
+}
+
+/// Limits the number of Lines taken from the queue at once.
+const BATCH_SIZE: usize = 512;
+
+fn run(address: &SocketAddress, config: &SocketConfig, shared: &Shared) {
+    let mut connection: Option<Box<dyn Write + Send>> = None;
+    // Reset by successful writes only, so that a peer accepting connections
+    // and dropping them right away is not reconnected to in a busy loop.
+    let mut attempt = 0;
+
+    while let Some(mut batch) = shared.next_batch() {
+        let stream = match &mut connection {
+            Some(stream) => stream,
+            None => match address.connect() {
+                Ok(stream) => connection.insert(stream),
+                Err(_) => {
+                    shared.requeue(batch);
+                    if !shared.backoff(config.backoff(attempt)) {
+                        return;
+                    }
+                    attempt = attempt.saturating_add(1);
+                    continue;
+                }
+            },
+        };
+
+        let (delivered, written) = write_batch(stream, &batch);
+        shared.sent.fetch_add(delivered as u64, Ordering::Relaxed);
+        match written {
+            Ok(()) => attempt = 0,
+            Err(_) => {
+                connection = None;
+                shared.requeue(batch.split_off(delivered));
+                if !shared.backoff(config.backoff(attempt)) {
+                    return;
+                }
+                attempt = attempt.saturating_add(1);
+            }
+        }
+    }
+}
+
+/// Writes the Lines as a single payload,
+/// returning how many of them were written in full along with the outcome.
+fn write_batch(stream: &mut impl Write, batch: &[String]) -> (usize, io::Result<()>) {
+    let payload = batch.concat();
+    let mut written = 0;
+    let result = loop {
+        if written == payload.len() {
+            break stream.flush();
+        }
+        match stream.write(&payload.as_bytes()[written..]) {
+            Ok(0) => break Err(io::ErrorKind::WriteZero.into()),
+            Ok(count) => written += count,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => (),
+            Err(error) => break Err(error),
+        }
+    };
+
+    let delivered = batch
+        .iter()
+        .scan(0, |end, line| {
+            *end += line.len();
+            Some(*end)
+        })
+        .take_while(|end| *end <= written)
+        .count();
+    (delivered, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    use crate::{InfluxLine, InfluxLineError, OverflowPolicy, SocketConfig, SocketWriter};
+
+    use super::write_batch;
+
+    /// Accepts a number of bytes, in chunks of up to three, and fails afterwards.
+    struct Failing {
+        accepted: Vec<u8>,
+        limit: usize,
+    }
+
+    impl std::io::Write for Failing {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let count = buf.len().min(3).min(self.limit - self.accepted.len());
+            if count == 0 {
+                return Err(std::io::ErrorKind::BrokenPipe.into());
+            }
+            self.accepted.extend_from_slice(&buf[..count]);
+            Ok(count)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn line(index: u64) -> InfluxLine {
+        InfluxLine::try_new("cpu", "index", index).unwrap()
+    }
+
+    fn read_lines(listener: &TcpListener) -> Vec<String> {
+        let (stream, _) = listener.accept().unwrap();
+        BufReader::new(stream).lines().map(Result::unwrap).collect()
+    }
+
+    /// Reserves a port that nobody listens on for a while.
+    fn unused_address() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    #[rstest::rstest]
+    #[case::no_scheme("localhost:8094")]
+    #[case::udp("udp://localhost:8094")]
+    #[case::empty_tcp("tcp://")]
+    fn bad_addresses(#[case] address: &str) {
+        let error = SocketWriter::new(SocketConfig::new(address)).unwrap_err();
+
+        assert!(matches!(error, InfluxLineError::BadUrl));
+    }
+
+    #[test]
+    fn writes_newline_terminated_lines() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("tcp://{}", listener.local_addr().unwrap());
+        let writer = SocketWriter::new(SocketConfig::new(address)).unwrap();
+
+        writer.write(&line(1));
+        writer.write(&line(2).add_newline());
+        writer.close();
+
+        assert_eq!(vec!["cpu index=1u", "cpu index=2u"], read_lines(&listener));
+    }
+
+    #[test]
+    fn reconnects_once_peer_is_up() {
+        let address = unused_address();
+        let writer = SocketWriter::new(
+            SocketConfig::new(format!("tcp://{}", address))
+                .with_backoff(Duration::from_millis(5), Duration::from_millis(20)),
+        )
+        .unwrap();
+
+        writer.write(&line(1));
+        std::thread::sleep(Duration::from_millis(50));
+        let listener = TcpListener::bind(&address).unwrap();
+        writer.write(&line(2));
+        while writer.sent() < 2 {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        writer.close();
+
+        assert_eq!(vec!["cpu index=1u", "cpu index=2u"], read_lines(&listener));
+    }
+
+    #[rstest::rstest]
+    #[case::drop_oldest(OverflowPolicy::DropOldest, vec!["cpu index=2u", "cpu index=3u"])]
+    #[case::drop_newest(OverflowPolicy::DropNewest, vec!["cpu index=1u", "cpu index=2u"])]
+    fn overflow_policies(#[case] overflow: OverflowPolicy, #[case] expected: Vec<&str>) {
+        let address = unused_address();
+        let writer = SocketWriter::new(
+            SocketConfig::new(format!("tcp://{}", address))
+                .with_capacity(2)
+                .with_overflow(overflow)
+                .with_backoff(Duration::from_millis(5), Duration::from_millis(5)),
+        )
+        .unwrap();
+
+        for index in 1..=3 {
+            writer.write(&line(index));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(1, writer.dropped());
+
+        let listener = TcpListener::bind(&address).unwrap();
+        while writer.sent() < 2 {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        writer.close();
+
+        assert_eq!(expected, read_lines(&listener));
+    }
+
+    #[rstest::rstest]
+    #[case::nothing(0, 0)]
+    #[case::within_first(3, 0)]
+    #[case::first(4, 1)]
+    #[case::within_second(7, 1)]
+    #[case::everything(12, 3)]
+    fn counts_lines_written_in_full(#[case] limit: usize, #[case] expected: usize) {
+        let batch = vec!["a=1\n".to_owned(), "b=2\n".to_owned(), "c=3\n".to_owned()];
+        let mut stream = Failing {
+            accepted: Vec::new(),
+            limit,
+        };
+
+        let (delivered, written) = write_batch(&mut stream, &batch);
+
+        assert_eq!(expected, delivered);
+        assert_eq!(limit == 12, written.is_ok());
+    }
+
+    #[test]
+    fn backs_off_after_failed_writes() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("tcp://{}", listener.local_addr().unwrap());
+        let accepted = std::thread::spawn(move || {
+            listener.set_nonblocking(true).unwrap();
+            let mut accepted = 0;
+            let start = std::time::Instant::now();
+            while start.elapsed() < Duration::from_millis(300) {
+                match listener.accept() {
+                    // Drops the connection right away, resetting it on the next write.
+                    Ok(_) => accepted += 1,
+                    Err(_) => std::thread::sleep(Duration::from_millis(1)),
+                }
+            }
+            accepted
+        });
+        let writer = SocketWriter::new(
+            SocketConfig::new(address)
+                .with_backoff(Duration::from_millis(50), Duration::from_millis(50)),
+        )
+        .unwrap();
+
+        for index in 0..1000 {
+            writer.write(&line(index));
+            std::thread::sleep(Duration::from_micros(200));
+        }
+        let accepted = accepted.join().unwrap();
+        drop(writer);
+
+        assert!(accepted <= 10, "accepted {} connections", accepted);
+    }
+
+    #[test]
+    fn drops_queue_on_close_while_disconnected() {
+        let writer =
+            SocketWriter::new(SocketConfig::new(format!("tcp://{}", unused_address()))).unwrap();
+
+        writer.write(&line(1));
+        writer.write(&line(2));
+        let dropped = {
+            let shared = writer.shared.clone();
+            writer.close();
+            shared.dropped.load(std::sync::atomic::Ordering::Relaxed)
+        };
+
+        assert_eq!(2, dropped);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_to_unix_socket() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("telegraf.sock");
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let writer =
+            SocketWriter::new(SocketConfig::new(format!("unix://{}", path.display()))).unwrap();
+
+        writer.write(&line(1));
+        writer.close();
+
+        let (stream, _) = listener.accept().unwrap();
+        let received: Vec<String> = BufReader::new(stream).lines().map(Result::unwrap).collect();
+        assert_eq!(vec!["cpu index=1u"], received);
+    }
+}
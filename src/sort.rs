@@ -0,0 +1,591 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fmt::Debug;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+use crate::{Clock, InfluxLine, InfluxLineError, SystemClock, Timestamp};
+
+/// Sorts Lines by series, i.e. the measurement with tags ordered by key,
+/// and then by timestamp, with Lines without one first.
+///
+/// This is the order InfluxDB ingests fastest when backfilling.
+/// Lines of the same series and timestamp keep their relative order,
+/// so that the last one still wins on write.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let input = "\
+/// cpu,zone=b,host=a usage=0.3 20
+/// mem,host=a used=1i 10
+/// cpu,host=a,zone=b usage=0.1 10
+/// ";
+///
+/// let lines = input.lines().map(|line| line.parse::<InfluxLine>());
+/// let mut output = Vec::new();
+/// LineSorter::new().with_chunk_lines(2).sort_external(lines, &mut output).unwrap();
+///
+/// assert_eq!("\
+/// cpu,host=a,zone=b usage=0.1 10
+/// cpu,zone=b,host=a usage=0.3 20
+/// mem,host=a used=1i 10
+/// ", String::from_utf8(output).unwrap());
+/// ```
+#[derive(Clone)]
+pub struct LineSorter {
+    chunk_lines: usize,
+    fan_in: usize,
+    directory: Option<PathBuf>,
+    /// Tells the time that names spill directories apart.
+    clock: Arc<dyn Clock>,
+}
+
+/// Merges already sorted streams of Lines into one stream in the order of [`LineSorter`],
+/// e.g. exports of many shards.
+///
+/// Lines of equal order are taken from the sources in the order they were given.
+/// Errors of the sources are passed through,
+/// and a Line that is out of order within its source
+/// is replaced with [`InfluxLineError::UnsortedInput`].
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let first = ["cpu usage=0.1 10", "cpu usage=0.3 30"];
+/// let second = ["cpu usage=0.2 20", "mem used=1i 10"];
+///
+/// let merged: Vec<String> = LineMerger::new([first, second].map(|lines| {
+///     lines.into_iter().map(|line| line.parse::<InfluxLine>())
+/// }))
+/// .map(|line| line.unwrap().to_string())
+/// .collect();
+///
+/// assert_eq!(
+///     vec!["cpu usage=0.1 10", "cpu usage=0.2 20", "cpu usage=0.3 30", "mem used=1i 10"],
+///     merged
+/// );
+/// ```
+pub struct LineMerger<I> {
+    sources: Vec<Source<I>>,
+    heads: BinaryHeap<Reverse<Head>>,
+    /// Sources whose next Line is not in the heap yet.
+    pending: Vec<usize>,
+}
+
+/// The position of a Line in the sort order.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SortKey {
+    measurement: String,
+    tags: Vec<(String, String)>,
+    timestamp: Option<Timestamp>,
+}
+
+struct Source<I> {
+    lines: I,
+    /// The number of Lines taken so far, to report out of order ones.
+    taken: usize,
+    last: Option<SortKey>,
+}
+
+/// The next Line of a source, ordered by its key and then by the source.
+struct Head {
+    key: SortKey,
+    source: usize,
+    line: InfluxLine,
+}
+
+/// A directory of spilled chunks that is removed once sorting is done.
+struct SpillDirectory {
+    path: PathBuf,
+    /// The number of files named so far.
+    files: usize,
+}
+
+impl LineSorter {
+    pub const DEFAULT_CHUNK_LINES: usize = 100_000;
+    pub const DEFAULT_FAN_IN: usize = 64;
+
+    /// Creates a sorter that holds up to [`Self::DEFAULT_CHUNK_LINES`] Lines in memory,
+    /// merges up to [`Self::DEFAULT_FAN_IN`] chunks at once,
+    /// and spills to the system temporary directory.
+    pub fn new() -> Self {
+        Self {
+            chunk_lines: Self::DEFAULT_CHUNK_LINES,
+            fan_in: Self::DEFAULT_FAN_IN,
+            directory: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Limits the number of Lines held in memory, at least one.
+    pub fn with_chunk_lines(mut self, chunk_lines: usize) -> Self {
+        self.chunk_lines = chunk_lines.max(1);
+        self
+    }
+
+    /// Limits the number of chunks merged at once, and so the number of open files,
+    /// at least two.
+    ///
+    /// More chunks are merged into intermediate files first, in as many passes as needed.
+    pub fn with_fan_in(mut self, fan_in: usize) -> Self {
+        self.fan_in = fan_in.max(2);
+        self
+    }
+
+    /// Spills sorted chunks into another directory, which must exist.
+    pub fn with_directory<P>(mut self, directory: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    /// Reads another clock than the [`SystemClock`] to name spill directories,
+    /// e.g. a [`crate::ManualClock`] in tests.
+    pub fn with_clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Sorts Lines in memory.
+    pub fn sort(lines: &mut [InfluxLine]) {
+        lines.sort_by_cached_key(|line| SortKey::from(line));
+    }
+
+    /// Sorts Lines of any number, writing them newline-terminated to the output.
+    ///
+    /// Lines are sorted in chunks of [`Self::with_chunk_lines`],
+    /// which are spilled to temporary files unless the input fits into one,
+    /// and then merged into the output, [`Self::with_fan_in`] chunks at a time.
+    /// Stops at the first error of the input and returns the number of Lines written otherwise.
+    pub fn sort_external<I, W>(&self, lines: I, output: W) -> Result<usize, InfluxLineError>
+    where
+        I: IntoIterator<Item = Result<InfluxLine, InfluxLineError>>,
+        W: Write,
+    {
+        let mut chunk = Vec::new();
+        let mut spill: Option<(SpillDirectory, Vec<PathBuf>)> = None;
+
+        for line in lines {
+            chunk.push(line?);
+            if chunk.len() >= self.chunk_lines {
+                let (directory, chunks) = match &mut spill {
+                    Some(spill) => spill,
+                    None => spill.insert((self.spill_directory()?, Vec::new())),
+                };
+                let path = directory.next_path();
+                Self::sort(&mut chunk);
+                write_lines(chunk.drain(..).map(Ok), File::create(&path)?)?;
+                chunks.push(path);
+            }
+        }
+
+        Self::sort(&mut chunk);
+        let written = match spill {
+            None => write_lines(chunk.into_iter().map(Ok), output)?,
+            Some((mut directory, mut chunks)) => {
+                // The last chunk is merged from memory rather than spilled,
+                // which takes one place of the final merge.
+                while chunks.len() >= self.fan_in {
+                    chunks = self.merge_pass(&mut directory, chunks)?;
+                }
+                let mut sources: Vec<Box<dyn Iterator<Item = _>>> = Vec::new();
+                for path in chunks.drain(..) {
+                    sources.push(Box::new(read_lines(BufReader::new(File::open(path)?))));
+                }
+                sources.push(Box::new(chunk.into_iter().map(Ok)));
+                write_lines(LineMerger::new(sources), output)?
+            }
+        };
+        Ok(written)
+    }
+
+    /// Merges consecutive groups of spilled chunks into one file each,
+    /// so that Lines of equal order keep their relative order.
+    fn merge_pass(
+        &self,
+        directory: &mut SpillDirectory,
+        chunks: Vec<PathBuf>,
+    ) -> Result<Vec<PathBuf>, InfluxLineError> {
+        let mut merged = Vec::new();
+        for group in chunks.chunks(self.fan_in) {
+            if let [single] = group {
+                merged.push(single.clone());
+                continue;
+            }
+
+            let mut sources = Vec::new();
+            for path in group {
+                sources.push(read_lines(BufReader::new(File::open(path)?)));
+            }
+            let path = directory.next_path();
+            write_lines(LineMerger::new(sources), File::create(&path)?)?;
+            for path in group {
+                fs::remove_file(path)?;
+            }
+            merged.push(path);
+        }
+        Ok(merged)
+    }
+
+    fn spill_directory(&self) -> Result<SpillDirectory, InfluxLineError> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let parent = self.directory.clone().unwrap_or_else(std::env::temp_dir);
+        let path = parent.join(format!(
+            "influx-line-sort-{}-{}-{}",
+            std::process::id(),
+            self.clock.now(),
+            COUNTER.fetch_add(1, AtomicOrdering::Relaxed)
+        ));
+        fs::create_dir(&path)?;
+        Ok(SpillDirectory { path, files: 0 })
+    }
+}
+
+impl Debug for LineSorter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LineSorter")
+            .field("chunk_lines", &self.chunk_lines)
+            .field("fan_in", &self.fan_in)
+            .field("directory", &self.directory)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for LineSorter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I> LineMerger<I>
+where
+    I: Iterator<Item = Result<InfluxLine, InfluxLineError>>,
+{
+    /// Merges sources that are each sorted already.
+    pub fn new<S>(sources: S) -> Self
+    where
+        S: IntoIterator<Item = I>,
+    {
+        let sources: Vec<Source<I>> = sources
+            .into_iter()
+            .map(|lines| Source {
+                lines,
+                taken: 0,
+                last: None,
+            })
+            .collect();
+        Self {
+            pending: (0..sources.len()).rev().collect(),
+            sources,
+            heads: BinaryHeap::new(),
+        }
+    }
+
+    /// Takes the next Line of a source into the heap, or returns the error in its place.
+    /// Sources are advanced again after an error until they end.
+    fn advance(&mut self, index: usize) -> Option<InfluxLineError> {
+        let source = &mut self.sources[index];
+        let line = match source.lines.next()? {
+            Ok(line) => line,
+            Err(error) => return Some(error),
+        };
+        source.taken += 1;
+
+        let key = SortKey::from(&line);
+        if source.last.as_ref().is_some_and(|last| key < *last) {
+            return Some(InfluxLineError::UnsortedInput {
+                input: index,
+                line: source.taken,
+            });
+        }
+        source.last = Some(key.clone());
+        self.heads.push(Reverse(Head {
+            key,
+            source: index,
+            line,
+        }));
+        None
+    }
+}
+
+impl LineMerger<Box<dyn Iterator<Item = Result<InfluxLine, InfluxLineError>>>> {
+    /// Opens sorted uncompressed Line Protocol files,
+    /// skipping blank lines and `#` comments.
+    pub fn open<P>(paths: impl IntoIterator<Item = P>) -> Result<Self, InfluxLineError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut sources: Vec<Box<dyn Iterator<Item = _>>> = Vec::new();
+        for path in paths {
+            sources.push(Box::new(read_lines(BufReader::new(File::open(path)?))));
+        }
+        Ok(Self::new(sources))
+    }
+}
+
+impl<I> Iterator for LineMerger<I>
+where
+    I: Iterator<Item = Result<InfluxLine, InfluxLineError>>,
+{
+    type Item = Result<InfluxLine, InfluxLineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(index) = self.pending.pop() {
+            if let Some(error) = self.advance(index) {
+                self.pending.push(index);
+                return Some(Err(error));
+            }
+        }
+
+        let Reverse(head) = self.heads.pop()?;
+        // Lines of a sorted source only follow, so the successor may wait until the next call.
+        self.pending.push(head.source);
+        Some(Ok(head.line))
+    }
+}
+
+impl From<&InfluxLine> for SortKey {
+    fn from(line: &InfluxLine) -> Self {
+        let mut tags: Vec<(String, String)> = line
+            .tags()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        tags.sort_unstable();
+        Self {
+            measurement: line.measurement().to_string(),
+            tags,
+            timestamp: line.timestamp(),
+        }
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head {}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key
+            .cmp(&other.key)
+            .then(self.source.cmp(&other.source))
+    }
+}
+
+impl SpillDirectory {
+    /// Names a new file in the directory.
+    fn next_path(&mut self) -> PathBuf {
+        self.files += 1;
+        self.path.join(format!("{}.lp", self.files - 1))
+    }
+}
+
+impl Drop for SpillDirectory {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Reads Lines of a plain Line Protocol stream, skipping blank lines and `#` comments.
+///
+/// Yields an error for every malformed Line and keeps going,
+/// but stops after the first I/O error.
+fn read_lines<R>(reader: R) -> impl Iterator<Item = Result<InfluxLine, InfluxLineError>>
+where
+    R: BufRead,
+{
+    let mut failed = false;
+    reader
+        .lines()
+        .map_while(move |line| match line {
+            _ if failed => None,
+            Ok(line) => Some(Ok(line)),
+            Err(error) => {
+                failed = true;
+                Some(Err(error))
+            }
+        })
+        .filter(|line| {
+            line.as_ref().map_or(true, |line| {
+                !line.trim().is_empty() && !line.trim().starts_with('#')
+            })
+        })
+        .map(|line| line.map_err(InfluxLineError::from)?.trim().parse())
+}
+
+/// Writes newline-terminated Lines, stopping at the first error.
+fn write_lines<L, W>(lines: L, output: W) -> Result<usize, InfluxLineError>
+where
+    L: IntoIterator<Item = Result<InfluxLine, InfluxLineError>>,
+    W: Write,
+{
+    let mut output = BufWriter::new(output);
+    let mut written = 0;
+    for line in lines {
+        let mut encoded = line?.to_string();
+        if !encoded.ends_with('\n') {
+            encoded.push('\n');
+        }
+        output.write_all(encoded.as_bytes())?;
+        written += 1;
+    }
+    output.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{InfluxLine, InfluxLineError, LineMerger, LineSorter};
+
+    const UNSORTED: &str = "\
+        mem,host=b used=1i 30
+        cpu,host=b usage=0.2 20
+        cpu usage=0.5 10
+        cpu,zone=z,host=a usage=0.1 20
+        # A comment
+        cpu,host=a,zone=z usage=0.3 10
+        cpu,host=a,zone=z usage=0.4
+        cpu,host=b usage=0.9 20
+    ";
+
+    const SORTED: &str = "\
+        cpu usage=0.5 10
+        cpu,host=a,zone=z usage=0.4
+        cpu,host=a,zone=z usage=0.3 10
+        cpu,zone=z,host=a usage=0.1 20
+        cpu,host=b usage=0.2 20
+        cpu,host=b usage=0.9 20
+        mem,host=b used=1i 30
+    ";
+
+    fn lines(source: &str) -> impl Iterator<Item = Result<InfluxLine, InfluxLineError>> {
+        source
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::parse)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn text(source: &str) -> String {
+        lines(source)
+            .map(|line| line.unwrap().to_string() + "\n")
+            .collect()
+    }
+
+    #[test]
+    fn sorts_in_memory() {
+        let mut sorted: Vec<InfluxLine> = lines(UNSORTED).map(Result::unwrap).collect();
+
+        LineSorter::sort(&mut sorted);
+
+        assert_eq!(
+            lines(SORTED).map(Result::unwrap).collect::<Vec<_>>(),
+            sorted
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::in_memory(100, LineSorter::DEFAULT_FAN_IN)]
+    #[case::single_line_chunks(1, LineSorter::DEFAULT_FAN_IN)]
+    #[case::uneven_chunks(3, LineSorter::DEFAULT_FAN_IN)]
+    #[case::merge_passes(1, 2)]
+    #[case::uneven_merge_passes(1, 3)]
+    fn sorts_externally(#[case] chunk_lines: usize, #[case] fan_in: usize) {
+        let directory = tempfile::tempdir().unwrap();
+        let sorter = LineSorter::new()
+            .with_chunk_lines(chunk_lines)
+            .with_fan_in(fan_in)
+            .with_directory(directory.path());
+        let mut output = Vec::new();
+
+        let written = sorter
+            .sort_external(lines(UNSORTED), &mut output)
+            .expect("Must sort here");
+
+        assert_eq!(7, written);
+        assert_eq!(text(SORTED), String::from_utf8(output).unwrap());
+        assert_eq!(0, fs::read_dir(directory.path()).unwrap().count());
+    }
+
+    #[test]
+    fn external_sort_stops_at_errors() {
+        let directory = tempfile::tempdir().unwrap();
+        let sorter = LineSorter::new()
+            .with_chunk_lines(1)
+            .with_directory(directory.path());
+
+        let result = sorter.sort_external(lines("cpu x=1i\ncpu x=\ncpu x=2i"), Vec::new());
+
+        assert!(result.is_err());
+        assert_eq!(0, fs::read_dir(directory.path()).unwrap().count());
+    }
+
+    #[test]
+    fn merges_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let first = directory.path().join("first.lp");
+        let second = directory.path().join("second.lp");
+        fs::write(&first, "cpu usage=1i 10\n\n# Comment\nmem used=1i 10\n").unwrap();
+        fs::write(&second, "cpu usage=2i 10\ncpu usage=3i 20\n").unwrap();
+
+        let merged: Vec<InfluxLine> = LineMerger::open([&first, &second])
+            .expect("Must open here")
+            .collect::<Result<_, _>>()
+            .expect("Must merge here");
+
+        assert_eq!(
+            lines("cpu usage=1i 10\ncpu usage=2i 10\ncpu usage=3i 20\nmem used=1i 10")
+                .map(Result::unwrap)
+                .collect::<Vec<_>>(),
+            merged
+        );
+    }
+
+    #[test]
+    fn merge_reports_and_skips_bad_lines() {
+        let merged: Vec<_> = LineMerger::new([
+            lines("cpu x=1i 10\ncpu x= 15\ncpu x=2i 20"),
+            lines("cpu x=3i 30\ncpu x=4i 5\ncpu x=5i 40"),
+        ])
+        .map(|line| line.map(|line| line.to_string()))
+        .collect();
+
+        assert_eq!("cpu x=1i 10", merged[0].as_ref().unwrap());
+        assert!(merged[1].is_err());
+        assert_eq!("cpu x=2i 20", merged[2].as_ref().unwrap());
+        assert_eq!("cpu x=3i 30", merged[3].as_ref().unwrap());
+        assert!(matches!(
+            merged[4],
+            Err(InfluxLineError::UnsortedInput { input: 1, line: 2 })
+        ));
+        assert_eq!("cpu x=5i 40", merged[5].as_ref().unwrap());
+        assert_eq!(6, merged.len());
+    }
+}
@@ -0,0 +1,717 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{InfluxLine, InfluxLineError};
+
+/// A write-ahead spool keeping Lines on disk while the downstream sink is unavailable.
+///
+/// Every appended Line gets an offset, growing by one,
+/// and is stored as a checksummed record in a segment file.
+/// Lines are replayed in the order of their offsets
+/// until they are acknowledged with [`Self::ack`],
+/// after which fully acknowledged segments are deleted.
+///
+/// The last acknowledged offset is persisted,
+/// so after a crash the spool picks up right where it stopped.
+/// A torn record at the end of a segment is truncated on [`Self::open`].
+///
+/// # Eviction
+///
+/// Whole segments are evicted, oldest first,
+/// once the total size exceeds [`Self::with_max_bytes`]
+/// or their newest record is older than [`Self::with_max_age`],
+/// acknowledged or not.
+///
+/// # Layout
+///
+/// ```text
+/// <dir>/00000000000000000000.segment
+/// <dir>/00000000000000001024.segment
+/// <dir>/ack
+/// ```
+///
+/// Segments are named after the offset of their first record.
+/// A record is a little-endian header with the payload length,
+/// the CRC32 of the rest of the record and the append time in milliseconds,
+/// followed by the Line encoded without a newline.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let directory = tempfile::tempdir().unwrap();
+/// let mut spool = Spool::open(directory.path()).unwrap();
+///
+/// spool.append(&InfluxLine::try_new("cpu", "usage", 0.5).unwrap()).unwrap();
+/// spool.append(&InfluxLine::try_new("cpu", "usage", 0.7).unwrap()).unwrap();
+///
+/// let mut written = Vec::new();
+/// spool
+///     .drain(100, |lines| {
+///         written.extend_from_slice(lines);
+///         Ok(())
+///     })
+///     .unwrap();
+///
+/// assert_eq!(2, written.len());
+/// assert_eq!(0, spool.pending());
+/// ```
+#[derive(Debug)]
+pub struct Spool {
+    directory: PathBuf,
+    segments: VecDeque<Segment>,
+    /// The first offset that is not acknowledged yet.
+    acked: u64,
+    next_offset: u64,
+    writer: Option<File>,
+    segment_size: u64,
+    max_bytes: Option<u64>,
+    max_age: Option<Duration>,
+    evicted: u64,
+    /// Where the last replay started and stopped reading,
+    /// so the next one seeks past acknowledged records instead of reading them again.
+    cursors: [Option<Cursor>; 2],
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    first_offset: u64,
+    records: u64,
+    bytes: u64,
+    /// Milliseconds since the UNIX epoch.
+    last_append: i64,
+}
+
+/// The byte position of a record in a segment.
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    segment: u64,
+    offset: u64,
+    position: u64,
+}
+
+/// A record read back from a segment.
+struct Record {
+    appended: i64,
+    payload: Vec<u8>,
+}
+
+const HEADER_SIZE: usize = 16;
+const SEGMENT_EXTENSION: &str = "segment";
+const ACK_FILE: &str = "ack";
+
+impl Spool {
+    const DEFAULT_SEGMENT_SIZE: u64 = 16 * 1024 * 1024;
+
+    /// Opens a spool in a directory, creating it if needed,
+    /// and recovers the state left by the previous run.
+    pub fn open<P>(directory: P) -> Result<Self, InfluxLineError>
+    where
+        P: AsRef<Path>,
+    {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)?;
+
+        let acked = match fs::read_to_string(directory.join(ACK_FILE)) {
+            Ok(text) => u64::from_str(text.trim()).map_err(|_| InfluxLineError::CorruptedSpool)?,
+            Err(error) if error.kind() == ErrorKind::NotFound => 0,
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut offsets = Vec::new();
+        for entry in fs::read_dir(&directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some(SEGMENT_EXTENSION)
+            {
+                continue;
+            }
+            let offset = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| u64::from_str(stem).ok())
+                .ok_or(InfluxLineError::CorruptedSpool)?;
+            offsets.push(offset);
+        }
+        offsets.sort_unstable();
+
+        let mut spool = Self {
+            directory,
+            segments: VecDeque::new(),
+            acked,
+            next_offset: acked,
+            writer: None,
+            segment_size: Self::DEFAULT_SEGMENT_SIZE,
+            max_bytes: None,
+            max_age: None,
+            evicted: 0,
+            cursors: [None; 2],
+        };
+        for first_offset in offsets {
+            let segment = spool.recover(first_offset)?;
+            spool.next_offset = spool.next_offset.max(segment.end());
+            spool.segments.push_back(segment);
+        }
+        spool.delete_acked()?;
+        Ok(spool)
+    }
+
+    /// Starts a new segment once the current one grows over the size, 16 MiB by default.
+    pub fn with_segment_size(mut self, segment_size: u64) -> Self {
+        self.segment_size = segment_size.max(1);
+        self
+    }
+
+    /// Limits the total size of all segments.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes.replace(max_bytes);
+        self
+    }
+
+    /// Limits the age of records, judging by the newest record in a segment.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age.replace(max_age);
+        self
+    }
+
+    /// Appends a Line and returns its offset, evicting old segments if needed.
+    ///
+    /// The record is handed over to the OS right away, so it survives a crash of the process.
+    /// Use [`Self::sync`] to survive a power loss as well.
+    pub fn append(&mut self, line: &InfluxLine) -> Result<u64, InfluxLineError> {
+        let payload = line.to_string();
+        let payload = payload.trim_end_matches('\n').as_bytes();
+        let appended = now();
+
+        let mut record = Vec::with_capacity(HEADER_SIZE + payload.len());
+        record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        record.extend_from_slice(&checksum(appended, payload).to_le_bytes());
+        record.extend_from_slice(&appended.to_le_bytes());
+        record.extend_from_slice(payload);
+
+        let rolls = self
+            .segments
+            .back()
+            .is_none_or(|segment| segment.bytes >= self.segment_size);
+        if rolls {
+            self.writer = None;
+            self.segments.push_back(Segment {
+                first_offset: self.next_offset,
+                records: 0,
+                bytes: 0,
+                last_append: appended,
+            });
+        }
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => {
+                let active = self.segments.back().expect("Segment is created above");
+                let path = self.segment_path(active.first_offset);
+                let file = OpenOptions::new().create(true).append(true).open(path)?;
+                self.writer.insert(file)
+            }
+        };
+        writer.write_all(&record)?;
+
+        let segment = self.segments.back_mut().expect("Segment is created above");
+        segment.records += 1;
+        segment.bytes += record.len() as u64;
+        segment.last_append = appended;
+        let offset = self.next_offset;
+        self.next_offset += 1;
+
+        self.evict()?;
+        Ok(offset)
+    }
+
+    /// Flushes the current segment to the disk.
+    pub fn sync(&mut self) -> Result<(), InfluxLineError> {
+        if let Some(writer) = &self.writer {
+            writer.sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Reads up to `max_lines` unacknowledged Lines along with their offsets, oldest first.
+    ///
+    /// Reading does not move the spool forward, see [`Self::ack`].
+    /// Reading resumes where the previous call stopped if its Lines were acknowledged,
+    /// so draining a segment in batches reads every record once.
+    ///
+    /// Stops before a record that cannot be read back as a Line, e.g. one written by a version
+    /// of this crate that formatted Lines differently. If that record comes first,
+    /// fails with [`InfluxLineError::UnreadableSpoolRecord`] naming its offset,
+    /// which may be acknowledged to skip it.
+    pub fn replay(&mut self, max_lines: usize) -> Result<Vec<(u64, InfluxLine)>, InfluxLineError> {
+        let mut lines = Vec::new();
+        let mut start = None;
+        let mut stop = None;
+        for segment in self.segments.iter() {
+            if lines.len() >= max_lines {
+                break;
+            }
+            if segment.end() <= self.acked {
+                continue;
+            }
+
+            let mut cursor = self.cursor_in(segment);
+            let mut file = File::open(self.segment_path(segment.first_offset))?;
+            file.seek(SeekFrom::Start(cursor.position))?;
+            let mut reader = BufReader::new(file);
+            while cursor.offset < segment.end() && lines.len() < max_lines {
+                let Some(record) = read_record(&mut reader)? else {
+                    break;
+                };
+                let offset = cursor.offset;
+                let position = cursor.position;
+                cursor.offset += 1;
+                cursor.position += (HEADER_SIZE + record.payload.len()) as u64;
+                if offset < self.acked {
+                    continue;
+                }
+
+                let line = std::str::from_utf8(&record.payload)
+                    .ok()
+                    .and_then(|text| InfluxLine::from_str(text).ok());
+                let Some(line) = line else {
+                    if lines.is_empty() {
+                        return Err(InfluxLineError::UnreadableSpoolRecord { offset });
+                    }
+                    self.cursors = [start, stop];
+                    return Ok(lines);
+                };
+                start.get_or_insert(Cursor {
+                    segment: segment.first_offset,
+                    offset,
+                    position,
+                });
+                stop = Some(Cursor {
+                    segment: segment.first_offset,
+                    ..cursor
+                });
+                lines.push((offset, line));
+            }
+        }
+        self.cursors = [start, stop];
+        Ok(lines)
+    }
+
+    /// Acknowledges every Line up to and including the offset,
+    /// persisting the position and deleting fully acknowledged segments.
+    pub fn ack(&mut self, offset: u64) -> Result<(), InfluxLineError> {
+        if offset < self.acked {
+            return Ok(());
+        }
+        self.acked = offset.saturating_add(1).min(self.next_offset);
+        self.persist_ack()?;
+        self.delete_acked()
+    }
+
+    /// Replays Lines in batches, acknowledging every batch accepted by the sink.
+    ///
+    /// Stops at the first batch the sink fails to accept and returns its error,
+    /// so the batch is replayed again next time.
+    /// Stops at an unreadable record the same way, see [`Self::replay`].
+    pub fn drain<F>(&mut self, batch_size: usize, mut sink: F) -> Result<(), InfluxLineError>
+    where
+        F: FnMut(&[InfluxLine]) -> Result<(), InfluxLineError>,
+    {
+        loop {
+            let batch = self.replay(batch_size.max(1))?;
+            let Some(&(last, _)) = batch.last() else {
+                return Ok(());
+            };
+
+            let lines: Vec<InfluxLine> = batch.into_iter().map(|(_, line)| line).collect();
+            sink(&lines)?;
+            self.ack(last)?;
+        }
+    }
+
+    /// The number of Lines waiting to be acknowledged.
+    pub fn pending(&self) -> u64 {
+        self.segments
+            .iter()
+            .map(|segment| segment.end() - segment.first_offset.max(self.acked).min(segment.end()))
+            .sum()
+    }
+
+    /// The total size of all segments in bytes.
+    pub fn size(&self) -> u64 {
+        self.segments.iter().map(|segment| segment.bytes).sum()
+    }
+
+    /// The number of unacknowledged Lines lost to eviction since the spool was opened.
+    pub fn evicted(&self) -> u64 {
+        self.evicted
+    }
+
+    /// Deletes segments over the size or age limits.
+    ///
+    /// Called on every append, but may be called periodically
+    /// to expire records while nothing is appended.
+    pub fn evict(&mut self) -> Result<(), InfluxLineError> {
+        let now = now();
+        let mut evicted_any = false;
+
+        while let Some(oldest) = self.segments.front() {
+            let over_size = self
+                .max_bytes
+                .is_some_and(|max_bytes| self.size() > max_bytes);
+            let expired = self.max_age.is_some_and(|max_age| {
+                now.saturating_sub(oldest.last_append) > max_age.as_millis() as i64
+            });
+            if !over_size && !expired {
+                break;
+            }
+
+            let oldest = self.remove_oldest()?;
+            self.evicted += oldest.end() - oldest.first_offset.max(self.acked).min(oldest.end());
+            self.acked = self.acked.max(oldest.end());
+            evicted_any = true;
+        }
+
+        if evicted_any {
+            self.persist_ack()?;
+        }
+        Ok(())
+    }
+
+    /// Scans a segment left by the previous run, truncating a torn or corrupted tail.
+    fn recover(&self, first_offset: u64) -> Result<Segment, InfluxLineError> {
+        let path = self.segment_path(first_offset);
+        let mut segment = Segment {
+            first_offset,
+            records: 0,
+            bytes: 0,
+            last_append: 0,
+        };
+
+        let file = File::open(&path)?;
+        let length = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        while let Some(record) = read_record(&mut reader)? {
+            segment.records += 1;
+            segment.bytes += (HEADER_SIZE + record.payload.len()) as u64;
+            segment.last_append = record.appended;
+        }
+
+        if segment.bytes < length {
+            OpenOptions::new()
+                .write(true)
+                .open(&path)?
+                .set_len(segment.bytes)?;
+        }
+        Ok(segment)
+    }
+
+    fn remove_oldest(&mut self) -> Result<Segment, InfluxLineError> {
+        let segment = self
+            .segments
+            .pop_front()
+            .expect("Caller checks there is a segment");
+        if self.segments.is_empty() {
+            self.writer = None;
+        }
+        match fs::remove_file(self.segment_path(segment.first_offset)) {
+            Ok(()) => Ok(segment),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(segment),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn delete_acked(&mut self) -> Result<(), InfluxLineError> {
+        while let Some(oldest) = self.segments.front() {
+            if oldest.end() > self.acked {
+                break;
+            }
+            self.remove_oldest()?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the acknowledgement file atomically.
+    fn persist_ack(&self) -> Result<(), InfluxLineError> {
+        let temporary = self.directory.join(format!("{}.tmp", ACK_FILE));
+        let mut file = File::create(&temporary)?;
+        file.write_all(self.acked.to_string().as_bytes())?;
+        file.sync_data()?;
+        fs::rename(temporary, self.directory.join(ACK_FILE))?;
+        Ok(())
+    }
+
+    /// Picks the closest known position to the first unacknowledged record of a segment.
+    fn cursor_in(&self, segment: &Segment) -> Cursor {
+        let target = self.acked.max(segment.first_offset);
+        self.cursors
+            .into_iter()
+            .flatten()
+            .filter(|cursor| cursor.segment == segment.first_offset && cursor.offset <= target)
+            .max_by_key(|cursor| cursor.offset)
+            .unwrap_or(Cursor {
+                segment: segment.first_offset,
+                offset: segment.first_offset,
+                position: 0,
+            })
+    }
+
+    fn segment_path(&self, first_offset: u64) -> PathBuf {
+        self.directory
+            .join(format!("{:020}.{}", first_offset, SEGMENT_EXTENSION))
+    }
+}
+
+impl Segment {
+    /// The offset following the last record.
+    fn end(&self) -> u64 {
+        self.first_offset + self.records
+    }
+}
+
+/// Reads the next record, treating a torn or corrupted one as the end of the segment.
+fn read_record(reader: &mut impl Read) -> Result<Option<Record>, InfluxLineError> {
+    let mut header = [0; HEADER_SIZE];
+    if !read_full(reader, &mut header)? {
+        return Ok(None);
+    }
+
+    let length = u32::from_le_bytes(header[0..4].try_into().expect("Slice has 4 bytes"));
+    let expected = u32::from_le_bytes(header[4..8].try_into().expect("Slice has 4 bytes"));
+    let appended = i64::from_le_bytes(header[8..16].try_into().expect("Slice has 8 bytes"));
+
+    let mut payload = vec![0; length as usize];
+    if !read_full(reader, &mut payload)? || checksum(appended, &payload) != expected {
+        return Ok(None);
+    }
+    Ok(Some(Record { appended, payload }))
+}
+
+/// Fills the buffer, returning `false` if the input ends first.
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> Result<bool, InfluxLineError> {
+    match reader.read_exact(buffer) {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn checksum(appended: i64, payload: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&appended.to_le_bytes());
+    hasher.update(payload);
+    hasher.finalize()
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::{InfluxLine, InfluxLineError, Spool};
+
+    fn line(index: u64) -> InfluxLine {
+        InfluxLine::try_new("cpu", "index", index).unwrap()
+    }
+
+    fn offsets(spool: &mut Spool) -> Vec<u64> {
+        spool
+            .replay(usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|(offset, _)| offset)
+            .collect()
+    }
+
+    #[test]
+    fn replays_in_order_across_segments() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut spool = Spool::open(directory.path()).unwrap().with_segment_size(40);
+
+        for index in 0..5 {
+            assert_eq!(index, spool.append(&line(index)).unwrap());
+        }
+        let replayed = spool.replay(10).unwrap();
+
+        assert_eq!(3, spool.segments.len());
+        assert_eq!(
+            (0..5).map(|index| (index, line(index))).collect::<Vec<_>>(),
+            replayed
+        );
+    }
+
+    #[test]
+    fn resumes_from_last_ack() {
+        let directory = tempfile::tempdir().unwrap();
+        {
+            let mut spool = Spool::open(directory.path()).unwrap().with_segment_size(40);
+            for index in 0..5 {
+                spool.append(&line(index)).unwrap();
+            }
+            spool.ack(2).unwrap();
+        }
+
+        let mut spool = Spool::open(directory.path()).unwrap();
+        assert_eq!(vec![3, 4], offsets(&mut spool));
+        assert_eq!(5, spool.append(&line(5)).unwrap());
+        assert_eq!(3, spool.pending());
+    }
+
+    #[test]
+    fn truncates_torn_records() {
+        let directory = tempfile::tempdir().unwrap();
+        {
+            let mut spool = Spool::open(directory.path()).unwrap();
+            spool.append(&line(0)).unwrap();
+            spool.append(&line(1)).unwrap();
+        }
+        let segment = directory.path().join("00000000000000000000.segment");
+        OpenOptions::new()
+            .append(true)
+            .open(&segment)
+            .unwrap()
+            .write_all(&[13, 0, 0, 0, 1, 2])
+            .unwrap();
+
+        let mut spool = Spool::open(directory.path()).unwrap();
+        spool.append(&line(2)).unwrap();
+
+        assert_eq!(vec![0, 1, 2], offsets(&mut spool));
+    }
+
+    #[test]
+    fn stops_at_corrupted_records() {
+        let directory = tempfile::tempdir().unwrap();
+        {
+            let mut spool = Spool::open(directory.path()).unwrap();
+            for index in 0..3 {
+                spool.append(&line(index)).unwrap();
+            }
+        }
+        let segment = directory.path().join("00000000000000000000.segment");
+        let mut bytes = std::fs::read(&segment).unwrap();
+        // Flips a byte in the payload of the second record.
+        bytes[28 + 16 + 4] ^= 0xff;
+        std::fs::write(&segment, bytes).unwrap();
+
+        let mut spool = Spool::open(directory.path()).unwrap();
+
+        assert_eq!(vec![0], offsets(&mut spool));
+    }
+
+    #[test]
+    fn resumes_reading_after_acked_records() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut spool = Spool::open(directory.path()).unwrap();
+        for index in 0..4 {
+            spool.append(&line(index)).unwrap();
+        }
+
+        let first = spool.replay(2).unwrap();
+        spool.ack(first[1].0).unwrap();
+        // Acknowledged records are not read again, so damaging them goes unnoticed.
+        let segment = directory.path().join("00000000000000000000.segment");
+        let mut bytes = std::fs::read(&segment).unwrap();
+        bytes[..56].fill(0xff);
+        std::fs::write(&segment, bytes).unwrap();
+
+        assert_eq!(vec![(2, line(2)), (3, line(3))], spool.replay(10).unwrap());
+    }
+
+    #[test]
+    fn reports_unreadable_records() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut spool = Spool::open(directory.path()).unwrap();
+        spool.append(&line(0)).unwrap();
+        let payload = b"not a line";
+        let mut record = Vec::new();
+        record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        record.extend_from_slice(&super::checksum(0, payload).to_le_bytes());
+        record.extend_from_slice(&0_i64.to_le_bytes());
+        record.extend_from_slice(payload);
+        spool.writer.as_mut().unwrap().write_all(&record).unwrap();
+        spool.segments[0].records += 1;
+        spool.segments[0].bytes += record.len() as u64;
+        spool.next_offset += 1;
+        spool.append(&line(2)).unwrap();
+
+        assert_eq!(vec![(0, line(0))], spool.replay(10).unwrap());
+        spool.ack(0).unwrap();
+        assert!(matches!(
+            spool.replay(10),
+            Err(InfluxLineError::UnreadableSpoolRecord { offset: 1 })
+        ));
+        spool.ack(1).unwrap();
+        assert_eq!(vec![(2, line(2))], spool.replay(10).unwrap());
+    }
+
+    #[test]
+    fn drains_until_sink_fails() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut spool = Spool::open(directory.path()).unwrap().with_segment_size(40);
+        for index in 0..5 {
+            spool.append(&line(index)).unwrap();
+        }
+
+        let mut calls = 0;
+        let error = spool
+            .drain(2, |_| {
+                calls += 1;
+                if calls > 1 {
+                    Err(InfluxLineError::Failed)
+                } else {
+                    Ok(())
+                }
+            })
+            .unwrap_err();
+
+        assert!(matches!(error, InfluxLineError::Failed));
+        assert_eq!(vec![2, 3, 4], offsets(&mut spool));
+        assert_eq!(2, spool.segments.len());
+    }
+
+    #[test]
+    fn evicts_oldest_segments_by_size() {
+        let directory = tempfile::tempdir().unwrap();
+        // Every record takes 28 bytes, so every segment holds two of them.
+        let mut spool = Spool::open(directory.path())
+            .unwrap()
+            .with_segment_size(56)
+            .with_max_bytes(112);
+
+        for index in 0..6 {
+            spool.append(&line(index)).unwrap();
+        }
+
+        assert_eq!(vec![2, 3, 4, 5], offsets(&mut spool));
+        assert_eq!(2, spool.evicted());
+        assert!(spool.size() <= 112);
+    }
+
+    #[test]
+    fn evicts_expired_segments() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut spool = Spool::open(directory.path())
+            .unwrap()
+            .with_segment_size(28)
+            .with_max_age(Duration::from_millis(50));
+
+        spool.append(&line(0)).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        spool.append(&line(1)).unwrap();
+
+        assert_eq!(vec![1], offsets(&mut spool));
+        assert_eq!(1, spool.evicted());
+    }
+}
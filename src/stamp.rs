@@ -0,0 +1,215 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{Clock, InfluxLine, Precision, SystemClock, Timestamp};
+
+/// Assigns timestamps to Lines that have none,
+/// so that the time is chosen by the writer rather than the server receiving them.
+///
+/// Without timestamps, InfluxDB uses the time a request is received,
+/// which changes when the request is retried.
+/// Stamping before encoding keeps the same time across retries,
+/// and stamping on ingest records when the data actually arrived.
+///
+/// Timestamps are truncated to [`Self::with_precision`],
+/// so that they survive writers using a coarser precision unchanged.
+/// Lines that have a timestamp already keep it.
+///
+/// Besides being used directly, a stamper can be plugged into
+/// [`crate::BatchConfig::with_stamper`], and with the respective features into
+/// `WriteConfig::with_stamper` and `WriteReceiver::with_stamper`.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let clock = ManualClock::new(Timestamp::from(1704067200123456789_i64));
+/// let stamper = Stamper::new()
+///     .with_clock(clock.clone())
+///     .with_precision(Precision::Milliseconds)
+///     .with_mode(StampMode::EachBatch);
+///
+/// let lines = vec![
+///     InfluxLine::try_new("cpu", "usage", 0.5).unwrap(),
+///     InfluxLine::try_new("cpu", "usage", 0.7).unwrap().with_timestamp(10),
+/// ];
+/// let stamped = stamper.stamp_batch(lines);
+///
+/// assert_eq!(Some(Timestamp::from(1704067200123000000_i64)), stamped[0].timestamp());
+/// assert_eq!(Some(Timestamp::from(10)), stamped[1].timestamp());
+/// ```
+#[derive(Clone)]
+pub struct Stamper {
+    clock: Arc<dyn Clock>,
+    precision: Precision,
+    mode: StampMode,
+}
+
+/// Which Lines of a batch share the same timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StampMode {
+    /// Reads the clock for every Line.
+    #[default]
+    EachLine,
+    /// Reads the clock once per batch, e.g. per request or payload,
+    /// so that the Lines of a batch are simultaneous.
+    EachBatch,
+}
+
+impl Stamper {
+    /// Creates a stamper reading the [`SystemClock`] for every Line with nanosecond precision.
+    pub fn new() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            precision: Precision::Nanoseconds,
+            mode: StampMode::default(),
+        }
+    }
+
+    /// Reads another clock, e.g. a [`crate::ManualClock`] in tests.
+    pub fn with_clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Truncates timestamps to a precision, nanoseconds by default.
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Shares timestamps within batches, or not, which is the default.
+    pub fn with_mode(mut self, mode: StampMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Returns which Lines of a batch share the same timestamp.
+    pub fn mode(&self) -> StampMode {
+        self.mode
+    }
+
+    /// Reads the clock and truncates the time to the precision.
+    pub fn now(&self) -> Timestamp {
+        let precision = Duration::from_nanos(self.precision.nanoseconds().unsigned_abs());
+        self.clock.now().truncate(precision)
+    }
+
+    /// Stamps a Line with the current time unless it has a timestamp.
+    pub fn stamp(&self, line: InfluxLine) -> InfluxLine {
+        match line.timestamp() {
+            Some(_) => line,
+            None => line.with_timestamp(self.now()),
+        }
+    }
+
+    /// Stamps Lines without a timestamp, reading the clock as often as [`Self::with_mode`] says.
+    pub fn stamp_batch<I>(&self, lines: I) -> Vec<InfluxLine>
+    where
+        I: IntoIterator<Item = InfluxLine>,
+    {
+        let mut instant = None;
+        lines
+            .into_iter()
+            .map(|line| match (line.timestamp(), self.mode) {
+                (Some(_), _) => line,
+                (None, StampMode::EachLine) => line.with_timestamp(self.now()),
+                (None, StampMode::EachBatch) => {
+                    line.with_timestamp(*instant.get_or_insert_with(|| self.now()))
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for Stamper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Stamper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Stamper")
+            .field("precision", &self.precision)
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{InfluxLine, ManualClock, Precision, StampMode, Stamper, Timestamp};
+
+    fn lines() -> Vec<InfluxLine> {
+        (0..3)
+            .map(|index| InfluxLine::try_new("cpu", "index", index).unwrap())
+            .collect()
+    }
+
+    /// A clock that moves forward by a second every time it is read.
+    #[derive(Debug)]
+    struct TickingClock(ManualClock);
+
+    impl crate::Clock for TickingClock {
+        fn now(&self) -> Timestamp {
+            let now = self.0.now();
+            self.0.advance(Duration::from_secs(1));
+            now
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::each_line(StampMode::EachLine, vec![0, 1_000_000_000, 2_000_000_000])]
+    #[case::each_batch(StampMode::EachBatch, vec![0, 0, 0])]
+    fn stamps_batches(#[case] mode: StampMode, #[case] expected: Vec<i64>) {
+        let stamper = Stamper::new()
+            .with_clock(TickingClock(ManualClock::default()))
+            .with_mode(mode);
+
+        let stamped = stamper.stamp_batch(lines());
+
+        assert_eq!(
+            expected
+                .into_iter()
+                .map(Timestamp::from)
+                .map(Some)
+                .collect::<Vec<_>>(),
+            stamped
+                .iter()
+                .map(InfluxLine::timestamp)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::nanoseconds(Precision::Nanoseconds, 1704067200123456789)]
+    #[case::microseconds(Precision::Microseconds, 1704067200123456000)]
+    #[case::seconds(Precision::Seconds, 1704067200000000000)]
+    fn truncates_to_precision(#[case] precision: Precision, #[case] expected: i64) {
+        let stamper = Stamper::new()
+            .with_clock(ManualClock::new(Timestamp::from(1704067200123456789_i64)))
+            .with_precision(precision);
+
+        let stamped = stamper.stamp(InfluxLine::try_new("cpu", "usage", 0.5).unwrap());
+
+        assert_eq!(Some(Timestamp::from(expected)), stamped.timestamp());
+    }
+
+    #[test]
+    fn keeps_existing_timestamps() {
+        let stamper = Stamper::new().with_clock(ManualClock::default());
+        let line = InfluxLine::try_new("cpu", "usage", 0.5)
+            .unwrap()
+            .with_timestamp(15);
+
+        assert_eq!(Some(Timestamp::from(15)), stamper.stamp(line).timestamp());
+    }
+}
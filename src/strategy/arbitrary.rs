@@ -0,0 +1,107 @@
+use std::ops::ControlFlow;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::{NASTY_CHARACTERS, name, quoted};
+use crate::{InfluxLine, InfluxValue, KeyName, MeasurementName, QuotedString, Timestamp};
+
+fn text(u: &mut Unstructured<'_>, min: u32) -> Result<String> {
+    let mut text = String::new();
+    u.arbitrary_loop(Some(min), Some(12), |u| {
+        match u.int_in_range(0..=9)? {
+            0..=3 => text.push(*u.choose(&NASTY_CHARACTERS)?),
+            4..=7 => text.push(char::from(*u.choose(b"abcxyzABC019_")?)),
+            8 => text.push_str(&"\\".repeat(u.int_in_range(2..=5)?)),
+            _ => text.push(u.arbitrary()?),
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+    Ok(text)
+}
+
+impl<'a> Arbitrary<'a> for KeyName {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let text = text(u, 1)?;
+        Ok(KeyName::new(name(&text)).expect("Names are valid"))
+    }
+}
+
+impl<'a> Arbitrary<'a> for MeasurementName {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let text = text(u, 1)?;
+        Ok(MeasurementName::new(name(&text)).expect("Names are valid"))
+    }
+}
+
+impl<'a> Arbitrary<'a> for QuotedString {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(QuotedString::new(quoted(&text(u, 0)?)))
+    }
+}
+
+/// Floats are finite, as the Line Protocol has no representation for NaN and infinity.
+impl<'a> Arbitrary<'a> for InfluxValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let value = match u.int_in_range(0..=4)? {
+            0 => {
+                let float = f64::arbitrary(u)?;
+                InfluxValue::from(if float.is_finite() { float } else { 0.0 })
+            }
+            1 => InfluxValue::from(i64::arbitrary(u)?),
+            2 => InfluxValue::from(u64::arbitrary(u)?),
+            3 => InfluxValue::from(bool::arbitrary(u)?),
+            _ => InfluxValue::from(QuotedString::arbitrary(u)?),
+        };
+        Ok(value)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Timestamp {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Timestamp::from(i64::arbitrary(u)?))
+    }
+}
+
+/// Lines have up to four tags and one to four fields.
+/// Repeated keys are merged, so there may be fewer.
+impl<'a> Arbitrary<'a> for InfluxLine {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let measurement = MeasurementName::arbitrary(u)?;
+        let mut tags = Vec::new();
+        u.arbitrary_loop(Some(0), Some(4), |u| {
+            tags.push(<(KeyName, KeyName)>::arbitrary(u)?);
+            Ok(ControlFlow::Continue(()))
+        })?;
+        let mut fields = Vec::new();
+        u.arbitrary_loop(Some(1), Some(4), |u| {
+            fields.push(<(KeyName, InfluxValue)>::arbitrary(u)?);
+            Ok(ControlFlow::Continue(()))
+        })?;
+        let timestamp = Option::<Timestamp>::arbitrary(u)?;
+
+        Ok(InfluxLine::full(measurement, tags, fields, timestamp).expect("Fields are present"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use crate::InfluxLine;
+
+    #[rstest::rstest]
+    #[case::zeros(vec![0; 256])]
+    #[case::ones(vec![255; 256])]
+    #[case::counting((0..=255).collect())]
+    #[case::scrambled((0..4096_u32).map(|index| (index.wrapping_mul(2654435761) >> 13) as u8).collect())]
+    fn lines_round_trip(#[case] data: Vec<u8>) {
+        let mut u = Unstructured::new(&data);
+
+        while !u.is_empty() {
+            let line = InfluxLine::arbitrary(&mut u).expect("Must generate here");
+            let parsed: InfluxLine = line.to_string().parse().expect("Must parse here");
+
+            assert_eq!(line, parsed);
+        }
+    }
+}
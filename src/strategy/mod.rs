@@ -0,0 +1,27 @@
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "proptest")]
+mod proptest;
+
+/// Characters that need escaping somewhere in the Line Protocol,
+/// or are otherwise easy to get wrong, which generated text leans toward.
+const NASTY_CHARACTERS: [char; 12] = [
+    ' ', ',', '=', '"', '\\', '\'', '#', '\t', 'é', 'ß', '🦀', '🔥',
+];
+
+/// Makes generated text a valid name.
+///
+/// Line breaks are replaced with spaces, since they end a Line,
+/// the reserved `_` prefix is dropped, and empty names become `x`.
+fn name(text: &str) -> String {
+    let text = text.replace(['\n', '\r'], " ");
+    match text.trim_start_matches('_') {
+        "" => "x".to_owned(),
+        name => name.to_owned(),
+    }
+}
+
+/// Makes generated text a valid String Field value.
+fn quoted(text: &str) -> String {
+    text.replace(['\n', '\r'], " ")
+}
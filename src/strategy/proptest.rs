@@ -0,0 +1,160 @@
+use proptest::prelude::*;
+
+use super::{NASTY_CHARACTERS, name, quoted};
+use crate::{InfluxLine, InfluxValue, KeyName, MeasurementName, QuotedString, Timestamp};
+
+fn text() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        4 => prop::sample::select(&NASTY_CHARACTERS[..]).prop_map(String::from),
+        4 => "[a-zA-Z0-9_]",
+        1 => "\\\\{2,5}",
+        1 => any::<char>().prop_map(String::from),
+    ];
+    prop::collection::vec(piece, 1..12).prop_map(|pieces| pieces.concat())
+}
+
+impl Arbitrary for KeyName {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        text()
+            .prop_map(|text| KeyName::new(name(&text)).expect("Names are valid"))
+            .boxed()
+    }
+}
+
+impl Arbitrary for MeasurementName {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        text()
+            .prop_map(|text| MeasurementName::new(name(&text)).expect("Names are valid"))
+            .boxed()
+    }
+}
+
+impl Arbitrary for QuotedString {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![1 => Just(String::new()), 9 => text()]
+            .prop_map(|text| QuotedString::new(quoted(&text)))
+            .boxed()
+    }
+}
+
+/// Floats are finite, as the Line Protocol has no representation for NaN and infinity.
+impl Arbitrary for InfluxValue {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        let finite = prop::num::f64::NORMAL
+            | prop::num::f64::SUBNORMAL
+            | prop::num::f64::ZERO
+            | prop::num::f64::POSITIVE
+            | prop::num::f64::NEGATIVE;
+        prop_oneof![
+            finite.prop_map(InfluxValue::from),
+            any::<i64>().prop_map(InfluxValue::from),
+            any::<u64>().prop_map(InfluxValue::from),
+            any::<bool>().prop_map(InfluxValue::from),
+            any::<QuotedString>().prop_map(InfluxValue::from),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Timestamp {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any::<i64>().prop_map(Timestamp::from).boxed()
+    }
+}
+
+/// Lines have up to four tags and one to four fields.
+/// Repeated keys are merged, so there may be fewer.
+impl Arbitrary for InfluxLine {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            any::<MeasurementName>(),
+            prop::collection::vec(any::<(KeyName, KeyName)>(), 0..=4),
+            prop::collection::vec(any::<(KeyName, InfluxValue)>(), 1..=4),
+            any::<Option<Timestamp>>(),
+        )
+            .prop_map(|(measurement, tags, fields, timestamp)| {
+                InfluxLine::full(measurement, tags, fields, timestamp).expect("Fields are present")
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::{Debug, Display};
+    use std::str::FromStr;
+
+    use proptest::prelude::*;
+    use proptest::test_runner::TestCaseError;
+
+    use crate::{InfluxLine, InfluxValue, KeyName, MeasurementName, QuotedString, Timestamp};
+
+    fn round_trip<T>(value: T) -> Result<(), TestCaseError>
+    where
+        T: Display + FromStr + PartialEq + Debug,
+        T::Err: Debug,
+    {
+        let displayed = value.to_string();
+        let parsed = displayed.parse::<T>();
+        prop_assert!(
+            matches!(&parsed, Ok(parsed) if *parsed == value),
+            "{:?} displayed as {:?} parsed as {:?}",
+            value,
+            displayed,
+            parsed
+        );
+        Ok(())
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(512))]
+
+        #[test]
+        fn key_names_round_trip(value in any::<KeyName>()) {
+            round_trip(value)?;
+        }
+
+        #[test]
+        fn measurement_names_round_trip(value in any::<MeasurementName>()) {
+            round_trip(value)?;
+        }
+
+        #[test]
+        fn quoted_strings_round_trip(value in any::<QuotedString>()) {
+            round_trip(value)?;
+        }
+
+        #[test]
+        fn values_round_trip(value in any::<InfluxValue>()) {
+            round_trip(value)?;
+        }
+
+        #[test]
+        fn timestamps_round_trip(value in any::<Timestamp>()) {
+            round_trip(value)?;
+        }
+
+        #[test]
+        fn lines_round_trip(value in any::<InfluxLine>()) {
+            round_trip(value)?;
+        }
+    }
+}
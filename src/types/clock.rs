@@ -0,0 +1,112 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::Timestamp;
+
+/// A source of the current time, so that time-dependent code can be tested
+/// with a [`ManualClock`] instead of the [`SystemClock`].
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> Timestamp;
+}
+
+/// Reads the system clock, saturating outside of the nanosecond range,
+/// i.e. before 1677 or after 2262.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+/// A clock that stands still until it is set or advanced explicitly.
+///
+/// Clones share the same time, so one can be handed out while the other is moved.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use influx_line::*;
+///
+/// let clock = ManualClock::new(Timestamp::from(1704067200000000000_i64));
+/// let handed_out = clock.clone();
+///
+/// clock.advance(Duration::from_secs(60));
+///
+/// assert_eq!(Timestamp::from(1704067260000000000_i64), handed_out.now());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Arc<AtomicI64>,
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        let nanoseconds = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(since_epoch) => i64::try_from(since_epoch.as_nanos()).unwrap_or(i64::MAX),
+            Err(error) => i64::try_from(error.duration().as_nanos()).map_or(i64::MIN, |n| -n),
+        };
+        Timestamp::from(nanoseconds)
+    }
+}
+
+impl ManualClock {
+    /// Creates a clock showing a given time.
+    pub fn new(now: Timestamp) -> Self {
+        Self {
+            now: Arc::new(AtomicI64::new(now.into())),
+        }
+    }
+
+    /// Sets the time.
+    pub fn set(&self, now: Timestamp) {
+        self.now.store(now.into(), Ordering::SeqCst);
+    }
+
+    /// Moves the time forward, saturating at the end of the nanosecond range.
+    pub fn advance(&self, duration: Duration) {
+        let nanoseconds = i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX);
+        // The closure always returns a value, so the update cannot fail.
+        let _ = self
+            .now
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |now| {
+                Some(now.saturating_add(nanoseconds))
+            });
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::from(self.now.load(Ordering::SeqCst))
+    }
+}
+
+impl<C> Clock for Arc<C>
+where
+    C: Clock + ?Sized,
+{
+    fn now(&self) -> Timestamp {
+        (**self).now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{Clock, ManualClock, SystemClock, Timestamp};
+
+    #[test]
+    fn system_clock_is_recent() {
+        let after_2024 = Timestamp::from(1704067200000000000_i64);
+
+        assert!(SystemClock.now() > after_2024);
+    }
+
+    #[test]
+    fn manual_clock_saturates() {
+        let clock = ManualClock::new(Timestamp::from(i64::MAX - 1));
+
+        clock.advance(Duration::from_secs(1));
+
+        assert_eq!(Timestamp::from(i64::MAX), clock.now());
+    }
+}
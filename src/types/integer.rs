@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use core::str::FromStr;
 
 use crate::InfluxLineError;
 
@@ -159,7 +159,7 @@ impl TryFrom<InfluxUInteger> for u8 {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use core::str::FromStr;
 
     use super::{InfluxInteger, InfluxUInteger};
 
@@ -185,8 +185,8 @@ mod tests {
     }
 
     #[rstest::rstest]
-    #[case("123u", (123 as u32).into())]
-    #[case("0u", (0 as u32).into())]
+    #[case("123u", 123_u32.into())]
+    #[case("0u", 0_u32.into())]
     fn successful_uint_parsing(#[case] input: &str, #[case] expected_integer: InfluxUInteger) {
         let actual_integer = InfluxUInteger::from_str(input).expect("Must parse here");
 
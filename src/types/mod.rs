@@ -1,5 +1,8 @@
 pub mod boolean;
+#[cfg(feature = "std")]
+pub mod clock;
 pub mod integer;
 pub mod string;
 pub mod timestamp;
+#[cfg(feature = "alloc")]
 pub mod value;
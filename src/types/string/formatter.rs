@@ -1,6 +1,16 @@
+/// Special symbols of Tag and Field keys and of Tag values.
+///
+/// Two contiguous backslashes are read as one, so a backslash is escaped as well.
+pub(crate) const KEY_SPECIAL_CHARACTERS: [char; 4] = [',', '=', ' ', '\\'];
+/// Special symbols of measurements, see [`KEY_SPECIAL_CHARACTERS`].
+pub(crate) const MEASUREMENT_SPECIAL_CHARACTERS: [char; 3] = [',', ' ', '\\'];
+/// Special symbols inside double-quoted String Field values.
+pub(crate) const QUOTED_SPECIAL_CHARACTERS: [char; 2] = ['"', '\\'];
+pub(crate) const ESCAPE_CHARACTER: char = '\\';
+
 /// Processes a string char-by-char and escapes all special symbols.
 #[derive(Debug)]
-pub(super) struct LinearFormatter<'a> {
+pub(crate) struct LinearFormatter<'a> {
     special_characters: &'a [char],
     escape_character: &'a char,
 }
@@ -1,8 +1,9 @@
-use std::fmt::Display;
-use std::str::FromStr;
+use alloc::string::String;
+use core::fmt::Display;
+use core::str::FromStr;
 
 use crate::InfluxLineError;
-use crate::types::string::formatter::LinearFormatter;
+use crate::types::string::{ESCAPE_CHARACTER, KEY_SPECIAL_CHARACTERS, LinearFormatter, check_name};
 
 use super::parser::{LinearParser, StrayEscapes};
 
@@ -79,17 +80,14 @@ use super::parser::{LinearParser, StrayEscapes};
 pub struct KeyName(String);
 
 impl KeyName {
-    const SPECIAL_CHARACTERS: [char; 3] = [',', '=', ' '];
-    const ESCAPE_CHARACTER: char = '\\';
+    const SPECIAL_CHARACTERS: [char; 4] = KEY_SPECIAL_CHARACTERS;
+    const ESCAPE_CHARACTER: char = ESCAPE_CHARACTER;
 
     pub fn new<S>(name: S) -> Result<Self, InfluxLineError>
     where
         S: AsRef<str> + Into<String>,
     {
-        if name.as_ref().is_empty() || name.as_ref().starts_with('_') {
-            return Err(InfluxLineError::NameRestriction);
-        }
-
+        check_name(name.as_ref())?;
         Ok(Self(name.into()))
     }
 }
@@ -135,7 +133,7 @@ impl FromStr for KeyName {
 }
 
 impl Display for KeyName {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let formatter = LinearFormatter::new(&Self::SPECIAL_CHARACTERS, &Self::ESCAPE_CHARACTER);
         write!(f, "{}", formatter.chars(self).collect::<String>())
     }
@@ -143,7 +141,7 @@ impl Display for KeyName {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use core::str::FromStr;
 
     use super::KeyName;
 
@@ -165,7 +163,7 @@ mod tests {
     fn successful_parsing(#[case] escaped_input: &str, #[case] expected_raw: &str) {
         let expected_name = KeyName::new(expected_raw).expect("Must be a valid name");
 
-        let actual_name = KeyName::from_str(&escaped_input).expect("Must parse here");
+        let actual_name = KeyName::from_str(escaped_input).expect("Must parse here");
 
         assert_eq!(expected_name, actual_name);
     }
@@ -184,7 +182,9 @@ mod tests {
     #[rstest::rstest]
     #[case::with_space(r#"john cena"#, r#"john\ cena"#)]
     #[case::with_comma(r#"you,me"#, r#"you\,me"#)]
-    #[case::silly_escapes_combination(r#"a\ b"#, r#"a\\ b"#)]
+    #[case::silly_escapes_combination(r#"a\ b"#, r#"a\\\ b"#)]
+    #[case::backslash(r#"a\b"#, r#"a\\b"#)]
+    #[case::trailing_backslash(r#"a\"#, r#"a\\"#)]
     fn display(#[case] input: &str, #[case] expected_string: &str) {
         let name = KeyName::new(input).expect("Must be a valid name");
 
@@ -1,8 +1,11 @@
-use std::fmt::Display;
-use std::str::FromStr;
+use alloc::string::String;
+use core::fmt::Display;
+use core::str::FromStr;
 
 use crate::InfluxLineError;
-use crate::types::string::formatter::LinearFormatter;
+use crate::types::string::{
+    ESCAPE_CHARACTER, LinearFormatter, MEASUREMENT_SPECIAL_CHARACTERS, check_name,
+};
 
 use super::parser::{LinearParser, StrayEscapes};
 
@@ -80,17 +83,14 @@ use super::parser::{LinearParser, StrayEscapes};
 pub struct MeasurementName(String);
 
 impl MeasurementName {
-    const SPECIAL_CHARACTERS: [char; 2] = [',', ' '];
-    const ESCAPE_CHARACTER: char = '\\';
+    const SPECIAL_CHARACTERS: [char; 3] = MEASUREMENT_SPECIAL_CHARACTERS;
+    const ESCAPE_CHARACTER: char = ESCAPE_CHARACTER;
 
     pub fn new<S>(name: S) -> Result<Self, InfluxLineError>
     where
         S: AsRef<str> + Into<String>,
     {
-        if name.as_ref().is_empty() || name.as_ref().starts_with('_') {
-            return Err(InfluxLineError::NameRestriction);
-        }
-
+        check_name(name.as_ref())?;
         Ok(Self(name.into()))
     }
 }
@@ -136,7 +136,7 @@ impl FromStr for MeasurementName {
 }
 
 impl Display for MeasurementName {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let formatter = LinearFormatter::new(&Self::SPECIAL_CHARACTERS, &Self::ESCAPE_CHARACTER);
         write!(f, "{}", formatter.chars(self).collect::<String>())
     }
@@ -144,7 +144,7 @@ impl Display for MeasurementName {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use core::str::FromStr;
 
     use crate::MeasurementName;
 
@@ -167,7 +167,7 @@ mod tests {
     fn successful_parsing(#[case] escaped_input: &str, #[case] expected_raw: &str) {
         let expected_name = MeasurementName::new(expected_raw).expect("Must be a valid name");
 
-        let actual_name = MeasurementName::from_str(&escaped_input).expect("Must parse here");
+        let actual_name = MeasurementName::from_str(escaped_input).expect("Must parse here");
 
         assert_eq!(expected_name, actual_name);
     }
@@ -185,7 +185,9 @@ mod tests {
     #[rstest::rstest]
     #[case::with_space(r#"john cena"#, r#"john\ cena"#)]
     #[case::with_comma(r#"you,me"#, r#"you\,me"#)]
-    #[case::silly_escapes_combination(r#"a\ b"#, r#"a\\ b"#)]
+    #[case::silly_escapes_combination(r#"a\ b"#, r#"a\\\ b"#)]
+    #[case::backslash(r#"a\b"#, r#"a\\b"#)]
+    #[case::trailing_backslash(r#"a\"#, r#"a\\"#)]
     fn display(#[case] input: &str, #[case] expected_string: &str) {
         let name = MeasurementName::new(input).expect("Must be a valid name");
 
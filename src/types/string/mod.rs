@@ -1,9 +1,32 @@
 mod formatter;
+#[cfg(feature = "alloc")]
 mod key;
+#[cfg(feature = "alloc")]
 mod measurement;
+#[cfg(feature = "alloc")]
 mod parser;
+#[cfg(feature = "alloc")]
 mod quoted;
 
+use crate::InfluxLineError;
+
+pub(crate) use self::formatter::{
+    ESCAPE_CHARACTER, KEY_SPECIAL_CHARACTERS, LinearFormatter, MEASUREMENT_SPECIAL_CHARACTERS,
+    QUOTED_SPECIAL_CHARACTERS,
+};
+#[cfg(feature = "alloc")]
 pub use self::key::KeyName;
+#[cfg(feature = "alloc")]
 pub use self::measurement::MeasurementName;
+#[cfg(feature = "alloc")]
 pub use self::quoted::QuotedString;
+
+/// Checks the [Naming restrictions](
+/// https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/#naming-restrictions
+/// ) shared by measurements, keys and tag values.
+pub(crate) fn check_name(name: &str) -> Result<(), InfluxLineError> {
+    if name.is_empty() || name.starts_with('_') {
+        return Err(InfluxLineError::NameRestriction);
+    }
+    Ok(())
+}
@@ -1,3 +1,6 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use crate::InfluxLineError;
 
 /// Consumes a string char-by-char and handles all escape symbols,
@@ -1,8 +1,9 @@
-use std::fmt::Display;
-use std::str::FromStr;
+use alloc::string::String;
+use core::fmt::Display;
+use core::str::FromStr;
 
 use crate::InfluxLineError;
-use crate::types::string::formatter::LinearFormatter;
+use crate::types::string::{ESCAPE_CHARACTER, LinearFormatter, QUOTED_SPECIAL_CHARACTERS};
 
 use super::parser::{LinearParser, StrayEscapes};
 
@@ -32,8 +33,8 @@ use super::parser::{LinearParser, StrayEscapes};
 pub struct QuotedString(String);
 
 impl QuotedString {
-    const SPECIAL_CHARACTERS: [char; 2] = ['"', '\\'];
-    const ESCAPE_CHARACTER: char = '\\';
+    const SPECIAL_CHARACTERS: [char; 2] = QUOTED_SPECIAL_CHARACTERS;
+    const ESCAPE_CHARACTER: char = ESCAPE_CHARACTER;
 
     /// Creates a Quoted String from a raw value
     ///
@@ -94,9 +95,9 @@ impl FromStr for QuotedString {
             StrayEscapes::Forbid,
         );
 
-        s.chars()
-            .skip(1)
-            .take(s.len() - 2)
+        // Both quotes are single bytes.
+        s[1..s.len() - 1]
+            .chars()
             .try_for_each(|character| parser.process_char(character))?;
 
         let name = Self::from(parser.extract()?);
@@ -105,7 +106,7 @@ impl FromStr for QuotedString {
 }
 
 impl Display for QuotedString {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let formatter = LinearFormatter::new(&Self::SPECIAL_CHARACTERS, &Self::ESCAPE_CHARACTER);
         write!(f, "\"{}\"", formatter.chars(self).collect::<String>())
     }
@@ -113,7 +114,7 @@ impl Display for QuotedString {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use core::str::FromStr;
 
     use crate::QuotedString;
 
@@ -121,6 +122,8 @@ mod tests {
     #[case::empty_string("\"\"", "")]
     #[case::quotes("\"\\\"string\\\" within a string\"", "\"string\" within a string")]
     #[case::backslash("\"slash \\\\ escaped\"", "slash \\ escaped")]
+    #[case::multi_byte("\"é\"", "é")]
+    #[case::multi_byte_escapes("\"🦀 \\\\ ß\\\"\"", "🦀 \\ ß\"")]
     fn successful_parsing(#[case] escaped_input: &str, #[case] expected_value: &str) {
         let expected_string = QuotedString::new(expected_value);
 
@@ -1,8 +1,14 @@
-use std::str::FromStr;
+use core::str::FromStr;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
 
+#[cfg(feature = "chrono")]
 use chrono::{DateTime, Utc};
 
 use crate::InfluxLineError;
+#[cfg(feature = "std")]
+use crate::{Clock, SystemClock};
 
 /// Represents a Timestamp (in nanoseconds) at the end of the Line Protocol.
 #[derive(
@@ -21,12 +27,189 @@ use crate::InfluxLineError;
 #[from(u8, u16, u32, i8, i16, i32, i64)]
 pub struct Timestamp(i64);
 
+/// Units of a raw timestamp in Line Protocol.
+///
+/// [`Timestamp`] always holds nanoseconds,
+/// but writers and servers commonly agree on coarser precisions to save bytes.
+///
+/// [`std::fmt::Display`] uses the short names that [`FromStr`] accepts, e.g. `ms`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, derive_more::Display,
+)]
+pub enum Precision {
+    #[default]
+    #[display("ns")]
+    Nanoseconds,
+    #[display("us")]
+    Microseconds,
+    #[display("ms")]
+    Milliseconds,
+    #[display("s")]
+    Seconds,
+}
+
+impl Precision {
+    /// Returns the number of nanoseconds in a single unit.
+    pub fn nanoseconds(&self) -> i64 {
+        match self {
+            Precision::Nanoseconds => 1,
+            Precision::Microseconds => 1_000,
+            Precision::Milliseconds => 1_000_000,
+            Precision::Seconds => 1_000_000_000,
+        }
+    }
+}
+
+impl Timestamp {
+    /// Converts a raw timestamp of a given precision to nanoseconds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influx_line::*;
+    ///
+    /// let timestamp = Timestamp::from_precision(1704067200, Precision::Seconds).unwrap();
+    ///
+    /// assert_eq!(Timestamp::from(1704067200000000000_i64), timestamp);
+    /// assert_eq!(1704067200000, timestamp.to_precision(Precision::Milliseconds));
+    /// ```
+    pub fn from_precision(value: i64, precision: Precision) -> Result<Self, InfluxLineError> {
+        value
+            .checked_mul(precision.nanoseconds())
+            .map(Self)
+            .ok_or(InfluxLineError::TimestampOutOfRange)
+    }
+
+    /// Reads the [`SystemClock`].
+    ///
+    /// Code that needs to be tested at a fixed time should take a [`Clock`] instead.
+    #[cfg(feature = "std")]
+    pub fn now() -> Self {
+        SystemClock.now()
+    }
+
+    /// Adds a duration, returning `None` if the result does not fit into the nanosecond range.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use influx_line::*;
+    ///
+    /// let timestamp = Timestamp::from(1704067200000000000_i64);
+    ///
+    /// assert_eq!(
+    ///     Some(Timestamp::from(1704067201500000000_i64)),
+    ///     timestamp.checked_add(Duration::from_millis(1500))
+    /// );
+    /// assert_eq!(None, timestamp.checked_add(Duration::from_secs(300 * 365 * 24 * 60 * 60)));
+    /// ```
+    pub fn checked_add(self, duration: Duration) -> Option<Self> {
+        let nanoseconds = i64::try_from(duration.as_nanos()).ok()?;
+        self.0.checked_add(nanoseconds).map(Self)
+    }
+
+    /// Subtracts a duration, returning `None` if the result does not fit into the nanosecond range.
+    pub fn checked_sub(self, duration: Duration) -> Option<Self> {
+        let nanoseconds = i64::try_from(duration.as_nanos()).ok()?;
+        self.0.checked_sub(nanoseconds).map(Self)
+    }
+
+    /// Returns the time passed since an earlier timestamp,
+    /// or `None` if it is actually later.
+    pub fn duration_since(self, earlier: Timestamp) -> Option<Duration> {
+        let nanoseconds = self.0.checked_sub(earlier.0)?;
+        u64::try_from(nanoseconds).ok().map(Duration::from_nanos)
+    }
+
+    /// Rounds down to a multiple of the interval since the Unix epoch,
+    /// e.g. to the start of its minute, also for timestamps before the epoch.
+    ///
+    /// A zero interval leaves the timestamp unchanged,
+    /// and so does rounding down beyond the nanosecond range.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use influx_line::*;
+    ///
+    /// let timestamp = Timestamp::from(1704067245000000000_i64);
+    ///
+    /// assert_eq!(
+    ///     Timestamp::from(1704067200000000000_i64),
+    ///     timestamp.truncate(Duration::from_secs(60))
+    /// );
+    /// assert_eq!(
+    ///     Timestamp::from(1704067260000000000_i64),
+    ///     timestamp.round(Duration::from_secs(60))
+    /// );
+    /// ```
+    pub fn truncate(self, interval: Duration) -> Self {
+        match Self::interval(interval) {
+            Some(interval) => self
+                .0
+                .checked_sub(self.0.rem_euclid(interval))
+                .map_or(self, Self),
+            None => self,
+        }
+    }
+
+    /// Rounds to the nearest multiple of the interval since the Unix epoch, with halves up.
+    ///
+    /// A zero interval leaves the timestamp unchanged,
+    /// and so does rounding up beyond the nanosecond range.
+    pub fn round(self, interval: Duration) -> Self {
+        let Some(interval) = Self::interval(interval) else {
+            return self;
+        };
+        let remainder = self.0.rem_euclid(interval);
+        let rounded = if remainder >= interval - remainder {
+            self.0.checked_add(interval - remainder)
+        } else {
+            self.0.checked_sub(remainder)
+        };
+        rounded.map_or(self, Self)
+    }
+
+    /// Converts a bucketing interval to nanoseconds, saturating as no timestamp is further apart.
+    fn interval(interval: Duration) -> Option<i64> {
+        match interval.as_nanos() {
+            0 => None,
+            nanoseconds => Some(i64::try_from(nanoseconds).unwrap_or(i64::MAX)),
+        }
+    }
+
+    /// Converts the timestamp to a raw value of a given precision,
+    /// rounding towards negative infinity.
+    pub fn to_precision(self, precision: Precision) -> i64 {
+        self.0.div_euclid(precision.nanoseconds())
+    }
+}
+
+/// Accepts both InfluxDB v1 and v2 names, e.g. `u` and `us` for microseconds.
+impl FromStr for Precision {
+    type Err = InfluxLineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ns" | "n" | "nanosecond" => Ok(Precision::Nanoseconds),
+            "us" | "u" | "µs" | "microsecond" => Ok(Precision::Microseconds),
+            "ms" | "millisecond" => Ok(Precision::Milliseconds),
+            "s" | "second" => Ok(Precision::Seconds),
+            _ => Err(InfluxLineError::BadPrecision),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
 impl From<Timestamp> for DateTime<Utc> {
     fn from(value: Timestamp) -> Self {
         DateTime::from_timestamp_nanos(value.into()).to_utc()
     }
 }
 
+#[cfg(feature = "chrono")]
 impl TryFrom<DateTime<Utc>> for Timestamp {
     type Error = InfluxLineError;
 
@@ -38,6 +221,92 @@ impl TryFrom<DateTime<Utc>> for Timestamp {
     }
 }
 
+impl TryFrom<u64> for Timestamp {
+    type Error = InfluxLineError;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        i64::try_from(value)
+            .map(Self)
+            .map_err(|_| InfluxLineError::TimestampOutOfRange)
+    }
+}
+
+/// Treats the duration as the time since the Unix epoch.
+impl TryFrom<Duration> for Timestamp {
+    type Error = InfluxLineError;
+
+    fn try_from(value: Duration) -> Result<Self, Self::Error> {
+        i64::try_from(value.as_nanos())
+            .map(Self)
+            .map_err(|_| InfluxLineError::TimestampOutOfRange)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<SystemTime> for Timestamp {
+    type Error = InfluxLineError;
+
+    fn try_from(value: SystemTime) -> Result<Self, Self::Error> {
+        let nanoseconds = match value.duration_since(UNIX_EPOCH) {
+            Ok(since_epoch) => i64::try_from(since_epoch.as_nanos()),
+            Err(error) => i64::try_from(error.duration().as_nanos()).map(|n| -n),
+        };
+        nanoseconds
+            .map(Self)
+            .map_err(|_| InfluxLineError::TimestampOutOfRange)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Timestamp> for SystemTime {
+    fn from(value: Timestamp) -> Self {
+        let since_epoch = Duration::from_nanos(value.0.unsigned_abs());
+        if value.0 < 0 {
+            UNIX_EPOCH - since_epoch
+        } else {
+            UNIX_EPOCH + since_epoch
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<Timestamp> for time::OffsetDateTime {
+    fn from(value: Timestamp) -> Self {
+        time::OffsetDateTime::from_unix_timestamp_nanos(value.0.into())
+            .expect("Nanosecond timestamps are within the supported years")
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<time::OffsetDateTime> for Timestamp {
+    type Error = InfluxLineError;
+
+    fn try_from(value: time::OffsetDateTime) -> Result<Self, Self::Error> {
+        i64::try_from(value.unix_timestamp_nanos())
+            .map(Self)
+            .map_err(|_| InfluxLineError::DateTimeOutOfRange)
+    }
+}
+
+#[cfg(feature = "jiff")]
+impl From<Timestamp> for jiff::Timestamp {
+    fn from(value: Timestamp) -> Self {
+        jiff::Timestamp::from_nanosecond(value.0.into())
+            .expect("Nanosecond timestamps are within the supported years")
+    }
+}
+
+#[cfg(feature = "jiff")]
+impl TryFrom<jiff::Timestamp> for Timestamp {
+    type Error = InfluxLineError;
+
+    fn try_from(value: jiff::Timestamp) -> Result<Self, Self::Error> {
+        i64::try_from(value.as_nanosecond())
+            .map(Self)
+            .map_err(|_| InfluxLineError::DateTimeOutOfRange)
+    }
+}
+
 impl FromStr for Timestamp {
     type Err = InfluxLineError;
 
@@ -51,9 +320,12 @@ impl FromStr for Timestamp {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use core::str::FromStr;
+    use core::time::Duration;
+    #[cfg(feature = "std")]
+    use std::time::SystemTime;
 
-    use crate::Timestamp;
+    use crate::{Precision, Timestamp};
 
     #[rstest::rstest]
     #[case::big_timestamp("1556813561098000000", 1556813561098000000)]
@@ -82,4 +354,173 @@ mod tests {
 
         assert_eq!(expected_string, actual_string);
     }
+
+    #[rstest::rstest]
+    #[case::nanoseconds(1556813561098000123, "ns", 1556813561098000123)]
+    #[case::microseconds(1556813561098000, "u", 1556813561098000000)]
+    #[case::milliseconds(1556813561098, "ms", 1556813561098000000)]
+    #[case::seconds(-1556813561, "s", -1556813561000000000)]
+    fn from_precision(#[case] value: i64, #[case] precision: &str, #[case] expected: i64) {
+        let precision = Precision::from_str(precision).expect("Must parse here");
+
+        let actual = Timestamp::from_precision(value, precision).expect("Must fit here");
+
+        assert_eq!(Timestamp::from(expected), actual);
+    }
+
+    #[rstest::rstest]
+    #[case::positive(1556813561098765432, Precision::Milliseconds, 1556813561098)]
+    #[case::negative(-1, Precision::Seconds, -1)]
+    fn to_precision(#[case] value: i64, #[case] precision: Precision, #[case] expected: i64) {
+        assert_eq!(expected, Timestamp::from(value).to_precision(precision));
+    }
+
+    #[test]
+    fn precision_overflow() {
+        let _error = Timestamp::from_precision(i64::MAX, Precision::Seconds).unwrap_err();
+    }
+
+    #[rstest::rstest]
+    #[case::positive(1704067245, 60, 1704067200, 1704067260)]
+    #[case::exact(1704067200, 60, 1704067200, 1704067200)]
+    #[case::below_half(1704067229, 60, 1704067200, 1704067200)]
+    #[case::half(1704067230, 60, 1704067200, 1704067260)]
+    #[case::negative(-45, 60, -60, -60)]
+    #[case::negative_below_half(-31, 60, -60, -60)]
+    #[case::negative_half(-30, 60, -60, 0)]
+    #[case::zero_interval(15, 0, 15, 15)]
+    fn bucketing(
+        #[case] seconds: i64,
+        #[case] interval: u64,
+        #[case] truncated: i64,
+        #[case] rounded: i64,
+    ) {
+        let timestamp = Timestamp::from_precision(seconds, Precision::Seconds).unwrap();
+        let interval = Duration::from_secs(interval);
+
+        assert_eq!(
+            Timestamp::from_precision(truncated, Precision::Seconds).unwrap(),
+            timestamp.truncate(interval)
+        );
+        assert_eq!(
+            Timestamp::from_precision(rounded, Precision::Seconds).unwrap(),
+            timestamp.round(interval)
+        );
+    }
+
+    #[test]
+    fn bucketing_near_limits() {
+        let max = Timestamp::from(i64::MAX);
+        let min = Timestamp::from(i64::MIN);
+        let hour = Duration::from_secs(60 * 60);
+
+        assert_eq!(max, max.round(hour));
+        assert_eq!(min, min.truncate(Duration::MAX));
+        assert_eq!(
+            Timestamp::from(0),
+            Timestamp::from(5).truncate(Duration::MAX)
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::add_overflow(i64::MAX, 1, true, None)]
+    #[case::sub_overflow(i64::MIN, 1, false, None)]
+    #[case::huge_duration(0, u64::MAX, true, None)]
+    #[case::add(10, 5, true, Some(15))]
+    #[case::sub(10, 15, false, Some(-5))]
+    fn checked_arithmetic(
+        #[case] value: i64,
+        #[case] nanoseconds: u64,
+        #[case] add: bool,
+        #[case] expected: Option<i64>,
+    ) {
+        let timestamp = Timestamp::from(value);
+        let duration = Duration::from_nanos(nanoseconds);
+
+        let actual = if add {
+            timestamp.checked_add(duration)
+        } else {
+            timestamp.checked_sub(duration)
+        };
+
+        assert_eq!(expected.map(Timestamp::from), actual);
+    }
+
+    #[test]
+    fn duration_since() {
+        let earlier = Timestamp::from(-5);
+        let later = Timestamp::from(10);
+
+        assert_eq!(
+            Some(Duration::from_nanos(15)),
+            later.duration_since(earlier)
+        );
+        assert_eq!(None, earlier.duration_since(later));
+    }
+
+    #[cfg(feature = "std")]
+    #[rstest::rstest]
+    #[case::epoch(0)]
+    #[case::after_epoch(1704067200123456789)]
+    #[case::before_epoch(-1704067200123456789)]
+    fn system_time_round_trip(#[case] value: i64) {
+        let timestamp = Timestamp::from(value);
+
+        let system_time = SystemTime::from(timestamp);
+
+        assert_eq!(
+            Ok(timestamp),
+            Timestamp::try_from(system_time).map_err(|_| ())
+        );
+    }
+
+    #[test]
+    fn unsigned_conversions() {
+        assert_eq!(
+            Timestamp::from(15),
+            Timestamp::try_from(15_u64).expect("Must fit here")
+        );
+        assert!(Timestamp::try_from(u64::MAX).is_err());
+        assert_eq!(
+            Timestamp::from(1_500_000_000),
+            Timestamp::try_from(Duration::from_millis(1500)).expect("Must fit here")
+        );
+        assert!(Timestamp::try_from(Duration::MAX).is_err());
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_round_trip() {
+        let timestamp = Timestamp::from(-1704067200123456789_i64);
+
+        let date_time = time::OffsetDateTime::from(timestamp);
+
+        assert_eq!(1916, date_time.year());
+        assert_eq!(
+            timestamp,
+            Timestamp::try_from(date_time).expect("Must fit here")
+        );
+        assert!(
+            Timestamp::try_from(time::OffsetDateTime::new_utc(
+                time::Date::from_calendar_date(2300, time::Month::January, 1).unwrap(),
+                time::Time::MIDNIGHT
+            ))
+            .is_err()
+        );
+    }
+
+    #[cfg(feature = "jiff")]
+    #[test]
+    fn jiff_round_trip() {
+        let timestamp = Timestamp::from(1704067200123456789_i64);
+
+        let jiff_timestamp = jiff::Timestamp::from(timestamp);
+
+        assert_eq!("2024-01-01T00:00:00.123456789Z", jiff_timestamp.to_string());
+        assert_eq!(
+            timestamp,
+            Timestamp::try_from(jiff_timestamp).expect("Must fit here")
+        );
+        assert!(Timestamp::try_from(jiff::Timestamp::MAX).is_err());
+    }
 }
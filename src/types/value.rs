@@ -1,4 +1,5 @@
-use std::str::FromStr;
+use alloc::string::String;
+use core::str::FromStr;
 
 use crate::{Boolean, InfluxInteger, InfluxLineError, InfluxUInteger, QuotedString};
 
@@ -19,12 +20,69 @@ pub enum InfluxValue {
     String(QuotedString),
 }
 
+/// Describes which variant an [`InfluxValue`] holds, without the value itself.
+///
+/// InfluxDB fixes the type of a field on its first write,
+/// so this is what matters when comparing fields across lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum InfluxValueType {
+    #[display("float")]
+    Float,
+    #[display("integer")]
+    Integer,
+    #[display("unsigned")]
+    UInteger,
+    #[display("boolean")]
+    Boolean,
+    #[display("string")]
+    String,
+}
+
+impl InfluxValue {
+    /// Returns the type of the value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use influx_line::*;
+    ///
+    /// assert_eq!(InfluxValue::from(1.5).value_type(), InfluxValueType::Float);
+    /// assert_eq!(InfluxValue::from(15).value_type(), InfluxValueType::Integer);
+    /// assert_eq!(InfluxValue::from("ok").value_type(), InfluxValueType::String);
+    /// ```
+    pub fn value_type(&self) -> InfluxValueType {
+        match self {
+            InfluxValue::Float(_) => InfluxValueType::Float,
+            InfluxValue::Integer(_) => InfluxValueType::Integer,
+            InfluxValue::UInteger(_) => InfluxValueType::UInteger,
+            InfluxValue::Boolean(_) => InfluxValueType::Boolean,
+            InfluxValue::String(_) => InfluxValueType::String,
+        }
+    }
+}
+
 impl From<&str> for InfluxValue {
     fn from(value: &str) -> Self {
         Self::String(value.into())
     }
 }
 
+/// Parses the type names used by InfluxDB, e.g. in explicit bucket schemas.
+impl FromStr for InfluxValueType {
+    type Err = InfluxLineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "float" => Ok(Self::Float),
+            "integer" => Ok(Self::Integer),
+            "unsigned" => Ok(Self::UInteger),
+            "boolean" => Ok(Self::Boolean),
+            "string" => Ok(Self::String),
+            _ => Err(InfluxLineError::BadValueType),
+        }
+    }
+}
+
 impl FromStr for InfluxValue {
     type Err = InfluxLineError;
 
@@ -176,9 +234,9 @@ impl TryFrom<InfluxValue> for String {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use core::str::FromStr;
 
-    use crate::InfluxValue;
+    use crate::{InfluxValue, InfluxValueType};
 
     #[rstest::rstest]
     #[case::sane_float("12.33", InfluxValue::Float(12.33))]
@@ -186,7 +244,7 @@ mod tests {
     #[case::negative_with_scientific_stuff("-1.234456e+78", InfluxValue::Float(-1.234456e+78))]
     #[case::positive_int("125i", InfluxValue::Integer(125.into()))]
     #[case::negative_int("-25565i", InfluxValue::Integer((-25565).into()))]
-    #[case::uint("999999999u", InfluxValue::UInteger((999999999 as u32).into()))]
+    #[case::uint("999999999u", InfluxValue::UInteger(999999999_u32.into()))]
     #[case::le_true("true", InfluxValue::Boolean(true.into()))]
     #[case::le_false("FALSE", InfluxValue::Boolean(false.into()))]
     #[case::string("\"Dunno what to say\"", InfluxValue::String("Dunno what to say".into()))]
@@ -198,9 +256,9 @@ mod tests {
 
     #[rstest::rstest]
     #[case::sane_float(17.0, "17.0")]
-    #[case::float_strange(25 as f32, "25.0")]
+    #[case::float_strange(25_f32, "25.0")]
     #[case::int(15, "15i")]
-    #[case::uint(0 as u32, "0u")]
+    #[case::uint(0_u32, "0u")]
     #[case::le_true(true, "true")]
     #[case::le_false(false, "false")]
     #[case::string("eat \"this\" hehe", "\"eat \\\"this\\\" hehe\"")]
@@ -212,4 +270,18 @@ mod tests {
 
         assert_eq!(expected_string, actual_string);
     }
+
+    #[rstest::rstest]
+    #[case::float(InfluxValueType::Float, "float")]
+    #[case::integer(InfluxValueType::Integer, "integer")]
+    #[case::unsigned(InfluxValueType::UInteger, "unsigned")]
+    #[case::boolean(InfluxValueType::Boolean, "boolean")]
+    #[case::string(InfluxValueType::String, "string")]
+    fn value_type_names(#[case] value_type: InfluxValueType, #[case] name: &str) {
+        assert_eq!(name, value_type.to_string());
+        assert_eq!(
+            value_type,
+            InfluxValueType::from_str(name).expect("Must parse here")
+        );
+    }
 }
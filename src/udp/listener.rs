@@ -0,0 +1,138 @@
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use crate::{InfluxLine, InfluxLineError, Precision};
+
+/// Receives Lines over UDP, the way InfluxDB's UDP service
+/// and Telegraf's `socket_listener` do.
+///
+/// Every datagram is parsed on its own,
+/// so Lines must not be split across datagrams, see [`super::UdpSink`].
+#[derive(Debug)]
+pub struct UdpListener {
+    socket: UdpSocket,
+    precision: Precision,
+    buffer: Vec<u8>,
+}
+
+/// Lines parsed from a single datagram.
+///
+/// Malformed Lines do not spoil the whole datagram:
+/// they are collected along with their errors instead.
+#[derive(Debug)]
+pub struct UdpDatagram {
+    source: SocketAddr,
+    lines: Vec<InfluxLine>,
+    errors: Vec<(String, InfluxLineError)>,
+}
+
+impl UdpListener {
+    /// The largest possible UDP payload, so that datagrams are never truncated.
+    const MAX_DATAGRAM_SIZE: usize = 65_535;
+
+    pub fn bind<A>(address: A) -> Result<Self, InfluxLineError>
+    where
+        A: ToSocketAddrs,
+    {
+        Ok(Self {
+            socket: UdpSocket::bind(address)?,
+            precision: Precision::Nanoseconds,
+            buffer: vec![0; Self::MAX_DATAGRAM_SIZE],
+        })
+    }
+
+    /// Interprets raw timestamps in a given precision, nanoseconds by default.
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, InfluxLineError> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Makes [`Self::recv`] fail after waiting for a datagram for too long.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), InfluxLineError> {
+        Ok(self.socket.set_read_timeout(timeout)?)
+    }
+
+    /// Waits for the next datagram and parses it.
+    ///
+    /// Fails on socket errors only.
+    pub fn recv(&mut self) -> Result<UdpDatagram, InfluxLineError> {
+        let (size, source) = self.socket.recv_from(&mut self.buffer)?;
+
+        let mut datagram = UdpDatagram {
+            source,
+            lines: Vec::new(),
+            errors: Vec::new(),
+        };
+        for raw in String::from_utf8_lossy(&self.buffer[..size]).split('\n') {
+            let raw = raw.trim();
+            if raw.is_empty() || raw.starts_with('#') {
+                continue;
+            }
+            match InfluxLine::parse_with_precision(raw, self.precision) {
+                Ok(line) => datagram.lines.push(line),
+                Err(error) => datagram.errors.push((raw.to_owned(), error)),
+            }
+        }
+        Ok(datagram)
+    }
+}
+
+impl UdpDatagram {
+    /// The address of the sender.
+    pub fn source(&self) -> SocketAddr {
+        self.source
+    }
+
+    pub fn lines(&self) -> &[InfluxLine] {
+        &self.lines
+    }
+
+    pub fn into_lines(self) -> Vec<InfluxLine> {
+        self.lines
+    }
+
+    /// Malformed Lines along with the reasons they were rejected.
+    pub fn errors(&self) -> &[(String, InfluxLineError)] {
+        &self.errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+
+    use crate::{InfluxLine, Precision, UdpListener};
+
+    #[test]
+    fn parses_datagrams() {
+        let mut listener = UdpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .with_precision(Precision::Seconds);
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        socket
+            .send_to(
+                b"cpu usage=1 1\n# comment\ncpu usage=\r\nmem used=2i",
+                listener.local_addr().unwrap(),
+            )
+            .unwrap();
+        let datagram = listener.recv().unwrap();
+
+        assert_eq!(socket.local_addr().unwrap(), datagram.source());
+        assert_eq!(
+            vec![
+                InfluxLine::try_new("cpu", "usage", 1.0)
+                    .unwrap()
+                    .with_timestamp(1_000_000_000),
+                InfluxLine::try_new("mem", "used", 2).unwrap(),
+            ],
+            datagram.lines()
+        );
+        assert_eq!(1, datagram.errors().len());
+        assert_eq!("cpu usage=", datagram.errors()[0].0);
+    }
+}
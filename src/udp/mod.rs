@@ -0,0 +1,5 @@
+mod listener;
+mod sender;
+
+pub use self::listener::{UdpDatagram, UdpListener};
+pub use self::sender::UdpSink;
@@ -0,0 +1,182 @@
+use std::fmt::Write as _;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use crate::{InfluxLine, InfluxLineError};
+
+/// Sends Lines over UDP, packing as many of them into a datagram as fit.
+///
+/// Lines are newline-terminated and never split across datagrams,
+/// so every datagram can be parsed on its own by the receiver,
+/// e.g. InfluxDB's UDP service, Telegraf's `socket_listener` or [`super::UdpListener`].
+///
+/// Lines are buffered until the next one does not fit into the datagram anymore,
+/// so call [`Self::flush`] to send the remainder.
+/// Dropping the sink flushes it as well, ignoring errors.
+///
+/// # Examples
+///
+/// ```rust
+/// use influx_line::*;
+///
+/// let mut listener = UdpListener::bind("127.0.0.1:0").unwrap();
+/// let mut sink = UdpSink::connect(listener.local_addr().unwrap())
+///     .unwrap()
+///     .with_mtu(512);
+///
+/// sink.write(&InfluxLine::try_new("cpu", "usage", 0.5).unwrap()).unwrap();
+/// sink.flush().unwrap();
+///
+/// let datagram = listener.recv().unwrap();
+/// assert_eq!(1, datagram.lines().len());
+/// ```
+#[derive(Debug)]
+pub struct UdpSink {
+    socket: UdpSocket,
+    mtu: usize,
+    buffer: String,
+    encoded: String,
+}
+
+impl UdpSink {
+    /// Fits into the common Ethernet MTU of 1500 bytes
+    /// along with the 40 bytes of the IPv6 and 8 bytes of the UDP header,
+    /// leaving 20 bytes of headroom for IPv6 extension headers or tunnels.
+    const DEFAULT_MTU: usize = 1432;
+
+    /// Binds an ephemeral local socket and connects it to a remote address.
+    pub fn connect<A>(address: A) -> Result<Self, InfluxLineError>
+    where
+        A: ToSocketAddrs,
+    {
+        let remote = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or(InfluxLineError::BadUrl)?;
+        let local: SocketAddr = match remote {
+            SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+            SocketAddr::V6(_) => ([0_u16; 8], 0).into(),
+        };
+
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(remote)?;
+        Ok(Self {
+            socket,
+            mtu: Self::DEFAULT_MTU,
+            buffer: String::new(),
+            encoded: String::new(),
+        })
+    }
+
+    /// Limits the payload size of a single datagram, 1432 bytes by default.
+    pub fn with_mtu(mut self, mtu: usize) -> Self {
+        self.mtu = mtu;
+        self
+    }
+
+    /// Buffers a Line, sending the previously buffered ones
+    /// if the Line does not fit into the same datagram.
+    ///
+    /// A Line longer than the MTU on its own is not sent at all
+    /// and is reported with [`InfluxLineError::LineTooLarge`],
+    /// keeping the buffered Lines intact.
+    pub fn write(&mut self, line: &InfluxLine) -> Result<(), InfluxLineError> {
+        self.encoded.clear();
+        // Writing to a String never fails.
+        let _ = write!(self.encoded, "{}", line);
+        if !self.encoded.ends_with('\n') {
+            self.encoded.push('\n');
+        }
+
+        if self.encoded.len() > self.mtu {
+            return Err(InfluxLineError::LineTooLarge {
+                size: self.encoded.len(),
+                limit: self.mtu,
+            });
+        }
+        if self.buffer.len() + self.encoded.len() > self.mtu {
+            self.flush()?;
+        }
+
+        self.buffer.push_str(&self.encoded);
+        Ok(())
+    }
+
+    /// Sends the buffered Lines, if any.
+    pub fn flush(&mut self) -> Result<(), InfluxLineError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let result = self.socket.send(self.buffer.as_bytes());
+        self.buffer.clear();
+        result?;
+        Ok(())
+    }
+}
+
+impl Drop for UdpSink {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{InfluxLine, InfluxLineError, UdpListener, UdpSink};
+
+    fn line(index: u64) -> InfluxLine {
+        InfluxLine::try_new("cpu", "index", index).unwrap()
+    }
+
+    #[test]
+    fn packs_lines_within_mtu() {
+        let mut listener = UdpListener::bind("127.0.0.1:0").unwrap();
+        let mut sink = UdpSink::connect(listener.local_addr().unwrap())
+            .unwrap()
+            .with_mtu(30);
+
+        // Every Line takes 13 bytes with the newline, so only two fit into a datagram.
+        for index in 0..5 {
+            sink.write(&line(index)).unwrap();
+        }
+        drop(sink);
+
+        let mut sizes = Vec::new();
+        let mut lines = Vec::new();
+        for _ in 0..3 {
+            let datagram = listener.recv().unwrap();
+            sizes.push(datagram.lines().len());
+            lines.extend(datagram.into_lines());
+        }
+        assert_eq!(vec![2, 2, 1], sizes);
+        assert_eq!((0..5).map(line).collect::<Vec<_>>(), lines);
+    }
+
+    #[test]
+    fn reports_oversized_lines() {
+        let mut listener = UdpListener::bind("127.0.0.1:0").unwrap();
+        listener
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        let mut sink = UdpSink::connect(listener.local_addr().unwrap())
+            .unwrap()
+            .with_mtu(13);
+        let oversized = InfluxLine::try_new("cpu", "index", 1000).unwrap();
+
+        sink.write(&line(1)).unwrap();
+        let error = sink.write(&oversized).unwrap_err();
+        sink.flush().unwrap();
+
+        assert!(matches!(
+            error,
+            InfluxLineError::LineTooLarge {
+                size: 16,
+                limit: 13
+            }
+        ));
+        assert_eq!(vec![line(1)], listener.recv().unwrap().into_lines());
+        assert!(listener.recv().is_err());
+    }
+}